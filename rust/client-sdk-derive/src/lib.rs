@@ -0,0 +1,158 @@
+//! Derive macros for the Q-Distributed-Database client SDK
+//!
+//! Use these through the `derive` feature of `q-distributed-db-client`
+//! rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitInt, LitStr};
+
+/// Implements `FromRow` for a struct
+///
+/// Named fields are read from the column with the field's name and tuple
+/// fields from the column at their position. Field attributes:
+///
+/// - `#[row(rename = "column")]` reads the named column
+/// - `#[row(index = n)]` reads the column at position `n`
+/// - `#[row(default)]` uses `Default::default()` if the column is missing or NULL
+/// - `#[row(flatten)]` builds the field from the same row with its own `FromRow`
+#[proc_macro_derive(FromRow, attributes(row))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Column a field is read from
+enum Source {
+    Name(String),
+    Index(usize),
+    Flatten,
+}
+
+/// Parsed `#[row(...)]` options of a field
+#[derive(Default)]
+struct FieldOptions {
+    rename: Option<LitStr>,
+    index: Option<LitInt>,
+    default: bool,
+    flatten: bool,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "FromRow can only be derived for structs",
+        ));
+    };
+
+    let fields = match &data.fields {
+        Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+        Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+        Fields::Unit => Vec::new(),
+    };
+    let values = fields
+        .iter()
+        .enumerate()
+        .map(|(position, field)| {
+            let options = field_options(field)?;
+            let source = if options.flatten {
+                Source::Flatten
+            } else if let Some(index) = &options.index {
+                Source::Index(index.base10_parse()?)
+            } else if let Some(rename) = &options.rename {
+                Source::Name(rename.value())
+            } else if let Some(ident) = &field.ident {
+                Source::Name(ident.unraw().to_string())
+            } else {
+                Source::Index(position)
+            };
+            Ok(field_value(&field.ty, &source, options.default))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let construct = match &data.fields {
+        Fields::Named(_) => {
+            let names = fields.iter().map(|field| &field.ident);
+            quote!({ #(#names: #values,)* })
+        }
+        Fields::Unnamed(_) => quote!(( #(#values,)* )),
+        Fields::Unit => quote!(),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::q_distributed_db_client::FromRow for #name #type_generics #where_clause {
+            fn from_row(
+                row: &::q_distributed_db_client::Row,
+            ) -> ::q_distributed_db_client::Result<Self> {
+                ::core::result::Result::Ok(Self #construct)
+            }
+        }
+    })
+}
+
+/// Builds the expression reading one field
+fn field_value(ty: &syn::Type, source: &Source, default: bool) -> TokenStream2 {
+    match (source, default) {
+        (Source::Flatten, _) => {
+            quote!(<#ty as ::q_distributed_db_client::FromRow>::from_row(row)?)
+        }
+        (Source::Name(name), false) => {
+            quote!(::q_distributed_db_client::Row::try_get::<#ty>(row, #name)?)
+        }
+        (Source::Index(index), false) => {
+            quote!(::q_distributed_db_client::Row::try_get_at::<#ty>(row, #index)?)
+        }
+        (Source::Name(name), true) => {
+            quote!(::q_distributed_db_client::from_row::column_or_default::<#ty>(row, #name)?)
+        }
+        (Source::Index(index), true) => {
+            quote!(::q_distributed_db_client::from_row::column_at_or_default::<#ty>(row, #index)?)
+        }
+    }
+}
+
+fn field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("row"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                options.rename = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("index") {
+                options.index = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("default") {
+                options.default = true;
+            } else if meta.path.is_ident("flatten") {
+                options.flatten = true;
+            } else {
+                return Err(meta.error("expected `rename`, `index`, `default` or `flatten`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    if options.flatten && (options.rename.is_some() || options.index.is_some() || options.default) {
+        return Err(syn::Error::new(
+            field.span(),
+            "`flatten` cannot be combined with `rename`, `index` or `default`",
+        ));
+    }
+    if options.rename.is_some() && options.index.is_some() {
+        return Err(syn::Error::new(
+            field.span(),
+            "`rename` and `index` are mutually exclusive",
+        ));
+    }
+    Ok(options)
+}
@@ -0,0 +1,15 @@
+//! Decodes arbitrary bytes as a single frame
+//!
+//! Covers legacy and versioned frames, every compression algorithm and the
+//! extension map. Decoding must fail cleanly without allocating more than
+//! the codec's size limit.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use q_distributed_db_client::MessageCodec;
+
+fuzz_target!(|data: &[u8]| {
+    let codec = MessageCodec::with_max_size(64 * 1024);
+    let _ = codec.decode_envelope(data);
+});
@@ -0,0 +1,21 @@
+//! Reads frames from an arbitrary byte stream
+//!
+//! Exercises the length prefix, fragment headers and reassembly in
+//! `read_envelope` until the input is exhausted or a frame is rejected.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use q_distributed_db_client::MessageCodec;
+
+fuzz_target!(|data: &[u8]| {
+    let codec = MessageCodec::with_max_size(1024).with_max_reassembled_size(64 * 1024);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let mut reader = data;
+        while codec.read_envelope(&mut reader).await.is_ok() {}
+    });
+});
@@ -0,0 +1,22 @@
+//! Decodes arbitrary LZ4 bodies in legacy and versioned frames
+//!
+//! The LZ4 body starts with an untrusted little-endian size prefix that must
+//! be checked against the codec limit before the output is allocated.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use q_distributed_db_client::{CompressionAlgorithm, FrameHeader, MessageCodec};
+
+fuzz_target!(|data: &[u8]| {
+    let codec = MessageCodec::with_max_size(64 * 1024);
+
+    let mut legacy = vec![CompressionAlgorithm::Lz4.id()];
+    legacy.extend_from_slice(data);
+    let _ = codec.decode(&legacy);
+
+    let mut versioned = Vec::with_capacity(data.len() + 8);
+    FrameHeader::new(0, CompressionAlgorithm::Lz4).write_to(&mut versioned);
+    versioned.extend_from_slice(data);
+    let _ = codec.decode_envelope(&versioned);
+});
@@ -0,0 +1,35 @@
+//! Decodes arbitrary message payloads as every response type
+//!
+//! Covers the payloads decoded by `DataClient`, `AdminClient` and
+//! `Transaction`, authentication replies and the signed tokens they carry,
+//! server error frames and the feature negotiation reply, including the
+//! conversion of decoded responses into results.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use q_distributed_db_client::{
+    AuthResponse, FeatureNegotiation, QueryResult, Response, ServerError, TokenSigner,
+    TransactionResponse,
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(response) = Response::from_payload(data) {
+        if let Ok(Response::Rows { columns, rows }) =
+            response.into_result(Some("SELECT 1"), Some(1))
+        {
+            let _ = QueryResult::from_raw(columns, rows);
+        }
+    }
+
+    let _ = TransactionResponse::from_payload(data);
+    let verifier = TokenSigner::new(&[1; 32], "fuzz").verifier();
+    if let Ok(AuthResponse::Authenticated(token)) = AuthResponse::from_payload(data) {
+        let _ = verifier.verify_auth_token(&token);
+    }
+    if let Ok(token) = std::str::from_utf8(data) {
+        let _ = verifier.verify(token);
+    }
+    let _ = ServerError::from_payload(data);
+    let _ = FeatureNegotiation::from_payload(data);
+});
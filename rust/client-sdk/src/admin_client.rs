@@ -7,6 +7,7 @@ use crate::auth::AuthenticationManager;
 use crate::connection::ConnectionManager;
 use crate::error::DatabaseError;
 use crate::protocol::{AdminRequest, AdminResponse, Request, Response};
+use crate::secret::SecretString;
 use crate::types::{
     ClusterMetrics, ClusterNodeInfo, NodeHealthMetrics, NodeId, Permission, Role, UserId, UserInfo,
     UserUpdate,
@@ -65,46 +66,9 @@ impl AdminClient {
     ///
     /// Returns information about all nodes including their status and role.
     pub async fn list_nodes(&self) -> Result<Vec<ClusterNodeInfo>> {
-        // Get connection from pool
-        let mut connection = self.connection_manager.get_connection().await?;
-
-        // Get valid auth token
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        // Build admin request
-        let request = Request::Admin(AdminRequest::ListNodes);
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        // Send request
-        let response = connection
-            .connection_mut()
-            .send_request(
-                crate::protocol::MessageType::Data,
-                payload,
-                5000, // 5 second timeout
-            )
-            .await?;
-
-        // Parse response
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::NodeList(nodes)) => Ok(nodes),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        match self.send(AdminRequest::ListNodes).await? {
+            AdminResponse::NodeList(nodes) => Ok(nodes),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -113,36 +77,9 @@ impl AdminClient {
     /// Returns detailed health information including CPU, memory, disk usage,
     /// connection count, and query throughput.
     pub async fn get_node_health(&self, node_id: NodeId) -> Result<NodeHealthMetrics> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        let request = Request::Admin(AdminRequest::GetNodeHealth { node_id });
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
-            .await?;
-
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::NodeHealth(health)) => Ok(health),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        match self.send(AdminRequest::GetNodeHealth { node_id }).await? {
+            AdminResponse::NodeHealth(health) => Ok(health),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -151,42 +88,16 @@ impl AdminClient {
     /// Initiates the node join process. The host parameter should be in the
     /// format "hostname:port" (e.g., "node3.example.com:7000").
     pub async fn add_node(&self, host: &str) -> Result<NodeId> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
         // Parse host:port
         let (hostname, port) = parse_host_port(host)?;
 
-        let request = Request::Admin(AdminRequest::AddNode {
+        let request = AdminRequest::AddNode {
             host: hostname.to_string(),
             port,
-        });
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
-            .await?;
-
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::NodeAdded(node_id)) => Ok(node_id),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        };
+        match self.send(request).await? {
+            AdminResponse::NodeAdded(node_id) => Ok(node_id),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -195,36 +106,9 @@ impl AdminClient {
     /// Gracefully removes a node from the cluster. Data will be migrated
     /// before the node is removed.
     pub async fn remove_node(&self, node_id: NodeId) -> Result<()> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        let request = Request::Admin(AdminRequest::RemoveNode { node_id });
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
-            .await?;
-
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::NodeRemoved) => Ok(()),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        match self.send(AdminRequest::RemoveNode { node_id }).await? {
+            AdminResponse::NodeRemoved => Ok(()),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -232,36 +116,9 @@ impl AdminClient {
     ///
     /// Rebalances data partitions to optimize distribution and performance.
     pub async fn rebalance_partitions(&self) -> Result<()> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        let request = Request::Admin(AdminRequest::RebalancePartitions);
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
-            .await?;
-
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::PartitionsRebalanced) => Ok(()),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        match self.send(AdminRequest::RebalancePartitions).await? {
+            AdminResponse::PartitionsRebalanced => Ok(()),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -270,36 +127,9 @@ impl AdminClient {
     /// Returns aggregated metrics including total queries, average latency,
     /// error rate, and storage usage.
     pub async fn get_cluster_metrics(&self) -> Result<ClusterMetrics> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        let request = Request::Admin(AdminRequest::GetClusterMetrics);
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
-            .await?;
-
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::ClusterMetrics(metrics)) => Ok(metrics),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        match self.send(AdminRequest::GetClusterMetrics).await? {
+            AdminResponse::ClusterMetrics(metrics) => Ok(metrics),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -316,40 +146,14 @@ impl AdminClient {
         password: &str,
         roles: &[Role],
     ) -> Result<UserId> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        let request = Request::Admin(AdminRequest::CreateUser {
+        let request = AdminRequest::CreateUser {
             username: username.to_string(),
-            password: password.to_string(),
+            password: SecretString::new(password),
             roles: roles.to_vec(),
-        });
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
-            .await?;
-
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::UserCreated(user_id)) => Ok(user_id),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        };
+        match self.send(request).await? {
+            AdminResponse::UserCreated(user_id) => Ok(user_id),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -357,36 +161,9 @@ impl AdminClient {
     ///
     /// Returns information about all users including their roles and permissions.
     pub async fn list_users(&self) -> Result<Vec<UserInfo>> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        let request = Request::Admin(AdminRequest::ListUsers);
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
-            .await?;
-
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::UserList(users)) => Ok(users),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        match self.send(AdminRequest::ListUsers).await? {
+            AdminResponse::UserList(users) => Ok(users),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -394,36 +171,12 @@ impl AdminClient {
     ///
     /// Modifies user credentials or roles.
     pub async fn update_user(&self, user_id: UserId, update: UserUpdate) -> Result<()> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        let request = Request::Admin(AdminRequest::UpdateUser { user_id, update });
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
-            .await?;
-
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::UserUpdated) => Ok(()),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        match self
+            .send(AdminRequest::UpdateUser { user_id, update })
+            .await?
+        {
+            AdminResponse::UserUpdated => Ok(()),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -431,36 +184,9 @@ impl AdminClient {
     ///
     /// Removes a user from the system. Active sessions will be invalidated.
     pub async fn delete_user(&self, user_id: UserId) -> Result<()> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        let request = Request::Admin(AdminRequest::DeleteUser { user_id });
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
-            .await?;
-
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::UserDeleted) => Ok(()),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        match self.send(AdminRequest::DeleteUser { user_id }).await? {
+            AdminResponse::UserDeleted => Ok(()),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -468,39 +194,13 @@ impl AdminClient {
     ///
     /// Adds a specific permission to the user's permission set.
     pub async fn grant_permission(&self, user_id: UserId, permission: Permission) -> Result<()> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        let request = Request::Admin(AdminRequest::GrantPermission {
+        let request = AdminRequest::GrantPermission {
             user_id,
             permission,
-        });
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
-            .await?;
-
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
-
-        match response {
-            Response::Admin(AdminResponse::PermissionGranted) => Ok(()),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        };
+        match self.send(request).await? {
+            AdminResponse::PermissionGranted => Ok(()),
+            other => Err(other.unexpected()),
         }
     }
 
@@ -508,39 +208,44 @@ impl AdminClient {
     ///
     /// Removes a specific permission from the user's permission set.
     pub async fn revoke_permission(&self, user_id: UserId, permission: Permission) -> Result<()> {
-        let mut connection = self.connection_manager.get_connection().await?;
-        let _token = self.auth_manager.get_valid_token().await?;
-
-        let request = Request::Admin(AdminRequest::RevokePermission {
+        let request = AdminRequest::RevokePermission {
             user_id,
             permission,
-        });
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize request: {}", e),
-            })?;
-
-        let response = connection
-            .connection_mut()
-            .send_request(crate::protocol::MessageType::Data, payload, 5000)
+        };
+        match self.send(request).await? {
+            AdminResponse::PermissionRevoked => Ok(()),
+            other => Err(other.unexpected()),
+        }
+    }
+
+    /// Sends an admin request and returns the admin response
+    ///
+    /// The request carries the token, so the server runs it as the token's
+    /// user. With permission checks enabled, requests the token does not
+    /// allow fail before they are sent. A request rejected because the token
+    /// expired or was revoked is replayed once after re-authenticating.
+    async fn send(&self, request: AdminRequest) -> Result<AdminResponse> {
+        self.auth_manager
+            .check_permission(request.required_permission())
             .await?;
 
-        let response: Response = bincode::deserialize(&response.payload).map_err(|e| {
-            DatabaseError::SerializationError {
-                message: format!("Failed to deserialize response: {}", e),
-            }
-        })?;
+        let mut connection = self.connection_manager.get_connection().await?;
+        let response = self
+            .auth_manager
+            .send_authenticated(
+                connection.connection_mut(),
+                |token| Request::AuthenticatedAdmin {
+                    request: request.clone(),
+                    auth_token: Some(token.signature.clone()),
+                },
+                5000, // 5 second timeout
+            )
+            .await;
+        self.connection_manager.return_connection(connection).await;
 
-        match response {
-            Response::Admin(AdminResponse::PermissionRevoked) => Ok(()),
-            Response::Error(e) => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: e,
-            }),
-            _ => Err(DatabaseError::InternalError {
-                component: "AdminClient".to_string(),
-                details: "Unexpected response type".to_string(),
-            }),
+        match response? {
+            Response::Admin(response) => Ok(response),
+            other => Err(other.unexpected("AdminClient")),
         }
     }
 }
@@ -685,19 +390,19 @@ mod tests {
     #[test]
     fn test_user_update_creation() {
         let update = UserUpdate {
-            password: Some("new_password".to_string()),
+            password: Some("new_password".into()),
             roles: Some(vec![Role::User]),
         };
 
         assert!(update.password.is_some());
         assert!(update.roles.is_some());
-        assert_eq!(update.password.unwrap(), "new_password");
+        assert_eq!(update.password.unwrap().expose(), "new_password");
     }
 
     #[test]
     fn test_user_update_partial() {
         let update = UserUpdate {
-            password: Some("new_password".to_string()),
+            password: Some("new_password".into()),
             roles: None,
         };
 
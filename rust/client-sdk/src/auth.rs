@@ -2,14 +2,39 @@
 //!
 //! This module implements token-based authentication, automatic re-authentication,
 //! and credential management.
+//!
+//! Tokens are obtained from the server with `AuthRequest` messages sent as the
+//! payload of `MessageType::Auth` frames. Rejected credentials and tokens come
+//! back as error frames.
+//!
+//! Passwords are authenticated with SCRAM-SHA-256 when the server negotiates
+//! `Feature::ScramSha256`. Against servers without it, the `PasswordPolicy`
+//! decides whether the password may be sent in a `Login` request instead.
+//!
+//! Credentials are fetched from a `CredentialProvider` at every login, so
+//! rotated secrets take effect without restarting the client.
+//!
+//! Tokens are renewed in the background by a `TokenRefresher` before they
+//! expire. Callers that find the token expired share a single re-authentication.
+//!
+//! Data requests sent with `send_authenticated` survive tokens the server
+//! rejects before they expire locally, e.g. revoked ones: the manager logs in
+//! again and the request is replayed once.
 
+use crate::connection::{Connection, ConnectionManager};
+use crate::credentials::{CredentialProvider, StaticCredentialProvider};
 use crate::error::DatabaseError;
-use crate::types::{Role, UserId};
+use crate::protocol::{Request, Response};
+use crate::scram::ScramClient;
+use crate::secret::{SecretBytes, SecretString};
+use crate::token::{TokenClaims, TokenVerifier};
+use crate::types::{Feature, PasswordPolicy, Permission, Role, TokenRefreshConfig, UserId};
 use crate::Result;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::{Arc, Weak};
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
 
 /// Certificate type placeholder
 ///
@@ -26,21 +51,23 @@ pub struct Certificate {
 /// - Username/password authentication
 /// - Certificate-based authentication (TLS)
 /// - Token reuse
+///
+/// The password and token are redacted when the credentials are printed.
 #[derive(Debug, Clone)]
 pub struct Credentials {
     /// Username for authentication
     pub username: String,
     /// Optional password for username/password authentication
-    pub password: Option<String>,
+    pub password: Option<SecretString>,
     /// Optional certificate for TLS authentication
     pub certificate: Option<Certificate>,
     /// Optional pre-existing token for token reuse
-    pub token: Option<String>,
+    pub token: Option<SecretString>,
 }
 
 impl Credentials {
     /// Creates new credentials with username and password
-    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+    pub fn new(username: impl Into<String>, password: impl Into<SecretString>) -> Self {
         Self {
             username: username.into(),
             password: Some(password.into()),
@@ -60,7 +87,7 @@ impl Credentials {
     }
 
     /// Sets the password
-    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+    pub fn with_password(mut self, password: impl Into<SecretString>) -> Self {
         self.password = Some(password.into());
         self
     }
@@ -72,7 +99,7 @@ impl Credentials {
     }
 
     /// Sets the token
-    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+    pub fn with_token(mut self, token: impl Into<SecretString>) -> Self {
         self.token = Some(token.into());
         self
     }
@@ -107,7 +134,16 @@ pub struct AuthToken {
     /// Token expiration timestamp
     pub expiration: DateTime<Utc>,
     /// Cryptographic signature for validation
-    pub signature: Vec<u8>,
+    ///
+    /// Servers that issue signed tokens put the token here in compact JWT
+    /// form, see `signed_token`.
+    pub signature: SecretBytes,
+    /// Session secret established during authentication, used to key
+    /// per-message authentication tags
+    pub session_key: Option<SecretBytes>,
+    /// Claims of the signed token, set once it has been verified
+    #[serde(skip)]
+    pub claims: Option<TokenClaims>,
 }
 
 impl AuthToken {
@@ -122,7 +158,34 @@ impl AuthToken {
             user_id,
             roles,
             expiration,
-            signature,
+            signature: SecretBytes::new(signature),
+            session_key: None,
+            claims: None,
+        }
+    }
+
+    /// Attaches the session secret established during authentication
+    pub fn with_session_key(mut self, session_key: Vec<u8>) -> Self {
+        self.session_key = Some(SecretBytes::new(session_key));
+        self
+    }
+
+    /// Returns the token in compact JWT form, e.g. to forward it as proof of
+    /// identity, or `None` if the server did not issue a signed token
+    pub fn signed_token(&self) -> Option<&str> {
+        std::str::from_utf8(self.signature.expose())
+            .ok()
+            .filter(|token| token.split('.').count() == 3)
+    }
+
+    /// Returns the permissions the token grants
+    ///
+    /// Verified claims list them explicitly, including permissions granted
+    /// to the user individually. Otherwise they are derived from the roles.
+    pub fn permissions(&self) -> Vec<Permission> {
+        match &self.claims {
+            Some(claims) => claims.permissions.clone(),
+            None => Role::union(&self.roles),
         }
     }
 
@@ -149,79 +212,367 @@ impl AuthToken {
     }
 }
 
+/// Timeout for authentication requests
+pub(crate) const AUTH_TIMEOUT_MS: u64 = 5000;
+
+/// Authentication request
+///
+/// Sent as the payload of `MessageType::Auth` frames. The bincode variant
+/// index is the request tag, so new variants must only ever be appended.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AuthRequest {
+    /// Exchange credentials for a token
+    Login {
+        username: String,
+        password: Option<SecretString>,
+        certificate: Option<Vec<u8>>,
+        token: Option<SecretString>,
+        /// Requested token lifetime; the server may issue a shorter one
+        token_ttl_secs: u64,
+    },
+    /// Exchange a valid token for one with a later expiration
+    Refresh { signature: SecretBytes },
+    /// Revoke a token
+    Logout { signature: SecretBytes },
+    /// Start a SCRAM-SHA-256 login with the client-first message
+    ScramClientFirst {
+        message: String,
+        /// Requested token lifetime; the server may issue a shorter one
+        token_ttl_secs: u64,
+    },
+    /// Finish a SCRAM-SHA-256 login with the client-final message, which
+    /// carries the client proof
+    ScramClientFinal { message: String },
+    /// Use the session of a token for message authentication on this
+    /// connection
+    ///
+    /// Sent signed with the connection's new keys, which proves the session
+    /// secret to the server.
+    Bind { signature: SecretBytes },
+}
+
+/// Authentication response
+///
+/// Like `AuthRequest`, variants are tagged by index and must only be appended.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AuthResponse {
+    /// Token issued for a login or refresh
+    Authenticated(AuthToken),
+    /// Token revoked
+    LoggedOut,
+    /// SCRAM-SHA-256 server-first message with the salt and iteration count
+    ScramServerFirst { message: String },
+    /// SCRAM-SHA-256 server-final message and the issued token
+    ///
+    /// The token must only be used once the server signature in `message`
+    /// has been verified.
+    ScramServerFinal { message: String, token: AuthToken },
+    /// Session bound to the connection
+    Bound,
+}
+
+impl AuthRequest {
+    /// Serializes the request into a message payload
+    pub fn to_payload(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| DatabaseError::SerializationError {
+            message: format!("Failed to serialize auth request: {}", e),
+        })
+    }
+
+    /// Deserializes a request from a message payload
+    pub fn from_payload(payload: &[u8]) -> Result<Self> {
+        bincode::deserialize(payload).map_err(|e| DatabaseError::SerializationError {
+            message: format!("Failed to deserialize auth request: {}", e),
+        })
+    }
+}
+
+impl AuthResponse {
+    /// Serializes the response into a message payload
+    pub fn to_payload(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| DatabaseError::SerializationError {
+            message: format!("Failed to serialize auth response: {}", e),
+        })
+    }
+
+    /// Deserializes a response from a message payload
+    pub fn from_payload(payload: &[u8]) -> Result<Self> {
+        bincode::deserialize(payload).map_err(|e| DatabaseError::SerializationError {
+            message: format!("Failed to deserialize auth response: {}", e),
+        })
+    }
+
+    /// Returns the issued token, or an error for any other response
+    fn into_token(self) -> Result<AuthToken> {
+        match self {
+            AuthResponse::Authenticated(token) => Ok(token),
+            other => Err(other.unexpected()),
+        }
+    }
+
+    fn unexpected(&self) -> DatabaseError {
+        let kind = match self {
+            AuthResponse::Authenticated(_) => "Authenticated",
+            AuthResponse::LoggedOut => "LoggedOut",
+            AuthResponse::ScramServerFirst { .. } => "ScramServerFirst",
+            AuthResponse::ScramServerFinal { .. } => "ScramServerFinal",
+            AuthResponse::Bound => "Bound",
+        };
+        DatabaseError::unexpected_response("AuthenticationManager", kind)
+    }
+}
+
 /// Authentication manager
 ///
 /// Manages the authentication lifecycle including:
 /// - Initial authentication
 /// - Token validation and renewal
-/// - Automatic re-authentication
+/// - Automatic re-authentication, shared by concurrent callers
+/// - Background renewal with `start_refresher`
 /// - Logout
+///
+/// Requests go to the server through a pooled connection of the connection
+/// manager set with `with_connection_manager`, or through the connection
+/// passed to `authenticate_with` or `send_authenticated`, so renewing a token
+/// never needs a second connection from a pool the caller may have
+/// exhausted. A login is always completed on a single connection, since
+/// SCRAM-SHA-256 takes two round trips.
 #[derive(Clone)]
 pub struct AuthenticationManager {
-    /// User credentials
-    credentials: Credentials,
+    /// Source of the credentials used at each login, if the manager can log in
+    credential_provider: Option<Arc<dyn CredentialProvider>>,
     /// Current authentication token (thread-safe)
     token: Arc<RwLock<Option<AuthToken>>>,
+    /// When the current token was stored, to schedule its renewal
+    issued_at: Arc<RwLock<Option<DateTime<Utc>>>>,
+    /// Held while the token is renewed, so concurrent renewals run once
+    renewal: Arc<Mutex<()>>,
     /// Token time-to-live
     token_ttl: std::time::Duration,
+    /// Connections used to reach the server
+    connection_manager: Option<Arc<ConnectionManager>>,
+    /// Whether pooled connections are bound to this manager's token
+    owns_pool_session: bool,
+    /// Whether the password may be sent without SCRAM-SHA-256
+    password_policy: PasswordPolicy,
+    /// Verifies issued tokens, if configured
+    token_verifier: Option<TokenVerifier>,
+    /// Whether `check_permission` checks the token's permissions
+    permission_checks: bool,
 }
 
 impl AuthenticationManager {
     /// Creates a new authentication manager
     pub fn new(credentials: Credentials, token_ttl: std::time::Duration) -> Self {
+        Self::from_provider(
+            Arc::new(StaticCredentialProvider::new(credentials)),
+            token_ttl,
+        )
+    }
+
+    /// Creates an authentication manager that fetches credentials from the
+    /// provider at every login
+    pub fn from_provider(
+        credential_provider: Arc<dyn CredentialProvider>,
+        token_ttl: std::time::Duration,
+    ) -> Self {
         Self {
-            credentials,
+            credential_provider: Some(credential_provider),
+            ..Self::without_credentials(token_ttl)
+        }
+    }
+
+    /// Creates an authentication manager that cannot log in
+    ///
+    /// The manager only uses tokens given to `use_token`, which are still
+    /// refreshed before they expire. Once a token has expired or been revoked,
+    /// requests fail with `AuthenticationFailed` until another one is given.
+    pub fn without_credentials(token_ttl: std::time::Duration) -> Self {
+        Self {
+            credential_provider: None,
             token: Arc::new(RwLock::new(None)),
+            issued_at: Arc::new(RwLock::new(None)),
+            renewal: Arc::new(Mutex::new(())),
             token_ttl,
+            connection_manager: None,
+            owns_pool_session: true,
+            password_policy: PasswordPolicy::default(),
+            token_verifier: None,
+            permission_checks: false,
         }
     }
 
+    /// Sets the connection manager used to reach the server
+    ///
+    /// The manager's token becomes the session pooled connections are bound
+    /// to, unless `with_shared_pool` is also used.
+    pub fn with_connection_manager(mut self, connection_manager: Arc<ConnectionManager>) -> Self {
+        self.connection_manager = Some(connection_manager);
+        self
+    }
+
+    /// Leaves the pool's session to the manager that owns the pool
+    ///
+    /// For managers sharing another manager's pool, such as the user views
+    /// of a client. Their logins and logouts do not change which session
+    /// pooled connections are bound to; `send_authenticated` binds each
+    /// connection to this manager's token for the request instead.
+    pub fn with_shared_pool(mut self) -> Self {
+        self.owns_pool_session = false;
+        self
+    }
+
+    /// Sets whether the password may be sent to servers without SCRAM-SHA-256
+    pub fn with_password_policy(mut self, password_policy: PasswordPolicy) -> Self {
+        self.password_policy = password_policy;
+        self
+    }
+
+    /// Verifies every issued token and attaches its claims
+    ///
+    /// Tokens that fail verification are rejected with `InvalidToken`.
+    pub fn with_token_verifier(mut self, token_verifier: TokenVerifier) -> Self {
+        self.token_verifier = Some(token_verifier);
+        self
+    }
+
+    /// Enables or disables the checks made by `check_permission`
+    pub fn with_permission_checks(mut self, enabled: bool) -> Self {
+        self.permission_checks = enabled;
+        self
+    }
+
     /// Authenticates with the server and obtains a token
     ///
-    /// This is a placeholder implementation that will be completed when
-    /// the connection layer is integrated.
+    /// Fails with `InvalidCredentials` if the server rejects the credentials.
     pub async fn authenticate(&self) -> Result<AuthToken> {
-        // Validate credentials
-        self.credentials.validate()?;
-
-        // TODO: Send AuthRequest to server through connection
-        // For now, create a mock token for testing
-        let token = AuthToken::new(
-            1, // user_id
-            vec![Role::User],
-            Utc::now() + chrono::Duration::from_std(self.token_ttl).unwrap(),
-            vec![0; 32], // signature
-        );
+        // Fetch and validate credentials
+        let credentials = self.credentials().await?;
+
+        let connection_manager = self.connection_manager()?;
+        let mut conn = connection_manager.get_connection().await?;
+        let token = self.login(conn.connection_mut(), &credentials).await;
+        connection_manager.return_connection(conn).await;
+        self.store(token?).await
+    }
 
-        // Store token
-        let mut token_guard = self.token.write().await;
-        *token_guard = Some(token.clone());
+    /// Authenticates over the given connection and obtains a token
+    pub async fn authenticate_with(&self, connection: &mut Connection) -> Result<AuthToken> {
+        let credentials = self.credentials().await?;
 
-        Ok(token)
+        let token = self.login(connection, &credentials).await?;
+        self.store(token).await
     }
 
     /// Gets a valid token, re-authenticating if necessary
+    ///
+    /// Concurrent callers that find the token expired wait for a single
+    /// re-authentication and share its token.
     pub async fn get_valid_token(&self) -> Result<AuthToken> {
+        self.valid_token(None).await
+    }
+
+    /// Gets a valid token, renewing it over `connection` or, without one,
+    /// over a pooled connection
+    async fn valid_token(&self, connection: Option<&mut Connection>) -> Result<AuthToken> {
         // Check if we have a token
         let token_guard = self.token.read().await;
-        if let Some(token) = token_guard.as_ref() {
+        let stale = match token_guard.as_ref() {
             // Check if token is still valid
-            if !token.is_expired() {
-                return Ok(token.clone());
-            }
-        }
+            Some(token) if !token.is_expired() => return Ok(token.clone()),
+            Some(token) => Some(token.signature.clone()),
+            None => None,
+        };
         drop(token_guard);
 
         // Token is expired or missing, re-authenticate
-        self.authenticate().await
+        self.renew(stale.as_ref(), connection).await
+    }
+
+    /// Fails with `InsufficientPermissions` if permission checks are enabled
+    /// and the token does not grant `permission`
+    ///
+    /// The server remains the authority; the check only saves a round trip
+    /// for requests it would reject.
+    pub async fn check_permission(&self, permission: Permission) -> Result<()> {
+        if !self.permission_checks {
+            return Ok(());
+        }
+
+        let token = self.get_valid_token().await?;
+        if !token.permissions().contains(&permission) {
+            return Err(DatabaseError::InsufficientPermissions {
+                required: format!("{:?}", permission),
+            });
+        }
+        Ok(())
+    }
+
+    /// Sends a data request carrying a valid token over `connection`
+    ///
+    /// `request` builds the request around a token. If the server rejects the
+    /// token, e.g. because it was revoked, the manager logs in again and the
+    /// request is replayed once with the new token. Renewals and logins go over
+    /// `connection` itself. The server checks the token before running a
+    /// request, so the rejected attempt had no effect.
+    ///
+    /// Transactions do not use this: their server-side state may not survive
+    /// the token, so the error is returned to the caller.
+    pub async fn send_authenticated<F>(
+        &self,
+        connection: &mut Connection,
+        request: F,
+        timeout_ms: u64,
+    ) -> Result<Response>
+    where
+        F: Fn(&AuthToken) -> Request,
+    {
+        let token = self.valid_token(Some(connection)).await?;
+        let response = match connection.use_session(&token).await {
+            Ok(()) => {
+                connection
+                    .send_data_request(&request(&token), timeout_ms)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        match response {
+            Err(e) if e.is_auth_error() => {
+                tracing::warn!("Server rejected the token, authenticating again: {}", e);
+                let token = self.replace_rejected(&token, connection).await?;
+                connection.use_session(&token).await?;
+                connection
+                    .send_data_request(&request(&token), timeout_ms)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    /// Starts renewing the token in the background
+    ///
+    /// The token is renewed once `refresh_fraction` of its lifetime has
+    /// passed, and failed renewals are retried with jittered backoff. The
+    /// refresher stops when it is dropped, when the manager is dropped, or
+    /// once there is no token to renew, e.g. after `logout`.
+    pub fn start_refresher(self: &Arc<Self>, config: TokenRefreshConfig) -> TokenRefresher {
+        TokenRefresher {
+            task: tokio::spawn(refresh_loop(Arc::downgrade(self), config)),
+        }
     }
 
     /// Refreshes the current token
     ///
-    /// Proactively renews the token before it expires.
+    /// Proactively renews the token before it expires. An expired token is
+    /// replaced by authenticating again.
     pub async fn refresh_token(&self) -> Result<AuthToken> {
-        // Get current token and clone the necessary data
-        let (user_id, roles) = {
+        self.refresh(None).await
+    }
+
+    /// Refreshes the current token over `connection` or, without one, over a
+    /// pooled connection
+    async fn refresh(&self, connection: Option<&mut Connection>) -> Result<AuthToken> {
+        let signature = {
             let token_guard = self.token.read().await;
             let current_token =
                 token_guard
@@ -233,70 +584,376 @@ impl AuthenticationManager {
             // Check if token is still valid
             if current_token.is_expired() {
                 drop(token_guard);
-                return self.authenticate().await;
+                return self.login_over(connection).await;
             }
 
-            // Clone the data we need
-            (current_token.user_id, current_token.roles.clone())
+            current_token.signature.clone()
         };
 
-        // TODO: Send token refresh request to server
-        // For now, create a new token with extended expiration
-        let new_token = AuthToken::new(
-            user_id,
-            roles,
-            Utc::now() + chrono::Duration::from_std(self.token_ttl).unwrap(),
-            vec![0; 32], // signature
-        );
-
-        // Store new token
-        let mut token_guard = self.token.write().await;
-        *token_guard = Some(new_token.clone());
-
-        Ok(new_token)
+        let request = AuthRequest::Refresh { signature };
+        let token = match connection {
+            Some(connection) => {
+                connection
+                    .send_auth_request(&request, AUTH_TIMEOUT_MS)
+                    .await
+            }
+            None => self.exchange(&request).await,
+        }?
+        .into_token()?;
+        self.store(token).await
     }
 
     /// Logs out and invalidates the current token
+    ///
+    /// The token is forgotten locally even if the server cannot be reached,
+    /// and is not renewed afterwards.
     pub async fn logout(&self) -> Result<()> {
-        // Get current token
-        let token_guard = self.token.read().await;
-        let _current_token =
-            token_guard
-                .as_ref()
+        // Wait for a renewal in progress rather than race it
+        let _renewal = self.renewal.lock().await;
+        let current_token =
+            self.token
+                .write()
+                .await
+                .take()
                 .ok_or_else(|| DatabaseError::AuthenticationFailed {
                     reason: "No token to logout".to_string(),
                 })?;
-        drop(token_guard);
-
-        // TODO: Send logout request to server
-
-        // Clear stored token
-        let mut token_guard = self.token.write().await;
-        *token_guard = None;
+        if let Some(connection_manager) = self.pool_session() {
+            connection_manager.set_session(None).await;
+        }
 
+        self.exchange(&AuthRequest::Logout {
+            signature: current_token.signature,
+        })
+        .await?;
         Ok(())
     }
 
+    /// Uses a token obtained elsewhere, e.g. forwarded by another service
+    ///
+    /// The token is verified if a verifier is configured.
+    pub async fn use_token(&self, token: AuthToken) -> Result<AuthToken> {
+        self.store(token).await
+    }
+
     /// Gets the current token without validation
     pub async fn get_token(&self) -> Option<AuthToken> {
         self.token.read().await.clone()
     }
+
+    /// Replaces the token unless another caller already replaced `stale`
+    ///
+    /// A token rejected by the server on refresh is replaced by logging in
+    /// again.
+    async fn renew(
+        &self,
+        stale: Option<&SecretBytes>,
+        mut connection: Option<&mut Connection>,
+    ) -> Result<AuthToken> {
+        let _renewal = self.renewal.lock().await;
+
+        match self.get_token().await {
+            Some(token) if !token.is_expired() => {
+                if stale != Some(&token.signature) {
+                    return Ok(token);
+                }
+                match self.refresh(connection.as_deref_mut()).await {
+                    Err(DatabaseError::AuthenticationFailed { reason }) => {
+                        tracing::warn!("Token refresh rejected, logging in again: {}", reason);
+                    }
+                    result => return result,
+                }
+            }
+            None if stale.is_some() => {
+                return Err(DatabaseError::AuthenticationFailed {
+                    reason: "Logged out while renewing the token".to_string(),
+                });
+            }
+            _ => {}
+        }
+        self.login_over(connection).await
+    }
+
+    /// Logs in again over `connection` unless another caller already
+    /// replaced the token the server rejected
+    async fn replace_rejected(
+        &self,
+        rejected: &AuthToken,
+        connection: &mut Connection,
+    ) -> Result<AuthToken> {
+        let _renewal = self.renewal.lock().await;
+
+        match self.get_token().await {
+            Some(token) if token.signature != rejected.signature && !token.is_expired() => {
+                Ok(token)
+            }
+            None => Err(DatabaseError::AuthenticationFailed {
+                reason: "Logged out while renewing the token".to_string(),
+            }),
+            _ => self.authenticate_with(connection).await,
+        }
+    }
+
+    /// Logs in over `connection` or, without one, over a pooled connection
+    async fn login_over(&self, connection: Option<&mut Connection>) -> Result<AuthToken> {
+        match connection {
+            Some(connection) => self.authenticate_with(connection).await,
+            None => self.authenticate().await,
+        }
+    }
+
+    /// Returns how long until the current token is due for renewal, or
+    /// `None` without a token
+    async fn refresh_delay(&self, refresh_fraction: f64) -> Option<std::time::Duration> {
+        let token = self.get_token().await?;
+        let issued_at = self.issued_at.read().await.unwrap_or_else(Utc::now);
+        let lifetime_ms = (token.expiration - issued_at).num_milliseconds().max(0);
+        let refresh_at =
+            issued_at + Duration::milliseconds((lifetime_ms as f64 * refresh_fraction) as i64);
+        Some((refresh_at - Utc::now()).to_std().unwrap_or_default())
+    }
+
+    /// Logs in over the given connection with the strongest mechanism the
+    /// server supports and the password policy allows
+    async fn login(
+        &self,
+        connection: &mut Connection,
+        credentials: &Credentials,
+    ) -> Result<AuthToken> {
+        if let Some(password) = &credentials.password {
+            if connection.has_feature(&Feature::ScramSha256) {
+                return self
+                    .scram_login(connection, &credentials.username, password.expose())
+                    .await;
+            }
+            if self.password_policy == PasswordPolicy::RequireScram {
+                return Err(DatabaseError::AuthenticationFailed {
+                    reason: "Server does not support SCRAM-SHA-256 and the password policy forbids sending the password".to_string(),
+                });
+            }
+            tracing::warn!(
+                "Node {} does not support SCRAM-SHA-256, sending password in login request",
+                connection.node_id()
+            );
+        }
+
+        connection
+            .send_auth_request(&self.login_request(credentials), AUTH_TIMEOUT_MS)
+            .await?
+            .into_token()
+    }
+
+    /// Runs a SCRAM-SHA-256 exchange and verifies the server signature before
+    /// accepting the issued token
+    async fn scram_login(
+        &self,
+        connection: &mut Connection,
+        username: &str,
+        password: &str,
+    ) -> Result<AuthToken> {
+        let client = ScramClient::new(username, password)?;
+        let client_first = AuthRequest::ScramClientFirst {
+            message: client.client_first(),
+            token_ttl_secs: self.token_ttl.as_secs(),
+        };
+        let server_first = match connection
+            .send_auth_request(&client_first, AUTH_TIMEOUT_MS)
+            .await?
+        {
+            AuthResponse::ScramServerFirst { message } => message,
+            other => return Err(other.unexpected()),
+        };
+
+        let (message, verifier) = client.client_final(&server_first)?;
+        match connection
+            .send_auth_request(&AuthRequest::ScramClientFinal { message }, AUTH_TIMEOUT_MS)
+            .await?
+        {
+            AuthResponse::ScramServerFinal { message, token } => {
+                verifier.verify(&message)?;
+                Ok(token)
+            }
+            other => Err(other.unexpected()),
+        }
+    }
+
+    /// Fetches the credentials for a login from the provider
+    async fn credentials(&self) -> Result<Credentials> {
+        let provider = self.credential_provider.as_ref().ok_or_else(|| {
+            DatabaseError::AuthenticationFailed {
+                reason: "No credentials to log in with".to_string(),
+            }
+        })?;
+        let credentials = provider.credentials().await?;
+        credentials.validate()?;
+        Ok(credentials)
+    }
+
+    fn login_request(&self, credentials: &Credentials) -> AuthRequest {
+        AuthRequest::Login {
+            username: credentials.username.clone(),
+            password: credentials.password.clone(),
+            certificate: credentials
+                .certificate
+                .as_ref()
+                .map(|certificate| certificate.data.clone()),
+            token: credentials.token.clone(),
+            token_ttl_secs: self.token_ttl.as_secs(),
+        }
+    }
+
+    fn connection_manager(&self) -> Result<&Arc<ConnectionManager>> {
+        self.connection_manager
+            .as_ref()
+            .ok_or_else(|| DatabaseError::InternalError {
+                component: "AuthenticationManager".to_string(),
+                details: "No connection manager configured".to_string(),
+            })
+    }
+
+    /// Sends a request over a pooled connection
+    async fn exchange(&self, request: &AuthRequest) -> Result<AuthResponse> {
+        let connection_manager = self.connection_manager()?;
+        let mut conn = connection_manager.get_connection().await?;
+        let response = conn
+            .connection_mut()
+            .send_auth_request(request, AUTH_TIMEOUT_MS)
+            .await;
+        connection_manager.return_connection(conn).await;
+        response
+    }
+
+    /// Stores a newly issued token, once verified
+    async fn store(&self, mut token: AuthToken) -> Result<AuthToken> {
+        if let Some(verifier) = &self.token_verifier {
+            token.claims = Some(verifier.verify_auth_token(&token)?);
+        }
+
+        *self.issued_at.write().await = Some(Utc::now());
+        *self.token.write().await = Some(token.clone());
+        if let Some(connection_manager) = self.pool_session() {
+            connection_manager.set_session(Some(token.clone())).await;
+        }
+        Ok(token)
+    }
+
+    /// Returns the connection manager whose session this manager owns
+    fn pool_session(&self) -> Option<&ConnectionManager> {
+        self.connection_manager
+            .as_deref()
+            .filter(|_| self.owns_pool_session)
+    }
+}
+
+/// Background renewal of an `AuthenticationManager`'s token
+///
+/// Created by `AuthenticationManager::start_refresher`. The task is aborted
+/// when the refresher is dropped.
+pub struct TokenRefresher {
+    task: JoinHandle<()>,
+}
+
+impl TokenRefresher {
+    /// Stops renewing the token
+    pub fn stop(&self) {
+        self.task.abort();
+    }
+
+    /// Returns whether the refresher has stopped
+    pub fn is_stopped(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for TokenRefresher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Shortest time the refresher sleeps before a renewal that is not yet due
+const MIN_REFRESH_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Renews the token of `manager` until it is dropped or has no token
+///
+/// The manager is only held while renewing, not while sleeping.
+async fn refresh_loop(manager: Weak<AuthenticationManager>, config: TokenRefreshConfig) {
+    let mut attempt = 0;
+    loop {
+        let Some(strong) = manager.upgrade() else {
+            return;
+        };
+        let Some(delay) = strong.refresh_delay(config.refresh_fraction).await else {
+            tracing::debug!("No token to renew, stopping token refresher");
+            return;
+        };
+        if !delay.is_zero() {
+            drop(strong);
+            tokio::time::sleep(delay.max(MIN_REFRESH_DELAY)).await;
+            continue;
+        }
+
+        let stale = strong.get_token().await.map(|token| token.signature);
+        let renewed = match strong.renew(stale.as_ref(), None).await {
+            // A token due as soon as it is issued, e.g. with a zero lifetime
+            // or a skewed server clock, would be renewed without pause
+            Ok(token) => match strong.refresh_delay(config.refresh_fraction).await {
+                Some(delay) if delay.is_zero() => Err(DatabaseError::TokenExpired {
+                    expired_at: token.expiration.timestamp(),
+                }),
+                _ => Ok(token),
+            },
+            Err(e) => Err(e),
+        };
+        match renewed {
+            Ok(token) => {
+                tracing::debug!("Renewed token, expires at {}", token.expiration);
+                attempt = 0;
+            }
+            Err(e) => {
+                let delay = config.retry_delay(attempt);
+                tracing::warn!("Token renewal failed, retrying in {:?}: {}", delay, e);
+                attempt = attempt.saturating_add(1);
+                drop(strong);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::MessageType;
+    use crate::testing::MockServer;
+    use crate::types::{IntegrityConfig, Permission};
 
     // Credentials Tests
     #[test]
     fn test_credentials_new() {
         let creds = Credentials::new("admin", "password");
         assert_eq!(creds.username, "admin");
-        assert_eq!(creds.password, Some("password".to_string()));
+        assert_eq!(creds.password, Some("password".into()));
         assert!(creds.certificate.is_none());
         assert!(creds.token.is_none());
     }
 
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let creds = Credentials::new("admin", "hunter2").with_token("opaque-token");
+        let printed = format!("{:?}", creds);
+        assert!(!printed.contains("hunter2") && !printed.contains("opaque-token"));
+
+        let token = AuthToken::new(1, vec![Role::User], Utc::now(), b"sig-bytes".to_vec())
+            .with_session_key(b"session-key".to_vec());
+        let printed = format!("{:?}", token);
+        assert!(printed.contains("[REDACTED]"));
+        assert!(!printed.contains(&format!("{:?}", b"sig-bytes".to_vec())));
+
+        let config = crate::types::ConnectionConfig::default().with_credentials("admin", "hunter2");
+        assert!(!format!("{:?}", config).contains("hunter2"));
+    }
+
     #[test]
     fn test_credentials_builder() {
         let creds = Credentials::with_username("admin")
@@ -304,8 +961,8 @@ mod tests {
             .with_token("existing_token");
 
         assert_eq!(creds.username, "admin");
-        assert_eq!(creds.password, Some("password".to_string()));
-        assert_eq!(creds.token, Some("existing_token".to_string()));
+        assert_eq!(creds.password, Some("password".into()));
+        assert_eq!(creds.token, Some("existing_token".into()));
     }
 
     #[test]
@@ -377,6 +1034,19 @@ mod tests {
     }
 
     // AuthenticationManager Tests
+    async fn manager_for(
+        server: &MockServer,
+        creds: Credentials,
+        ttl: std::time::Duration,
+    ) -> AuthenticationManager {
+        let connection_manager = Arc::new(ConnectionManager::new(server.config()));
+        AuthenticationManager::new(creds, ttl).with_connection_manager(connection_manager)
+    }
+
+    fn mock_credentials() -> Credentials {
+        Credentials::new(MockServer::USERNAME, MockServer::PASSWORD)
+    }
+
     #[tokio::test]
     async fn test_authentication_manager_creation() {
         let creds = Credentials::new("admin", "password");
@@ -388,22 +1058,102 @@ mod tests {
 
     #[tokio::test]
     async fn test_authentication_manager_authenticate() {
+        let server = MockServer::start().await.unwrap();
+        let user_id = server.add_user("admin", "password", vec![Role::Admin, Role::User]);
         let creds = Credentials::new("admin", "password");
-        let manager = AuthenticationManager::new(creds, std::time::Duration::from_secs(3600));
+        let manager = manager_for(&server, creds, std::time::Duration::from_secs(3600)).await;
 
         let token = manager.authenticate().await.unwrap();
-        assert_eq!(token.user_id, 1);
+        assert_eq!(token.user_id, user_id);
+        assert_eq!(token.roles, vec![Role::Admin, Role::User]);
         assert!(!token.is_expired());
 
         // Verify token is stored
         let stored_token = manager.get_token().await;
         assert!(stored_token.is_some());
+        assert_eq!(server.active_tokens().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_authentication_manager_rejects_bad_password() {
+        let server = MockServer::start().await.unwrap();
+        let creds = Credentials::new(MockServer::USERNAME, "wrong");
+        let manager = manager_for(&server, creds, std::time::Duration::from_secs(3600)).await;
+
+        let error = manager.authenticate().await.unwrap_err();
+        assert!(matches!(error, DatabaseError::InvalidCredentials));
+        assert!(manager.get_token().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_password_sent_only_as_scram_proof() {
+        let server = MockServer::start().await.unwrap();
+        let manager = manager_for(
+            &server,
+            mock_credentials(),
+            std::time::Duration::from_secs(3600),
+        )
+        .await;
+
+        manager.authenticate().await.unwrap();
+        let requests = server.auth_requests();
+        assert!(matches!(
+            requests.as_slice(),
+            [
+                AuthRequest::ScramClientFirst { .. },
+                AuthRequest::ScramClientFinal { .. }
+            ]
+        ));
+        assert!(!format!("{:?}", requests).contains(&format!("\"{}\"", MockServer::PASSWORD)));
+    }
+
+    #[tokio::test]
+    async fn test_password_policy_without_scram() {
+        let features = MockServer::DEFAULT_FEATURES
+            .into_iter()
+            .filter(|feature| *feature != Feature::ScramSha256)
+            .collect();
+        let server = MockServer::start_with_features(features).await.unwrap();
+
+        // The default policy refuses to send the password at all
+        let manager = manager_for(
+            &server,
+            mock_credentials(),
+            std::time::Duration::from_secs(3600),
+        )
+        .await;
+        let error = manager.authenticate().await.unwrap_err();
+        assert!(matches!(error, DatabaseError::AuthenticationFailed { .. }));
+        assert!(server.auth_requests().is_empty());
+
+        // Falling back to a plaintext login must be allowed explicitly
+        let manager = manager.with_password_policy(PasswordPolicy::AllowPlaintext);
+        manager.authenticate().await.unwrap();
+        assert!(matches!(
+            server.auth_requests().as_slice(),
+            [AuthRequest::Login { .. }]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_authentication_manager_requires_connection_manager() {
+        let manager =
+            AuthenticationManager::new(mock_credentials(), std::time::Duration::from_secs(3600));
+        assert!(matches!(
+            manager.authenticate().await,
+            Err(DatabaseError::InternalError { .. })
+        ));
     }
 
     #[tokio::test]
     async fn test_authentication_manager_get_valid_token() {
-        let creds = Credentials::new("admin", "password");
-        let manager = AuthenticationManager::new(creds, std::time::Duration::from_secs(3600));
+        let server = MockServer::start().await.unwrap();
+        let manager = manager_for(
+            &server,
+            mock_credentials(),
+            std::time::Duration::from_secs(3600),
+        )
+        .await;
 
         // First call should authenticate
         let token1 = manager.get_valid_token().await.unwrap();
@@ -411,13 +1161,20 @@ mod tests {
 
         // Second call should return cached token
         let token2 = manager.get_valid_token().await.unwrap();
-        assert_eq!(token1.user_id, token2.user_id);
+        assert_eq!(token1.signature, token2.signature);
+        // Only the two messages of the initial SCRAM exchange were sent
+        assert_eq!(server.auth_requests().len(), 2);
     }
 
     #[tokio::test]
     async fn test_authentication_manager_refresh_token() {
-        let creds = Credentials::new("admin", "password");
-        let manager = AuthenticationManager::new(creds, std::time::Duration::from_secs(3600));
+        let server = MockServer::start().await.unwrap();
+        let manager = manager_for(
+            &server,
+            mock_credentials(),
+            std::time::Duration::from_secs(3600),
+        )
+        .await;
 
         // Authenticate first
         let token1 = manager.authenticate().await.unwrap();
@@ -426,12 +1183,126 @@ mod tests {
         let token2 = manager.refresh_token().await.unwrap();
         assert_eq!(token1.user_id, token2.user_id);
         assert!(token2.expiration > token1.expiration);
+        assert_ne!(token1.signature, token2.signature);
+
+        // The server only honors the new token
+        let active = server.active_tokens();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].signature, token2.signature);
+        assert!(matches!(
+            server.auth_requests().last(),
+            Some(AuthRequest::Refresh { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pooled_connections_sign_frames_and_rekey_on_refresh() {
+        let server = MockServer::start().await.unwrap();
+        let config = server.config().with_integrity(IntegrityConfig::new());
+        let connection_manager = Arc::new(ConnectionManager::new(config));
+        let manager =
+            AuthenticationManager::new(mock_credentials(), std::time::Duration::from_secs(3600))
+                .with_connection_manager(Arc::clone(&connection_manager));
+        let token1 = manager.authenticate().await.unwrap();
+
+        // Both the login connection and one opened afterwards are bound
+        let mut first = connection_manager.get_connection().await.unwrap();
+        let mut second = connection_manager.get_connection().await.unwrap();
+        for conn in [&mut first, &mut second] {
+            let conn = conn.connection_mut();
+            assert!(conn.is_message_authentication_enabled());
+            assert_eq!(conn.auth_token().unwrap().signature, token1.signature);
+            let pong = conn
+                .send_request(MessageType::Ping, Vec::new(), 1000)
+                .await
+                .unwrap();
+            assert_eq!(pong.message_type, MessageType::Pong);
+        }
+        connection_manager.return_connection(first).await;
+        connection_manager.return_connection(second).await;
+
+        // After a refresh, connections are bound to the new session when
+        // handed out, and the server verifies frames with the new keys
+        let token2 = manager.refresh_token().await.unwrap();
+        for _ in 0..2 {
+            let mut conn = connection_manager.get_connection().await.unwrap();
+            assert_eq!(
+                conn.connection_mut().auth_token().unwrap().signature,
+                token2.signature
+            );
+            let pong = conn
+                .connection_mut()
+                .send_request(MessageType::Ping, Vec::new(), 1000)
+                .await
+                .unwrap();
+            assert_eq!(pong.message_type, MessageType::Pong);
+            connection_manager.return_connection(conn).await;
+        }
+
+        let binds = server
+            .auth_requests()
+            .into_iter()
+            .filter(|request| matches!(request, AuthRequest::Bind { .. }))
+            .count();
+        assert_eq!(binds, 4);
+    }
+
+    #[tokio::test]
+    async fn test_revoked_session_does_not_block_connections_for_login() {
+        let server = MockServer::start().await.unwrap();
+        let config = server.config().with_integrity(IntegrityConfig::new());
+        let connection_manager = Arc::new(ConnectionManager::new(config));
+        let manager =
+            AuthenticationManager::new(mock_credentials(), std::time::Duration::from_secs(3600))
+                .with_connection_manager(Arc::clone(&connection_manager));
+        manager.authenticate().await.unwrap();
+        let held = connection_manager.get_connection().await.unwrap();
+        server.revoke_tokens();
+
+        // A new connection cannot be bound to the revoked session
+        let mut conn = connection_manager.get_connection().await.unwrap();
+        assert!(!conn.connection_mut().is_message_authentication_enabled());
+
+        // It still carries the login that replaces the session
+        let response = manager
+            .send_authenticated(
+                conn.connection_mut(),
+                |token| Request::Execute {
+                    sql: "CREATE TABLE t (a INT)".to_string(),
+                    params: Vec::new(),
+                    prepared_statement_id: None,
+                    transaction_id: None,
+                    auth_token: Some(token.signature.clone()),
+                },
+                1000,
+            )
+            .await;
+        assert!(response.is_ok(), "{:?}", response);
+        assert!(conn.connection_mut().is_message_authentication_enabled());
+
+        // The connection bound to the revoked session is re-keyed when
+        // handed out again
+        connection_manager.return_connection(held).await;
+        let mut held = connection_manager.get_connection().await.unwrap();
+        let token = manager.get_token().await.unwrap();
+        let held = held.connection_mut();
+        assert_eq!(held.auth_token().unwrap().signature, token.signature);
+        let pong = held
+            .send_request(MessageType::Ping, Vec::new(), 1000)
+            .await
+            .unwrap();
+        assert_eq!(pong.message_type, MessageType::Pong);
     }
 
     #[tokio::test]
     async fn test_authentication_manager_logout() {
-        let creds = Credentials::new("admin", "password");
-        let manager = AuthenticationManager::new(creds, std::time::Duration::from_secs(3600));
+        let server = MockServer::start().await.unwrap();
+        let manager = manager_for(
+            &server,
+            mock_credentials(),
+            std::time::Duration::from_secs(3600),
+        )
+        .await;
 
         // Authenticate first
         manager.authenticate().await.unwrap();
@@ -440,6 +1311,182 @@ mod tests {
         // Logout
         manager.logout().await.unwrap();
         assert!(manager.get_token().await.is_none());
+        assert!(server.active_tokens().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_callers_share_one_reauthentication() {
+        let server = MockServer::start().await.unwrap();
+        let manager = Arc::new(
+            manager_for(
+                &server,
+                mock_credentials(),
+                std::time::Duration::from_secs(3600),
+            )
+            .await,
+        );
+        manager.authenticate().await.unwrap();
+        if let Some(token) = manager.token.write().await.as_mut() {
+            token.expiration = Utc::now() - chrono::Duration::seconds(1);
+        }
+        server.clear_received();
+
+        let callers: Vec<_> = (0..8)
+            .map(|_| {
+                let manager = Arc::clone(&manager);
+                tokio::spawn(async move { manager.get_valid_token().await })
+            })
+            .collect();
+        let mut signatures = Vec::new();
+        for caller in callers {
+            signatures.push(caller.await.unwrap().unwrap().signature);
+        }
+
+        signatures.dedup();
+        assert_eq!(signatures.len(), 1);
+        // One SCRAM exchange for all callers
+        assert_eq!(server.auth_requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresher_renews_before_expiry() {
+        let server = MockServer::start().await.unwrap();
+        let manager = Arc::new(
+            manager_for(
+                &server,
+                mock_credentials(),
+                std::time::Duration::from_secs(2),
+            )
+            .await,
+        );
+        let token1 = manager.authenticate().await.unwrap();
+
+        let refresher =
+            manager.start_refresher(TokenRefreshConfig::default().with_refresh_fraction(0.25));
+        tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
+
+        let token2 = manager.get_token().await.unwrap();
+        assert_ne!(token1.signature, token2.signature);
+        assert!(matches!(
+            server.auth_requests().last(),
+            Some(AuthRequest::Refresh { .. })
+        ));
+        assert!(!refresher.is_stopped());
+
+        // Nothing is left to renew once logged out
+        manager.logout().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(600)).await;
+        assert!(refresher.is_stopped());
+        assert!(manager.get_token().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_refresher_backs_off_on_tokens_issued_due() {
+        let server = MockServer::start().await.unwrap();
+        let manager =
+            Arc::new(manager_for(&server, mock_credentials(), std::time::Duration::ZERO).await);
+        manager.authenticate().await.unwrap();
+        server.clear_received();
+
+        let _refresher =
+            manager.start_refresher(TokenRefreshConfig::default().with_retry_backoff(100, 100));
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+        // Every renewal counts as failed, so they are spaced by the backoff.
+        // The tokens are already expired, so each renewal logs in again.
+        let renewals = server
+            .auth_requests()
+            .iter()
+            .filter(|request| matches!(request, AuthRequest::ScramClientFinal { .. }))
+            .count();
+        assert!((1..=10).contains(&renewals), "{} renewals", renewals);
+    }
+
+    #[tokio::test]
+    async fn test_reauthentication_uses_rotated_credentials() {
+        let server = MockServer::start().await.unwrap();
+        server.add_user("app", "first", vec![Role::User]);
+        let path = std::env::temp_dir().join(format!("qdb-auth-rotation-{}", std::process::id()));
+        std::fs::write(&path, "username=app\npassword=first\n").unwrap();
+
+        let manager = AuthenticationManager::from_provider(
+            Arc::new(crate::credentials::FileCredentialProvider::new(&path)),
+            std::time::Duration::from_secs(3600),
+        )
+        .with_connection_manager(Arc::new(ConnectionManager::new(server.config())));
+        let token1 = manager.authenticate().await.unwrap();
+
+        // The secret manager rotates the password on both sides
+        server.add_user("app", "rotated", vec![Role::User]);
+        std::fs::write(&path, "username=app\npassword=rotated\n").unwrap();
+        if let Some(token) = manager.token.write().await.as_mut() {
+            token.expiration = Utc::now() - chrono::Duration::seconds(1);
+        }
+
+        let token2 = manager.get_valid_token().await.unwrap();
+        assert_ne!(token1.signature, token2.signature);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_verified_tokens_carry_claims() {
+        let server = MockServer::start().await.unwrap();
+        let manager = manager_for(
+            &server,
+            mock_credentials(),
+            std::time::Duration::from_secs(3600),
+        )
+        .await
+        .with_token_verifier(server.token_verifier());
+
+        let token = manager.authenticate().await.unwrap();
+        let claims = token.claims.clone().expect("verified token has claims");
+        assert_eq!(claims.username, MockServer::USERNAME);
+        assert_eq!(claims.issuer, MockServer::TOKEN_ISSUER);
+        assert_eq!(claims.roles, token.roles);
+        assert!(claims.permissions.contains(&Permission::ManageUsers));
+        assert!(token.signed_token().is_some());
+
+        // Refreshed tokens are verified as well
+        let refreshed = manager.refresh_token().await.unwrap();
+        assert!(refreshed.claims.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tokens_from_another_issuer_are_rejected() {
+        let server = MockServer::start().await.unwrap();
+        let impostor = crate::token::TokenSigner::new(&[1; 32], MockServer::TOKEN_ISSUER);
+        let manager = manager_for(
+            &server,
+            mock_credentials(),
+            std::time::Duration::from_secs(3600),
+        )
+        .await
+        .with_token_verifier(impostor.verifier());
+
+        let error = manager.authenticate().await.unwrap_err();
+        assert!(matches!(error, DatabaseError::InvalidToken { .. }));
+        assert!(manager.get_token().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_of_revoked_token_fails() {
+        let server = MockServer::start().await.unwrap();
+        let manager = manager_for(
+            &server,
+            mock_credentials(),
+            std::time::Duration::from_secs(3600),
+        )
+        .await;
+        let token = manager.authenticate().await.unwrap();
+
+        // Revoke the token behind the manager's back
+        let other = manager.clone();
+        other.logout().await.unwrap();
+        *manager.token.write().await = Some(token);
+
+        let error = manager.refresh_token().await.unwrap_err();
+        assert!(matches!(error, DatabaseError::AuthenticationFailed { .. }));
     }
 }
 
@@ -447,8 +1494,30 @@ mod tests {
 #[cfg(test)]
 mod property_tests {
     use super::*;
+    use crate::testing::MockServer;
     use proptest::prelude::*;
 
+    // Starts a mock server accepting the credentials and a manager using it
+    async fn manager_for(
+        creds: Credentials,
+        ttl: std::time::Duration,
+    ) -> (MockServer, AuthenticationManager) {
+        let server = MockServer::start().await.unwrap();
+        server.add_user(
+            &creds.username,
+            creds
+                .password
+                .as_ref()
+                .map(SecretString::expose)
+                .unwrap_or_default(),
+            vec![Role::User],
+        );
+        let connection_manager = Arc::new(ConnectionManager::new(server.config()));
+        let manager =
+            AuthenticationManager::new(creds, ttl).with_connection_manager(connection_manager);
+        (server, manager)
+    }
+
     // Strategy for generating valid usernames
     fn username_strategy() -> impl Strategy<Value = String> {
         "[a-z]{3,20}"
@@ -512,7 +1581,7 @@ mod property_tests {
             creds in credentials_strategy(),
         ) {
             tokio::runtime::Runtime::new().unwrap().block_on(async {
-                let manager = AuthenticationManager::new(creds, std::time::Duration::from_secs(3600));
+                let (_server, manager) = manager_for(creds, std::time::Duration::from_secs(3600)).await;
 
                 // Authenticate to get a token
                 let token = manager.authenticate().await.unwrap();
@@ -543,7 +1612,7 @@ mod property_tests {
         ) {
             tokio::runtime::Runtime::new().unwrap().block_on(async {
                 // Create manager with very short TTL (1 second)
-                let manager = AuthenticationManager::new(creds, std::time::Duration::from_secs(1));
+                let (_server, manager) = manager_for(creds, std::time::Duration::from_secs(1)).await;
 
                 // Authenticate to get initial token
                 let token1 = manager.authenticate().await.unwrap();
@@ -579,7 +1648,7 @@ mod property_tests {
             creds in credentials_strategy(),
         ) {
             tokio::runtime::Runtime::new().unwrap().block_on(async {
-                let manager = AuthenticationManager::new(creds, std::time::Duration::from_secs(3600));
+                let (_server, manager) = manager_for(creds, std::time::Duration::from_secs(3600)).await;
 
                 // Authenticate to get a token
                 manager.authenticate().await.unwrap();
@@ -610,7 +1679,7 @@ mod property_tests {
             ttl_seconds in 60u64..3600u64, // TTL between 1 minute and 1 hour
         ) {
             tokio::runtime::Runtime::new().unwrap().block_on(async {
-                let manager = AuthenticationManager::new(creds, std::time::Duration::from_secs(ttl_seconds));
+                let (_server, manager) = manager_for(creds, std::time::Duration::from_secs(ttl_seconds)).await;
 
                 // Authenticate to get a token
                 let token = manager.authenticate().await.unwrap();
@@ -0,0 +1,50 @@
+//! Offline decoder for wire-traffic captures
+//!
+//! Prints every record of the given capture files, decoding payloads into
+//! typed requests and responses where possible. Rotated files should be
+//! passed oldest first:
+//!
+//! ```text
+//! qdb-capture traffic.qdcap.2 traffic.qdcap.1 traffic.qdcap
+//! ```
+
+use q_distributed_db_client::CaptureReader;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() || paths.iter().any(|p| p == "-h" || p == "--help") {
+        eprintln!("Usage: qdb-capture <capture-file>...");
+        return ExitCode::from(2);
+    }
+
+    let mut failed = false;
+    for path in &paths {
+        let reader = match CaptureReader::open(path) {
+            Ok(reader) => reader,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                failed = true;
+                continue;
+            }
+        };
+
+        for (index, record) in reader.enumerate() {
+            match record.and_then(|record| record.describe()) {
+                Ok(description) => println!("{}\n", description),
+                Err(e) => {
+                    // The rest of the file cannot be framed after a bad record
+                    eprintln!("{}: record {}: {}", path, index, e);
+                    failed = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
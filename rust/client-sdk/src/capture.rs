@@ -0,0 +1,741 @@
+//! Wire-traffic capture module for Q-Distributed-Database Client SDK
+//!
+//! This module records messages sent and received by connections to rotating
+//! capture files, and reads them back for offline decoding.
+//!
+//! A capture file starts with the 6-byte header `"QDCAP"` followed by the
+//! format version, then holds a sequence of records encoded as
+//! `[record_len: u32 BE][bincode CaptureRecord]`. Each record stores the
+//! message as an uncompressed legacy frame, independent of the wire format
+//! that was negotiated on the connection.
+//!
+//! Credentials never reach the file: authentication payloads are dropped, and
+//! tokens and passwords are blanked out of data requests. Records are written
+//! by a background thread so capturing does not block the connection.
+
+use crate::error::{DatabaseError, ServerError};
+use crate::protocol::{
+    AdminRequest, Message, MessageCodec, MessageType, Request, Response,
+    DEFAULT_MAX_REASSEMBLED_SIZE,
+};
+use crate::secret::{SecretBytes, SecretString};
+use crate::transaction::{TransactionRequest, TransactionResponse};
+use crate::types::{CaptureConfig, FeatureNegotiation, NodeId, Timestamp, UserUpdate};
+use crate::Result;
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread;
+
+/// Magic bytes and format version at the start of every capture file
+pub const CAPTURE_HEADER: [u8; 6] = *b"QDCAP\x01";
+
+/// Largest message a capture record can hold
+const MAX_CAPTURED_MESSAGE_SIZE: usize = DEFAULT_MAX_REASSEMBLED_SIZE;
+
+/// Number of payload bytes shown for payloads that cannot be decoded
+const HEX_PREVIEW_LEN: usize = 64;
+
+/// Records waiting for the writer thread before new ones are dropped
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Direction of a captured message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptureDirection {
+    /// Sent by the client
+    Sent,
+    /// Received from the server
+    Received,
+}
+
+/// A captured message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRecord {
+    /// When the message was captured, in milliseconds since the Unix epoch
+    pub captured_at: Timestamp,
+    /// Whether the message was sent or received
+    pub direction: CaptureDirection,
+    /// Node the connection was talking to
+    pub node_id: NodeId,
+    /// Original payload length if the payload was redacted
+    pub redacted_len: Option<u64>,
+    /// The message, encoded as an uncompressed legacy frame
+    pub frame: Vec<u8>,
+}
+
+impl CaptureRecord {
+    /// Creates a record for a message, dropping its payload if `redact` is set
+    ///
+    /// Without `redact`, tokens and passwords are still removed: payloads of
+    /// authentication messages are dropped, and secrets in data requests are
+    /// replaced by empty values. Data requests that cannot be decoded are
+    /// dropped as well.
+    pub fn new(
+        direction: CaptureDirection,
+        node_id: NodeId,
+        message: &Message,
+        redact: bool,
+    ) -> Result<Self> {
+        let payload = if redact {
+            None
+        } else {
+            without_secrets(message, direction)
+        };
+        let redacted_len = match payload {
+            Some(_) => None,
+            None => Some(message.payload.len() as u64),
+        };
+        let captured = Message::new(
+            message.sender,
+            message.recipient,
+            message.sequence_number,
+            message.timestamp,
+            message.message_type.clone(),
+            payload.unwrap_or_default(),
+        );
+        let frame = codec().encode(&captured)?;
+
+        Ok(Self {
+            captured_at: Utc::now().timestamp_millis(),
+            direction,
+            node_id,
+            redacted_len,
+            frame,
+        })
+    }
+
+    /// Decodes the captured message
+    pub fn message(&self) -> Result<Message> {
+        codec().decode(&self.frame)
+    }
+
+    /// Formats the record with its payload decoded into typed requests and
+    /// responses where possible
+    pub fn describe(&self) -> Result<String> {
+        let message = self.message()?;
+        let captured_at = Utc
+            .timestamp_millis_opt(self.captured_at)
+            .single()
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| self.captured_at.to_string());
+        let arrow = match self.direction {
+            CaptureDirection::Sent => "->",
+            CaptureDirection::Received => "<-",
+        };
+
+        let payload = match self.redacted_len {
+            Some(len) => format!("<{} payload bytes redacted>", len),
+            None => describe_payload(&message, self.direction),
+        };
+
+        Ok(format!(
+            "{} {} node {} {:?} seq={}\n{}",
+            captured_at,
+            arrow,
+            self.node_id,
+            message.message_type,
+            message.sequence_number,
+            payload
+        ))
+    }
+}
+
+/// Returns the payload of a message with tokens and passwords removed, or
+/// `None` if the payload must not be captured at all
+fn without_secrets(message: &Message, direction: CaptureDirection) -> Option<Vec<u8>> {
+    match (&message.message_type, direction) {
+        // Credentials, tokens and session secrets in both directions
+        (MessageType::Auth, _) => None,
+        (MessageType::Data, CaptureDirection::Sent) => {
+            let request = Request::from_payload(&message.payload).ok()?;
+            redact_request(request).to_payload().ok()
+        }
+        _ => Some(message.payload.clone()),
+    }
+}
+
+/// Replaces the tokens and passwords of a request with empty values
+fn redact_request(mut request: Request) -> Request {
+    match &mut request {
+        Request::Execute {
+            auth_token: Some(token),
+            ..
+        }
+        | Request::Query {
+            auth_token: Some(token),
+            ..
+        }
+        | Request::Prepare {
+            auth_token: Some(token),
+            ..
+        }
+        | Request::Batch {
+            auth_token: Some(token),
+            ..
+        }
+        | Request::AuthenticatedAdmin {
+            auth_token: Some(token),
+            ..
+        } => *token = SecretBytes::new(Vec::new()),
+        _ => {}
+    }
+    let admin = match &mut request {
+        Request::Admin(admin) | Request::AuthenticatedAdmin { request: admin, .. } => Some(admin),
+        _ => None,
+    };
+    if let Some(
+        AdminRequest::CreateUser { password, .. }
+        | AdminRequest::UpdateUser {
+            update:
+                UserUpdate {
+                    password: Some(password),
+                    ..
+                },
+            ..
+        },
+    ) = admin
+    {
+        *password = SecretString::new("");
+    }
+    request
+}
+
+/// Formats a payload as the typed value its message type and direction carry
+fn describe_payload(message: &Message, direction: CaptureDirection) -> String {
+    let payload = &message.payload;
+    if payload.is_empty() {
+        return "<empty payload>".to_string();
+    }
+
+    let described = match (&message.message_type, direction) {
+        (MessageType::Data, CaptureDirection::Sent) => {
+            Request::from_payload(payload).map(|r| format!("{:#?}", r))
+        }
+        (MessageType::Data, CaptureDirection::Received) => {
+            Response::from_payload(payload).map(|r| format!("{:#?}", r))
+        }
+        (MessageType::Transaction, CaptureDirection::Sent) => decode::<TransactionRequest>(payload),
+        (MessageType::Transaction, CaptureDirection::Received) => {
+            decode::<TransactionResponse>(payload)
+        }
+        (MessageType::Error, _) => ServerError::from_payload(payload).map(|e| format!("{:#?}", e)),
+        (MessageType::FeatureNegotiation, _) => decode::<FeatureNegotiation>(payload),
+        _ => Err(DatabaseError::SerializationError {
+            message: "No typed payload for message type".to_string(),
+        }),
+    };
+
+    described.unwrap_or_else(|_| {
+        let preview: String = payload
+            .iter()
+            .take(HEX_PREVIEW_LEN)
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let ellipsis = if payload.len() > HEX_PREVIEW_LEN {
+            "..."
+        } else {
+            ""
+        };
+        format!("<{} bytes: {}{}>", payload.len(), preview, ellipsis)
+    })
+}
+
+/// Decodes a bincode payload and formats it
+fn decode<T: for<'de> Deserialize<'de> + fmt::Debug>(payload: &[u8]) -> Result<String> {
+    bincode::deserialize::<T>(payload)
+        .map(|value| format!("{:#?}", value))
+        .map_err(|e| DatabaseError::SerializationError {
+            message: e.to_string(),
+        })
+}
+
+/// Codec used for the frames stored in capture records
+fn codec() -> MessageCodec {
+    MessageCodec::with_settings(MAX_CAPTURED_MESSAGE_SIZE, false, 0)
+}
+
+fn io_error(action: &str, path: &Path, e: std::io::Error) -> DatabaseError {
+    DatabaseError::InternalError {
+        component: "capture".to_string(),
+        details: format!("Failed to {} {}: {}", action, path.display(), e),
+    }
+}
+
+/// Destination for captured messages
+///
+/// Records are handed to a background thread that writes and flushes them in
+/// batches; if it falls `QUEUE_CAPACITY` records behind, further records are
+/// dropped rather than slowing down the connection. Cloning a sink shares the
+/// thread and file, so one sink can be used by every connection of a client.
+/// The thread exits once every clone has been dropped.
+#[derive(Clone)]
+pub struct CaptureSink {
+    /// Queue of the writer thread
+    queue: SyncSender<Command>,
+    /// Path of the active capture file
+    path: PathBuf,
+    /// Whether payloads are dropped from every record
+    redact_payloads: bool,
+}
+
+/// Work for the writer thread
+enum Command {
+    /// Append a record
+    Write(CaptureRecord),
+    /// Flush the file and report the result
+    Flush(SyncSender<Result<()>>),
+}
+
+impl CaptureSink {
+    /// Opens a capture file
+    ///
+    /// An existing non-empty file at the path is rotated first, so every file
+    /// starts with a capture header.
+    pub fn open(config: CaptureConfig) -> Result<Self> {
+        let path = config.path.clone();
+        let redact_payloads = config.redact_payloads;
+        let writer = CaptureWriter::open(config)?;
+
+        let (queue, commands) = mpsc::sync_channel(QUEUE_CAPACITY);
+        thread::Builder::new()
+            .name("qdb-capture".to_string())
+            .spawn(move || writer.run(commands))
+            .map_err(|e| io_error("start writer for", &path, e))?;
+
+        Ok(Self {
+            queue,
+            path,
+            redact_payloads,
+        })
+    }
+
+    /// Records a message
+    ///
+    /// The record is written in the background; fails if the writer has
+    /// stopped or is too far behind.
+    pub fn record(
+        &self,
+        direction: CaptureDirection,
+        node_id: NodeId,
+        message: &Message,
+    ) -> Result<()> {
+        let record = CaptureRecord::new(direction, node_id, message, self.redact_payloads)?;
+        self.queue
+            .try_send(Command::Write(record))
+            .map_err(|e| DatabaseError::InternalError {
+                component: "capture".to_string(),
+                details: match e {
+                    TrySendError::Full(_) => "Capture queue full, record dropped".to_string(),
+                    TrySendError::Disconnected(_) => "Capture writer stopped".to_string(),
+                },
+            })
+    }
+
+    /// Waits until every record recorded so far has been written and flushed
+    ///
+    /// Blocks the calling thread.
+    pub fn flush(&self) -> Result<()> {
+        let stopped = || DatabaseError::InternalError {
+            component: "capture".to_string(),
+            details: "Capture writer stopped".to_string(),
+        };
+
+        let (done, result) = mpsc::sync_channel(1);
+        self.queue
+            .send(Command::Flush(done))
+            .map_err(|_| stopped())?;
+        result.recv().map_err(|_| stopped())?
+    }
+}
+
+impl fmt::Debug for CaptureSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CaptureSink")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Writes records to the active capture file and rotates it
+struct CaptureWriter {
+    /// Capture settings
+    config: CaptureConfig,
+    /// The active capture file
+    file: BufWriter<File>,
+    /// Bytes written to the active file
+    written: u64,
+}
+
+impl CaptureWriter {
+    fn open(config: CaptureConfig) -> Result<Self> {
+        let existing = fs::metadata(&config.path).map(|m| m.len()).unwrap_or(0);
+        if existing > 0 {
+            rotate(&config)?;
+        }
+
+        let file = create(&config.path)?;
+        Ok(Self {
+            config,
+            file,
+            written: CAPTURE_HEADER.len() as u64,
+        })
+    }
+
+    /// Writes queued records until every sink has been dropped, flushing
+    /// whenever the queue runs empty
+    fn run(mut self, commands: Receiver<Command>) {
+        while let Ok(command) = commands.recv() {
+            for command in std::iter::once(command).chain(commands.try_iter()) {
+                match command {
+                    Command::Write(record) => {
+                        if let Err(e) = self.write(&record) {
+                            tracing::warn!("Failed to capture message: {}", e);
+                        }
+                    }
+                    Command::Flush(done) => {
+                        let _ = done.send(self.flush());
+                    }
+                }
+            }
+
+            if let Err(e) = self.flush() {
+                tracing::warn!("Failed to flush capture: {}", e);
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.file
+            .flush()
+            .map_err(|e| io_error("flush", &self.config.path, e))
+    }
+
+    fn write(&mut self, record: &CaptureRecord) -> Result<()> {
+        let encoded =
+            bincode::serialize(record).map_err(|e| DatabaseError::SerializationError {
+                message: format!("Failed to encode capture record: {}", e),
+            })?;
+        let size = 4 + encoded.len() as u64;
+
+        if self.written > CAPTURE_HEADER.len() as u64
+            && self.written + size > self.config.max_file_size
+        {
+            self.flush()?;
+            rotate(&self.config)?;
+            self.file = create(&self.config.path)?;
+            self.written = CAPTURE_HEADER.len() as u64;
+        }
+
+        let path = &self.config.path;
+        self.file
+            .write_all(&(encoded.len() as u32).to_be_bytes())
+            .and_then(|_| self.file.write_all(&encoded))
+            .map_err(|e| io_error("write", path, e))?;
+        self.written += size;
+        Ok(())
+    }
+}
+
+/// Creates a capture file and writes its header
+fn create(path: &Path) -> Result<BufWriter<File>> {
+    let mut file = File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| io_error("create", path, e))?;
+    file.write_all(&CAPTURE_HEADER)
+        .and_then(|_| file.flush())
+        .map_err(|e| io_error("write", path, e))?;
+    Ok(file)
+}
+
+/// Returns the path of the `index`-th rotated capture file
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Shifts `path` to `path.1`, `path.1` to `path.2` and so on, dropping the
+/// oldest file beyond `max_files`
+fn rotate(config: &CaptureConfig) -> Result<()> {
+    let path = &config.path;
+    if config.max_files <= 1 {
+        return fs::remove_file(path).map_err(|e| io_error("remove", path, e));
+    }
+
+    let oldest = rotated_path(path, config.max_files - 1);
+    if oldest.exists() {
+        fs::remove_file(&oldest).map_err(|e| io_error("remove", &oldest, e))?;
+    }
+
+    for index in (1..config.max_files - 1).rev() {
+        let from = rotated_path(path, index);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, index + 1))
+                .map_err(|e| io_error("rotate", &from, e))?;
+        }
+    }
+
+    fs::rename(path, rotated_path(path, 1)).map_err(|e| io_error("rotate", path, e))
+}
+
+/// Reads records from a capture file
+pub struct CaptureReader<R> {
+    reader: R,
+}
+
+impl CaptureReader<BufReader<File>> {
+    /// Opens a capture file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| io_error("open", path, e))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Creates a reader, validating the capture header
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; CAPTURE_HEADER.len()];
+        reader
+            .read_exact(&mut header)
+            .map_err(|e| DatabaseError::SerializationError {
+                message: format!("Failed to read capture header: {}", e),
+            })?;
+
+        if header != CAPTURE_HEADER {
+            return Err(DatabaseError::SerializationError {
+                message: "Not a capture file".to_string(),
+            });
+        }
+
+        Ok(Self { reader })
+    }
+
+    fn read_record(&mut self) -> Result<Option<CaptureRecord>> {
+        let mut length_bytes = [0u8; 4];
+        match self.reader.read_exact(&mut length_bytes) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => {
+                return Err(DatabaseError::SerializationError {
+                    message: format!("Failed to read capture record: {}", e),
+                })
+            }
+        }
+
+        let length = u32::from_be_bytes(length_bytes) as usize;
+        if length > MAX_CAPTURED_MESSAGE_SIZE + 1024 {
+            return Err(DatabaseError::MessageTooLarge {
+                size: length,
+                max_size: MAX_CAPTURED_MESSAGE_SIZE,
+            });
+        }
+
+        let mut data = vec![0u8; length];
+        self.reader
+            .read_exact(&mut data)
+            .map_err(|e| DatabaseError::SerializationError {
+                message: format!("Truncated capture record: {}", e),
+            })?;
+
+        bincode::deserialize(&data)
+            .map(Some)
+            .map_err(|e| DatabaseError::SerializationError {
+                message: format!("Failed to decode capture record: {}", e),
+            })
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Value;
+
+    fn capture_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("qdb-capture-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("traffic.qdcap")
+    }
+
+    fn query_message() -> Message {
+        let request = Request::Query {
+            sql: "SELECT * FROM users WHERE id = ?".to_string(),
+            params: vec![Value::Int(1)],
+            prepared_statement_id: None,
+            transaction_id: None,
+            auth_token: None,
+            streaming: false,
+        };
+        Message::new(
+            0,
+            3,
+            7,
+            1704067200000,
+            MessageType::Data,
+            request.to_payload().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_capture_round_trip() {
+        let path = capture_path("round-trip");
+        let sink = CaptureSink::open(CaptureConfig::new(&path)).unwrap();
+        sink.record(CaptureDirection::Sent, 3, &query_message())
+            .unwrap();
+        sink.flush().unwrap();
+
+        let records: Vec<_> = CaptureReader::open(&path)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].direction, CaptureDirection::Sent);
+        assert_eq!(records[0].node_id, 3);
+        assert_eq!(
+            records[0].message().unwrap().payload,
+            query_message().payload
+        );
+
+        let described = records[0].describe().unwrap();
+        assert!(described.contains("-> node 3 Data seq=7"));
+        assert!(described.contains("SELECT * FROM users WHERE id = ?"));
+    }
+
+    #[test]
+    fn test_capture_redacts_payloads() {
+        let path = capture_path("redact");
+        let sink =
+            CaptureSink::open(CaptureConfig::new(&path).with_redacted_payloads(true)).unwrap();
+        let message = query_message();
+        sink.record(CaptureDirection::Sent, 3, &message).unwrap();
+        sink.flush().unwrap();
+
+        let record = CaptureReader::open(&path).unwrap().next().unwrap().unwrap();
+        assert_eq!(record.redacted_len, Some(message.payload.len() as u64));
+        assert!(record.message().unwrap().payload.is_empty());
+        assert!(!record.describe().unwrap().contains("SELECT"));
+    }
+
+    #[test]
+    fn test_capture_always_redacts_auth_payloads() {
+        let path = capture_path("auth");
+        let sink = CaptureSink::open(CaptureConfig::new(&path)).unwrap();
+        let message = Message::new(0, 3, 1, 0, MessageType::Auth, b"secret".to_vec());
+        sink.record(CaptureDirection::Sent, 3, &message).unwrap();
+        sink.flush().unwrap();
+
+        let record = CaptureReader::open(&path).unwrap().next().unwrap().unwrap();
+        assert_eq!(record.redacted_len, Some(6));
+        assert!(record.message().unwrap().payload.is_empty());
+    }
+
+    #[test]
+    fn test_capture_removes_tokens_and_passwords() {
+        let path = capture_path("secrets");
+        let sink = CaptureSink::open(CaptureConfig::new(&path)).unwrap();
+        let requests = [
+            Request::Query {
+                sql: "SELECT 1".to_string(),
+                params: vec![],
+                prepared_statement_id: None,
+                transaction_id: None,
+                auth_token: Some(SecretBytes::new(b"token-secret".to_vec())),
+                streaming: false,
+            },
+            Request::AuthenticatedAdmin {
+                request: AdminRequest::CreateUser {
+                    username: "alice".to_string(),
+                    password: SecretString::new("create-secret"),
+                    roles: vec![],
+                },
+                auth_token: Some(SecretBytes::new(b"admin-token-secret".to_vec())),
+            },
+            Request::Admin(AdminRequest::UpdateUser {
+                user_id: 2,
+                update: UserUpdate {
+                    password: Some(SecretString::new("update-secret")),
+                    roles: None,
+                },
+            }),
+        ];
+        for request in &requests {
+            let message =
+                Message::new(0, 3, 1, 0, MessageType::Data, request.to_payload().unwrap());
+            sink.record(CaptureDirection::Sent, 3, &message).unwrap();
+        }
+        sink.flush().unwrap();
+
+        let contents = fs::read(&path).unwrap();
+        for secret in [
+            "token-secret",
+            "admin-token-secret",
+            "create-secret",
+            "update-secret",
+        ] {
+            assert!(
+                !contents
+                    .windows(secret.len())
+                    .any(|window| window == secret.as_bytes()),
+                "{} found in capture",
+                secret
+            );
+        }
+
+        // Everything else is kept
+        let described: Vec<_> = CaptureReader::open(&path)
+            .unwrap()
+            .map(|record| record.unwrap().describe().unwrap())
+            .collect();
+        assert!(described[0].contains("SELECT 1"));
+        assert!(described[1].contains("alice"));
+    }
+
+    #[test]
+    fn test_capture_rotates_files() {
+        let path = capture_path("rotate");
+        let config = CaptureConfig::new(&path)
+            .with_max_file_size(256)
+            .with_max_files(3);
+        let sink = CaptureSink::open(config).unwrap();
+        for _ in 0..10 {
+            sink.record(CaptureDirection::Sent, 3, &query_message())
+                .unwrap();
+        }
+        sink.flush().unwrap();
+
+        assert!(rotated_path(&path, 1).exists());
+        assert!(rotated_path(&path, 2).exists());
+        assert!(!rotated_path(&path, 3).exists());
+        for file in [path.clone(), rotated_path(&path, 1)] {
+            assert!(fs::metadata(&file).unwrap().len() <= 256);
+            assert!(CaptureReader::open(&file).unwrap().all(|r| r.is_ok()));
+        }
+    }
+
+    #[test]
+    fn test_capture_reader_rejects_other_files() {
+        assert!(CaptureReader::new(&b"not a capture"[..]).is_err());
+    }
+
+    #[test]
+    fn test_describe_undecodable_payload() {
+        let message = Message::new(3, 0, 1, 0, MessageType::Data, vec![0xFF; 100]);
+        let described = describe_payload(&message, CaptureDirection::Received);
+        assert!(described.starts_with("<100 bytes: ffff"));
+        assert!(described.ends_with("...>"));
+    }
+}
@@ -1,16 +1,20 @@
 //! Main client entry point for Q-Distributed-Database Client SDK
 
 use crate::admin_client::AdminClient;
-use crate::auth::{AuthenticationManager, Credentials};
+use crate::auth::{AuthToken, AuthenticationManager, Credentials, TokenRefresher};
 use crate::connection::{ConnectionManager, NodeHealth};
+use crate::credentials::{CredentialProvider, StaticCredentialProvider};
 use crate::data_client::DataClient;
 use crate::error::DatabaseError;
 use crate::metrics::{ClientMetrics, MetricsCollector};
-use crate::types::{ConnectionConfig, LogConfig, LogFormat, LogLevel, TracingConfig};
+use crate::types::{ConnectionConfig, LogConfig, LogFormat, LogLevel, Permission, TracingConfig};
 use crate::Result;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+/// Lifetime requested for tokens
+const TOKEN_TTL: std::time::Duration = std::time::Duration::from_secs(86400); // 24 hours
+
 /// Cluster health information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClusterHealth {
@@ -22,6 +26,30 @@ pub struct ClusterHealth {
     pub node_healths: Vec<NodeHealth>,
 }
 
+/// Identity of the user a `UserClient` acts as
+#[derive(Debug, Clone)]
+pub enum UserIdentity {
+    /// Credentials to log in with
+    Credentials(Credentials),
+    /// A token issued to the user, e.g. forwarded by a gateway
+    ///
+    /// With token refresh enabled, the token is refreshed before it expires,
+    /// but it cannot be replaced once it has expired or been revoked.
+    Token(AuthToken),
+}
+
+impl From<Credentials> for UserIdentity {
+    fn from(credentials: Credentials) -> Self {
+        UserIdentity::Credentials(credentials)
+    }
+}
+
+impl From<AuthToken> for UserIdentity {
+    fn from(token: AuthToken) -> Self {
+        UserIdentity::Token(token)
+    }
+}
+
 /// Main client for interacting with the database
 ///
 /// The Client is the main entry point for interacting with the q-distributed-database.
@@ -61,6 +89,8 @@ pub struct Client {
     admin_client: AdminClient,
     /// Metrics collector
     metrics: Arc<MetricsCollector>,
+    /// Background token renewal, if enabled
+    token_refresher: Option<Arc<TokenRefresher>>,
 }
 
 impl Client {
@@ -117,30 +147,43 @@ impl Client {
             Arc::new(ConnectionManager::new(config.clone()).with_metrics(Arc::clone(&metrics)));
 
         // 6. Create AuthenticationManager with credentials
-        let credentials = if let Some(password) = &config.password {
-            Credentials::new(config.username.clone(), password.clone())
-        } else if let Some(cert_data) = &config.certificate {
-            Credentials::with_username(config.username.clone()).with_certificate(
-                crate::auth::Certificate {
-                    data: cert_data.clone(),
-                },
-            )
-        } else {
-            return Err(DatabaseError::AuthenticationFailed {
-                reason: "No password or certificate provided".to_string(),
-            });
-        };
-
-        let auth_manager = Arc::new(AuthenticationManager::new(
-            credentials,
-            std::time::Duration::from_secs(86400), // 24 hours default TTL
+        let credential_provider: Arc<dyn CredentialProvider> =
+            if let Some(provider) = &config.credential_provider {
+                Arc::clone(provider)
+            } else if let Some(password) = &config.password {
+                Arc::new(StaticCredentialProvider::new(Credentials::new(
+                    config.username.clone(),
+                    password.clone(),
+                )))
+            } else if let Some(cert_data) = &config.certificate {
+                Arc::new(StaticCredentialProvider::new(
+                    Credentials::with_username(config.username.clone()).with_certificate(
+                        crate::auth::Certificate {
+                            data: cert_data.clone(),
+                        },
+                    ),
+                ))
+            } else {
+                return Err(DatabaseError::AuthenticationFailed {
+                    reason: "No password or certificate provided".to_string(),
+                });
+            };
+
+        let auth_manager = Arc::new(Self::auth_manager(
+            AuthenticationManager::from_provider(credential_provider, TOKEN_TTL),
+            &config,
+            &connection_manager,
         ));
 
-        // 7. Perform initial authentication
-        tracing::info!("Authenticating with username: {}", config.username);
+        // 7. Perform initial authentication over the first pooled connection
+        if config.credential_provider.is_some() {
+            tracing::info!("Authenticating with credentials from provider");
+        } else {
+            tracing::info!("Authenticating with username: {}", config.username);
+        }
         let start = std::time::Instant::now();
-        let conn = connection_manager.get_connection().await?;
-        match auth_manager.authenticate().await {
+        let mut conn = connection_manager.get_connection().await?;
+        match conn.connection_mut().authenticate(&auth_manager).await {
             Ok(_) => {
                 let latency = start.elapsed().as_millis() as f64;
                 metrics.record_auth_attempt(true, latency).await;
@@ -155,6 +198,11 @@ impl Client {
         }
         connection_manager.return_connection(conn).await;
 
+        let token_refresher = config
+            .token_refresh
+            .enabled
+            .then(|| Arc::new(auth_manager.start_refresher(config.token_refresh.clone())));
+
         // 8. Create DataClient with shared managers
         let data_client = DataClient::new(
             Arc::clone(&connection_manager),
@@ -175,9 +223,27 @@ impl Client {
             data_client,
             admin_client,
             metrics,
+            token_refresher,
         })
     }
 
+    /// Configures an authentication manager to reach the server through the
+    /// shared pool
+    fn auth_manager(
+        auth_manager: AuthenticationManager,
+        config: &ConnectionConfig,
+        connection_manager: &Arc<ConnectionManager>,
+    ) -> AuthenticationManager {
+        let auth_manager = auth_manager
+            .with_connection_manager(Arc::clone(connection_manager))
+            .with_password_policy(config.password_policy)
+            .with_permission_checks(config.permission_checks);
+        match &config.token_verifier {
+            Some(token_verifier) => auth_manager.with_token_verifier(token_verifier.clone()),
+            None => auth_manager,
+        }
+    }
+
     /// Initializes logging based on configuration
     fn initialize_logging(log_config: &LogConfig) {
         use tracing_subscriber::fmt::format::FmtSpan;
@@ -243,6 +309,94 @@ impl Client {
         &self.config
     }
 
+    /// Returns a valid authentication token, re-authenticating if necessary
+    ///
+    /// With a token verifier configured, the token carries its verified
+    /// claims. `AuthToken::signed_token` gives the token in a form that can be
+    /// forwarded as proof of identity.
+    pub async fn auth_token(&self) -> Result<AuthToken> {
+        self.auth_manager.get_valid_token().await
+    }
+
+    /// Returns the permissions of the authenticated user
+    ///
+    /// They are taken from the verified token claims when a token verifier is
+    /// configured, and derived from the user's roles otherwise. Useful to hide
+    /// actions the user cannot perform; the server still checks every request.
+    pub async fn effective_permissions(&self) -> Result<Vec<Permission>> {
+        Ok(self.auth_manager.get_valid_token().await?.permissions())
+    }
+
+    /// Returns a view of the client that acts as another user
+    ///
+    /// The view shares this client's connection pool and metrics, but attaches
+    /// the user's token to each request, so the server applies that user's
+    /// permissions and audits requests as theirs. With message authentication,
+    /// each request binds its connection to the user's session; the pool's
+    /// session stays this client's. With credentials, the user is logged in
+    /// before this returns; a token is verified if a token verifier is
+    /// configured. The user's token is renewed in the background like the
+    /// client's, until the last clone of the view is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let alice = client.as_user(Credentials::new("alice", password)).await?;
+    /// alice.data().query("SELECT * FROM orders").await?;
+    ///
+    /// let bob = client.as_user(forwarded_token).await?;
+    /// ```
+    pub async fn as_user(&self, identity: impl Into<UserIdentity>) -> Result<UserClient> {
+        let auth_manager = match identity.into() {
+            UserIdentity::Credentials(credentials) => {
+                tracing::debug!("Authenticating as user: {}", credentials.username);
+                let auth_manager = Self::auth_manager(
+                    AuthenticationManager::new(credentials, TOKEN_TTL).with_shared_pool(),
+                    &self.config,
+                    &self.connection_manager,
+                );
+
+                let start = std::time::Instant::now();
+                let result = auth_manager.authenticate().await;
+                let latency = start.elapsed().as_millis() as f64;
+                self.metrics
+                    .record_auth_attempt(result.is_ok(), latency)
+                    .await;
+                result?;
+                auth_manager
+            }
+            UserIdentity::Token(token) => {
+                let auth_manager = Self::auth_manager(
+                    AuthenticationManager::without_credentials(TOKEN_TTL).with_shared_pool(),
+                    &self.config,
+                    &self.connection_manager,
+                );
+                auth_manager.use_token(token).await?;
+                auth_manager
+            }
+        };
+        let auth_manager = Arc::new(auth_manager);
+        let token_refresher = self
+            .config
+            .token_refresh
+            .enabled
+            .then(|| Arc::new(auth_manager.start_refresher(self.config.token_refresh.clone())));
+
+        Ok(UserClient {
+            data_client: DataClient::new(
+                Arc::clone(&self.connection_manager),
+                Arc::clone(&auth_manager),
+                Arc::clone(&self.metrics),
+            ),
+            admin_client: AdminClient::new(
+                Arc::clone(&self.connection_manager),
+                Arc::clone(&auth_manager),
+            ),
+            auth_manager,
+            token_refresher,
+        })
+    }
+
     /// Returns the current metrics snapshot
     ///
     /// Provides comprehensive metrics including:
@@ -307,13 +461,16 @@ impl Client {
     pub async fn disconnect(self) -> Result<()> {
         tracing::info!("Disconnecting from database cluster");
 
+        // Stop renewing the token in every clone of the client
+        if let Some(token_refresher) = &self.token_refresher {
+            token_refresher.stop();
+        }
+
         // 1. Logout to invalidate token (best effort)
-        let conn = self.connection_manager.get_connection().await?;
         if let Err(e) = self.auth_manager.logout().await {
             tracing::warn!("Logout failed during disconnect: {}", e);
             eprintln!("Warning: logout failed during disconnect: {}", e);
         }
-        self.connection_manager.return_connection(conn).await;
 
         // 2. Close all connections in the pool
         tracing::debug!("Closing all connections");
@@ -324,6 +481,56 @@ impl Client {
     }
 }
 
+/// View of a `Client` acting as another user
+///
+/// Created by `Client::as_user`. Requests go through the client's connection
+/// pool with the user's token, and the view stays usable only as long as the
+/// client is connected.
+#[derive(Clone)]
+pub struct UserClient {
+    /// Authentication manager holding the user's token
+    auth_manager: Arc<AuthenticationManager>,
+    /// Data client for CRUD operations
+    data_client: DataClient,
+    /// Admin client for cluster and user management
+    admin_client: AdminClient,
+    /// Background renewal of the user's token, if enabled
+    token_refresher: Option<Arc<TokenRefresher>>,
+}
+
+impl UserClient {
+    /// Returns the data client acting as the user
+    pub fn data(&self) -> &DataClient {
+        &self.data_client
+    }
+
+    /// Returns the admin client acting as the user
+    pub fn admin(&self) -> &AdminClient {
+        &self.admin_client
+    }
+
+    /// Returns the user's token, renewing it if necessary
+    pub async fn auth_token(&self) -> Result<AuthToken> {
+        self.auth_manager.get_valid_token().await
+    }
+
+    /// Returns the permissions of the user, see
+    /// `Client::effective_permissions`
+    pub async fn effective_permissions(&self) -> Result<Vec<Permission>> {
+        Ok(self.auth_manager.get_valid_token().await?.permissions())
+    }
+
+    /// Logs the user out, invalidating their token
+    ///
+    /// The shared connection pool stays open.
+    pub async fn logout(self) -> Result<()> {
+        if let Some(token_refresher) = &self.token_refresher {
+            token_refresher.stop();
+        }
+        self.auth_manager.logout().await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3,13 +3,20 @@
 //! This module implements TCP connections, connection pooling, health monitoring,
 //! retry logic with exponential backoff, and graceful shutdown.
 
+use crate::auth::{AuthRequest, AuthResponse};
+use crate::capture::{CaptureDirection, CaptureSink};
 use crate::error::DatabaseError;
+use crate::integrity::FrameAuthenticator;
 use crate::metrics::MetricsCollector;
-use crate::protocol::{Message, MessageCodec, MessageType};
-use crate::types::{ConnectionConfig, NodeId, PoolConfig, Timestamp};
+use crate::protocol::{Message, MessageCodec, MessageType, Request, Response, WireFormat};
+use crate::secret::SecretBytes;
+use crate::transport::Transport;
+use crate::types::{
+    ConnectionConfig, Feature, IntegrityConfig, NodeId, PoolConfig, StatementId, Timestamp,
+};
 use crate::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::Future;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
@@ -115,10 +122,96 @@ impl NodeHealth {
     }
 }
 
+/// Statements prepared on one connection
+///
+/// Statement ids are only valid on the connection that prepared them, so
+/// every connection keeps its own cache. When full, the least recently used
+/// statement is evicted and must be closed on the server by the caller. A
+/// capacity of 0 disables the cache.
+#[derive(Debug)]
+pub struct StatementCache {
+    /// Maximum number of statements kept
+    capacity: usize,
+    /// Prepared statement ids and when they were last used, by SQL
+    entries: HashMap<String, (StatementId, u64)>,
+    /// Incremented on every lookup or insert
+    clock: u64,
+}
+
+impl StatementCache {
+    /// Creates an empty cache holding at most `capacity` statements
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Returns true if statements are kept at all
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Returns the id `sql` was prepared with, marking it as used
+    pub fn get(&mut self, sql: &str) -> Option<StatementId> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(sql).map(|(statement_id, last_used)| {
+            *last_used = clock;
+            *statement_id
+        })
+    }
+
+    /// Adds a statement, returning the id evicted to make room
+    ///
+    /// A disabled cache keeps nothing and hands the new id straight back.
+    pub fn insert(&mut self, sql: &str, statement_id: StatementId) -> Option<StatementId> {
+        if !self.is_enabled() {
+            return Some(statement_id);
+        }
+
+        self.clock += 1;
+        let mut evicted = None;
+        if !self.entries.contains_key(sql) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(sql, _)| sql.clone());
+            evicted = oldest
+                .and_then(|sql| self.entries.remove(&sql))
+                .map(|(id, _)| id);
+        }
+        self.entries
+            .insert(sql.to_string(), (statement_id, self.clock))
+            .map(|(replaced, _)| replaced)
+            .filter(|&replaced| replaced != statement_id)
+            .or(evicted)
+    }
+
+    /// Removes the statement prepared for `sql`, returning its id
+    pub fn remove(&mut self, sql: &str) -> Option<StatementId> {
+        self.entries
+            .remove(sql)
+            .map(|(statement_id, _)| statement_id)
+    }
+
+    /// Returns the number of cached statements
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no statements are cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
 /// A single TCP connection to a database node
 pub struct Connection {
-    /// TCP socket
-    socket: TcpStream,
+    /// Underlying byte stream, a TCP socket unless wrapped by a transport layer
+    socket: Box<dyn Transport>,
     /// Node identifier
     node_id: NodeId,
     /// Message codec for serialization
@@ -131,6 +224,20 @@ pub struct Connection {
     protocol: ProtocolType,
     /// Negotiated features
     negotiated_features: Vec<crate::types::Feature>,
+    /// Per-message authentication settings
+    integrity: IntegrityConfig,
+    /// Client nonce followed by the server nonce, agreed during negotiation
+    nonce: Vec<u8>,
+    /// Signs and verifies frames once message authentication is enabled
+    authenticator: Option<FrameAuthenticator>,
+    /// Records sent and received messages (optional)
+    capture: Option<CaptureSink>,
+    /// Statements prepared on this connection
+    statements: StatementCache,
+    /// Whether the server does not negotiate features and legacy framing is used
+    legacy: bool,
+    /// Whether a session binding awaits its answer
+    binding: bool,
 }
 
 impl Connection {
@@ -172,22 +279,68 @@ impl Connection {
         tracing::info!("Connected to {} (node {})", host, node_id);
 
         Ok(Self {
-            socket,
+            socket: Box::new(socket),
             node_id,
             codec: MessageCodec::new(),
             sequence_number: AtomicU64::new(0),
             auth_token: None,
             protocol: ProtocolType::TCP, // Default to TCP
             negotiated_features: Vec::new(),
+            integrity: IntegrityConfig::default(),
+            nonce: Vec::new(),
+            authenticator: None,
+            capture: None,
+            statements: StatementCache::new(ConnectionConfig::default().statement_cache_capacity),
+            legacy: false,
+            binding: false,
         })
     }
 
     /// Creates a new connection with compression settings
+    ///
+    /// If the server does not answer feature negotiation in time, the
+    /// connection is closed, since a late reply would be read as the answer
+    /// to the next request, and a new one is opened with legacy framing.
     pub async fn connect_with_config(
         host: &str,
         node_id: NodeId,
         config: &ConnectionConfig,
     ) -> Result<Self> {
+        let transport = Self::open_transport(host, node_id, config).await?;
+        match Self::from_transport(transport, node_id, config).await {
+            Err(DatabaseError::TimeoutError { .. }) => {
+                tracing::warn!(
+                    "Node {} did not answer feature negotiation within {}ms, reconnecting with legacy framing",
+                    node_id,
+                    config.timeout_ms
+                );
+                Self::connect_legacy(host, node_id, config).await
+            }
+            result => result,
+        }
+    }
+
+    /// Creates a new connection with legacy framing, without negotiating
+    /// features
+    ///
+    /// For servers known to predate feature negotiation.
+    pub async fn connect_legacy(
+        host: &str,
+        node_id: NodeId,
+        config: &ConnectionConfig,
+    ) -> Result<Self> {
+        let transport = Self::open_transport(host, node_id, config).await?;
+        let mut connection = Self::unnegotiated(transport, node_id, config);
+        connection.legacy = true;
+        Ok(connection)
+    }
+
+    /// Opens a socket to `host`, wrapped by the configured transport layer
+    async fn open_transport(
+        host: &str,
+        node_id: NodeId,
+        config: &ConnectionConfig,
+    ) -> Result<Box<dyn Transport>> {
         let socket = timeout(
             Duration::from_millis(config.timeout_ms),
             TcpStream::connect(host),
@@ -216,21 +369,82 @@ impl Connection {
                 details: format!("Failed to set TCP_NODELAY: {}", e),
             })?;
 
+        let mut transport: Box<dyn Transport> = Box::new(socket);
+        if let Some(layer) = &config.transport_layer {
+            transport = layer.wrap(node_id, transport);
+        }
+        Ok(transport)
+    }
+
+    /// Creates a connection over an already established transport
+    ///
+    /// Negotiates features like `connect_with_config`, except that a server
+    /// that does not answer in time fails with `TimeoutError`; the transport
+    /// is then out of step and must be dropped. The configured transport
+    /// layer is not applied.
+    pub async fn from_transport(
+        transport: Box<dyn Transport>,
+        node_id: NodeId,
+        config: &ConnectionConfig,
+    ) -> Result<Self> {
+        let mut connection = Self::unnegotiated(transport, node_id, config);
+
+        // Agree on the wire format and compression algorithms before any
+        // versioned or compressed frame is sent
+        let mut features = vec![
+            Feature::VersionedFraming,
+            Feature::FragmentedFrames,
+            Feature::ScramSha256,
+        ];
+        if config.compression_enabled {
+            features.extend(config.compression.features());
+        }
+        if config.integrity.enabled {
+            features.push(Feature::MessageAuthentication);
+        }
+        connection
+            .negotiate_features(features, config.timeout_ms)
+            .await?;
+
+        Ok(connection)
+    }
+
+    /// Creates a connection on legacy framing, before any negotiation
+    fn unnegotiated(
+        transport: Box<dyn Transport>,
+        node_id: NodeId,
+        config: &ConnectionConfig,
+    ) -> Self {
         // Create codec with compression settings
         let codec = MessageCodec::with_compression(
             config.compression_enabled,
             config.compression_threshold,
-        );
+        )
+        .with_compression_config(&config.compression)
+        .with_max_reassembled_size(config.max_reassembled_size);
 
-        Ok(Self {
-            socket,
+        Self {
+            socket: transport,
             node_id,
             codec,
             sequence_number: AtomicU64::new(0),
             auth_token: None,
             protocol: ProtocolType::TCP,
             negotiated_features: Vec::new(),
-        })
+            integrity: config.integrity.clone(),
+            nonce: Vec::new(),
+            authenticator: None,
+            capture: config.capture.clone(),
+            statements: StatementCache::new(config.statement_cache_capacity),
+            legacy: false,
+            binding: false,
+        }
+    }
+
+    /// Returns true if the server does not negotiate features, leaving the
+    /// connection on legacy framing
+    pub fn is_legacy(&self) -> bool {
+        self.legacy
     }
 
     /// Returns the node ID
@@ -238,19 +452,74 @@ impl Connection {
         self.node_id
     }
 
+    /// Returns the statements prepared on this connection
+    pub fn statements(&mut self) -> &mut StatementCache {
+        &mut self.statements
+    }
+
     /// Gets the next sequence number
     fn next_sequence_number(&self) -> u64 {
         self.sequence_number.fetch_add(1, Ordering::SeqCst)
     }
 
     /// Sends a message over the connection
+    ///
+    /// The frame carries an authentication tag once message authentication
+    /// is enabled.
     pub async fn send_message(&mut self, message: Message) -> Result<()> {
-        self.codec.write_message(&mut self.socket, &message).await
+        self.capture(CaptureDirection::Sent, &message);
+
+        match &self.authenticator {
+            Some(authenticator) => {
+                let envelope = authenticator.sign(message)?;
+                self.codec.write_envelope(&mut self.socket, &envelope).await
+            }
+            None => self.codec.write_message(&mut self.socket, &message).await,
+        }
     }
 
     /// Receives a message from the connection
+    ///
+    /// Once message authentication is enabled, frames with a missing or
+    /// invalid tag, a stale timestamp or a replayed sequence number are
+    /// rejected. The one exception is an untagged error answering a session
+    /// binding: a server that rejects the session never had the keys to
+    /// sign it.
     pub async fn receive_message(&mut self) -> Result<Message> {
-        self.codec.read_message(&mut self.socket).await
+        let message = match &mut self.authenticator {
+            Some(authenticator) => {
+                let envelope = self.codec.read_envelope(&mut self.socket).await?;
+                let rejected_binding = self.binding
+                    && envelope.message.message_type == MessageType::Error
+                    && envelope
+                        .extension(crate::integrity::MAC_EXTENSION)
+                        .is_none();
+                if !rejected_binding {
+                    authenticator.verify(&envelope)?;
+                }
+                envelope.message
+            }
+            None => self.codec.read_message(&mut self.socket).await?,
+        };
+
+        self.capture(CaptureDirection::Received, &message);
+        Ok(message)
+    }
+
+    /// Sets the capture recording this connection's messages
+    pub fn set_capture(&mut self, capture: Option<CaptureSink>) {
+        self.capture = capture;
+    }
+
+    /// Records a message to the capture, if any
+    ///
+    /// Capture failures are logged and never fail the request.
+    fn capture(&self, direction: CaptureDirection, message: &Message) {
+        if let Some(capture) = &self.capture {
+            if let Err(e) = capture.record(direction, self.node_id, message) {
+                tracing::warn!("Failed to capture message: {}", e);
+            }
+        }
     }
 
     /// Sends a request and waits for a response
@@ -284,16 +553,140 @@ impl Connection {
             })?
     }
 
+    /// Sends a data request and waits for its typed response
+    ///
+    /// Error frames and error responses are mapped to `DatabaseError` using
+    /// the SQL text and transaction of the request.
+    pub async fn send_data_request(
+        &mut self,
+        request: &Request,
+        timeout_ms: u64,
+    ) -> Result<Response> {
+        let payload = request.to_payload()?;
+        let sql = request.sql();
+        let transaction_id = request.transaction_id();
+
+        let message = self
+            .send_request(MessageType::Data, payload, timeout_ms)
+            .await?
+            .check_server_error(sql, transaction_id)?;
+
+        Response::from_payload(&message.payload)?.into_result(sql, transaction_id)
+    }
+
+    /// Sends an authentication request and waits for its response
+    ///
+    /// Rejected credentials and tokens are reported as error frames and
+    /// mapped to `InvalidCredentials` or `AuthenticationFailed`.
+    pub async fn send_auth_request(
+        &mut self,
+        request: &AuthRequest,
+        timeout_ms: u64,
+    ) -> Result<AuthResponse> {
+        let message = self
+            .send_request(MessageType::Auth, request.to_payload()?, timeout_ms)
+            .await?
+            .check_server_error(None, None)?;
+
+        AuthResponse::from_payload(&message.payload)
+    }
+
     /// Authenticates the connection with the given authentication manager
+    ///
+    /// The credentials are exchanged for a token over this connection.
     pub async fn authenticate(
         &mut self,
         auth_manager: &crate::auth::AuthenticationManager,
     ) -> Result<()> {
-        let token = auth_manager.authenticate().await?;
-        self.auth_token = Some(token);
+        let token = auth_manager.authenticate_with(self).await?;
+        self.use_session(&token).await
+    }
+
+    /// Uses `token` for this connection
+    ///
+    /// With message authentication configured, frames are signed with keys
+    /// derived from the token's session secret and this connection's nonces,
+    /// and the server is asked to do the same. Connections already using the
+    /// token are left as they are, so this can be called before every request
+    /// to re-key a connection after the token was refreshed.
+    pub async fn use_session(&mut self, token: &crate::auth::AuthToken) -> Result<()> {
+        if !self.integrity.enabled {
+            self.auth_token = Some(token.clone());
+            return Ok(());
+        }
+        if self
+            .auth_token
+            .as_ref()
+            .is_some_and(|current| current.signature == token.signature)
+        {
+            return Ok(());
+        }
+
+        let session_key = token
+            .session_key
+            .as_ref()
+            .map(SecretBytes::expose)
+            .ok_or_else(|| DatabaseError::AuthenticationFailed {
+                reason: "No session key for message authentication".to_string(),
+            })?;
+        self.enable_message_authentication(session_key)?;
+
+        // Signed with the new keys, proving the session secret to the server
+        let request = AuthRequest::Bind {
+            signature: token.signature.clone(),
+        };
+        self.binding = true;
+        let response = self
+            .send_auth_request(&request, crate::auth::AUTH_TIMEOUT_MS)
+            .await;
+        self.binding = false;
+        match response {
+            Ok(AuthResponse::Bound) => {
+                self.auth_token = Some(token.clone());
+                Ok(())
+            }
+            Ok(other) => {
+                self.authenticator = None;
+                Err(DatabaseError::AuthenticationFailed {
+                    reason: format!("Unexpected response to session binding: {:?}", other),
+                })
+            }
+            Err(e) => {
+                self.authenticator = None;
+                Err(e)
+            }
+        }
+    }
+
+    /// Starts signing and verifying every frame with keys derived from the
+    /// session secret and this connection's nonces
+    ///
+    /// Requires the versioned wire format and the `MessageAuthentication`
+    /// feature to have been negotiated. The server must be told to use the
+    /// same keys, which `use_session` does.
+    pub fn enable_message_authentication(&mut self, session_key: &[u8]) -> Result<()> {
+        if self.codec.wire_format() != WireFormat::Versioned
+            || !self.has_feature(&Feature::MessageAuthentication)
+            || self.nonce.is_empty()
+        {
+            return Err(DatabaseError::MessageAuthenticationFailed {
+                reason: "message authentication was not negotiated".to_string(),
+            });
+        }
+
+        self.authenticator = Some(FrameAuthenticator::client(
+            session_key,
+            &self.nonce,
+            &self.integrity,
+        ));
         Ok(())
     }
 
+    /// Returns true if frames are signed and verified
+    pub fn is_message_authentication_enabled(&self) -> bool {
+        self.authenticator.is_some()
+    }
+
     /// Sends an authenticated request
     ///
     /// Ensures the connection has a valid authentication token before sending.
@@ -316,7 +709,6 @@ impl Connection {
             });
         }
 
-        // Send the request (token would be included in the payload in a real implementation)
         self.send_request(message_type, payload, timeout_ms).await
     }
 
@@ -338,20 +730,25 @@ impl Connection {
     /// Negotiates features with the server
     ///
     /// Sends the client's supported features and receives the server's supported features.
-    /// Returns the intersection of both feature sets.
+    /// Returns the intersection of both feature sets. A server that answers
+    /// with an error is treated as supporting no features, leaving the
+    /// connection on legacy framing. A server that does not answer within
+    /// `timeout_ms` fails with `TimeoutError`, after which the connection
+    /// must be dropped.
     pub async fn negotiate_features(
         &mut self,
         client_features: Vec<crate::types::Feature>,
+        timeout_ms: u64,
     ) -> Result<Vec<crate::types::Feature>> {
-        use crate::types::{Feature, FeatureNegotiation};
+        use crate::types::{CompressionAlgorithm, FeatureNegotiation};
 
         // Send feature negotiation request
-        let request_payload = bincode::serialize(&FeatureNegotiation {
+        let client_nonce = crate::integrity::connection_nonce()?;
+        let request_payload = FeatureNegotiation {
             supported_features: client_features.clone(),
-        })
-        .map_err(|e| DatabaseError::SerializationError {
-            message: format!("Failed to serialize feature negotiation: {}", e),
-        })?;
+            nonce: client_nonce.clone(),
+        }
+        .to_payload()?;
 
         let seq = self.next_sequence_number();
         let timestamp = chrono::Utc::now().timestamp_millis();
@@ -368,14 +765,26 @@ impl Connection {
         self.send_message(request).await?;
 
         // Receive server's supported features
-        let response = self.receive_message().await?;
-
-        let server_features: FeatureNegotiation =
-            bincode::deserialize(&response.payload).map_err(|e| {
-                DatabaseError::SerializationError {
-                    message: format!("Failed to deserialize feature negotiation response: {}", e),
+        let response =
+            execute_with_timeout(self.receive_message(), timeout_ms, "feature_negotiation").await;
+        let server_features = match response {
+            Ok(response) if response.message_type == MessageType::FeatureNegotiation => {
+                FeatureNegotiation::from_payload(&response.payload)?
+            }
+            Ok(response) => {
+                tracing::warn!(
+                    "Node {} answered feature negotiation with {:?}, using legacy framing",
+                    self.node_id,
+                    response.message_type
+                );
+                self.legacy = true;
+                FeatureNegotiation {
+                    supported_features: Vec::new(),
+                    nonce: Vec::new(),
                 }
-            })?;
+            }
+            Err(e) => return Err(e),
+        };
 
         // Calculate intersection of features
         let negotiated: Vec<Feature> = client_features
@@ -385,11 +794,35 @@ impl Connection {
 
         // Store negotiated features
         self.negotiated_features = negotiated.clone();
+        self.nonce = if server_features.nonce.is_empty() {
+            Vec::new()
+        } else {
+            [client_nonce, server_features.nonce].concat()
+        };
+
+        // Keep only the compression algorithms both sides support
+        let algorithms: Vec<CompressionAlgorithm> = self
+            .codec
+            .compression_algorithms()
+            .iter()
+            .filter(|a| {
+                a.feature()
+                    .is_some_and(|f| self.negotiated_features.contains(&f))
+            })
+            .copied()
+            .collect();
+        self.codec.set_compression_algorithms(algorithms);
 
-        // Update codec compression based on negotiated features
-        if !self.negotiated_features.contains(&Feature::Compression) {
-            self.codec.compression_enabled = false;
+        if self
+            .negotiated_features
+            .contains(&Feature::VersionedFraming)
+        {
+            self.codec.set_wire_format(WireFormat::Versioned);
         }
+        self.codec.set_fragmentation_enabled(
+            self.negotiated_features
+                .contains(&Feature::FragmentedFrames),
+        );
 
         Ok(negotiated)
     }
@@ -483,6 +916,12 @@ pub struct ConnectionPool {
     hosts: Vec<String>,
     /// Connection timeout
     timeout_ms: u64,
+    /// Settings applied to each new connection (codec, compression)
+    connection_config: ConnectionConfig,
+    /// Token whose session connections are bound to, once authenticated
+    session: RwLock<Option<crate::auth::AuthToken>>,
+    /// Nodes found not to negotiate features, connected to with legacy framing
+    legacy_nodes: std::sync::Mutex<HashSet<NodeId>>,
 }
 
 impl ConnectionPool {
@@ -494,9 +933,26 @@ impl ConnectionPool {
             total_connections: AtomicU32::new(0),
             hosts,
             timeout_ms,
+            connection_config: ConnectionConfig {
+                timeout_ms,
+                ..Default::default()
+            },
+            session: RwLock::new(None),
+            legacy_nodes: std::sync::Mutex::new(HashSet::new()),
         }
     }
 
+    /// Sets the configuration used when opening new connections
+    ///
+    /// The hosts and timeout of the pool take precedence over those in `config`.
+    pub fn with_connection_config(mut self, config: ConnectionConfig) -> Self {
+        self.connection_config = ConnectionConfig {
+            timeout_ms: self.timeout_ms,
+            ..config
+        };
+        self
+    }
+
     /// Gets a connection from the pool or creates a new one
     pub async fn get_connection(&self) -> Result<PooledConnection> {
         // Try to get an available connection
@@ -509,8 +965,19 @@ impl ConnectionPool {
         });
 
         if let Some(mut conn) = available.pop_front() {
+            drop(available);
             conn.touch();
-            return Ok(conn);
+            // Re-key connections bound to a token that has since been refreshed.
+            // One that cannot be re-keyed may still be bound on the server, so
+            // a new connection takes its place.
+            match self.bind(conn.connection_mut()).await {
+                Ok(()) => return Ok(conn),
+                Err(e) => {
+                    tracing::warn!("Discarding pooled connection that failed to bind: {}", e);
+                    self.forget_connection();
+                }
+            }
+            return self.create_connection().await;
         }
 
         // Check if we can create a new connection
@@ -544,8 +1011,24 @@ impl ConnectionPool {
         let mut last_error = None;
         for (idx, host) in self.hosts.iter().enumerate() {
             let node_id = idx as NodeId + 1;
-            match Connection::connect(host, node_id, self.timeout_ms).await {
-                Ok(conn) => {
+            match self.connect(host, node_id).await {
+                Ok(mut conn) => {
+                    // A rejected session leaves the connection unbound, so it
+                    // can carry the login that replaces the session
+                    match self.bind(&mut conn).await {
+                        Ok(()) => {}
+                        Err(e) if e.is_auth_error() => {
+                            tracing::warn!(
+                                "Session rejected, leaving new connection unbound: {}",
+                                e
+                            );
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to bind connection to {}: {}", host, e);
+                            last_error = Some(e);
+                            continue;
+                        }
+                    }
                     self.total_connections.fetch_add(1, Ordering::SeqCst);
                     return Ok(PooledConnection::new(conn));
                 }
@@ -561,6 +1044,49 @@ impl ConnectionPool {
         }))
     }
 
+    /// Connects to a node, skipping feature negotiation on nodes that did
+    /// not answer it before
+    ///
+    /// Without this, every connection to an old server would wait out the
+    /// negotiation timeout. The decision lasts for the life of the pool.
+    async fn connect(&self, host: &str, node_id: NodeId) -> Result<Connection> {
+        let legacy = self
+            .legacy_nodes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&node_id);
+        if legacy {
+            return Connection::connect_legacy(host, node_id, &self.connection_config).await;
+        }
+
+        let conn = Connection::connect_with_config(host, node_id, &self.connection_config).await?;
+        if conn.is_legacy() {
+            self.legacy_nodes
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(node_id);
+        }
+        Ok(conn)
+    }
+
+    /// Sets the token whose session connections are bound to
+    ///
+    /// New connections are bound when they are created and idle ones when
+    /// they are next handed out, so that with message authentication every
+    /// connection signs its frames with keys of the current session.
+    pub async fn set_session(&self, token: Option<crate::auth::AuthToken>) {
+        *self.session.write().await = token;
+    }
+
+    /// Binds a connection to the current session, if any
+    async fn bind(&self, conn: &mut Connection) -> Result<()> {
+        let session = self.session.read().await.clone();
+        match session {
+            Some(token) => conn.use_session(&token).await,
+            None => Ok(()),
+        }
+    }
+
     /// Returns a connection to the pool
     pub async fn return_connection(&self, conn: PooledConnection) {
         // Check if connection is still valid
@@ -574,6 +1100,22 @@ impl ConnectionPool {
         available.push_back(conn);
     }
 
+    /// Frees the slot of a connection that was dropped instead of returned
+    ///
+    /// Used for connections left in an unknown state, such as one whose
+    /// response was abandoned part way.
+    pub fn forget_connection(&self) {
+        self.total_connections.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Removes every idle connection from the pool
+    ///
+    /// The connections still count towards the pool's total and must be
+    /// handed back with `return_connection`.
+    pub async fn take_idle_connections(&self) -> Vec<PooledConnection> {
+        self.available.lock().await.drain(..).collect()
+    }
+
     /// Gets the total number of connections
     pub fn total_connections(&self) -> u32 {
         self.total_connections.load(Ordering::SeqCst)
@@ -599,7 +1141,8 @@ impl ConnectionManager {
             config.hosts.clone(),
             config.pool_config.clone(),
             config.timeout_ms,
-        );
+        )
+        .with_connection_config(config.clone());
 
         Self {
             pool,
@@ -609,6 +1152,11 @@ impl ConnectionManager {
         }
     }
 
+    /// Returns the configuration connections are opened with
+    pub fn config(&self) -> &ConnectionConfig {
+        &self.config
+    }
+
     /// Sets the metrics collector
     pub fn with_metrics(mut self, metrics: Arc<MetricsCollector>) -> Self {
         self.metrics = metrics;
@@ -660,6 +1208,24 @@ impl ConnectionManager {
             .await;
     }
 
+    /// Sets the token whose session pooled connections are bound to
+    pub async fn set_session(&self, token: Option<crate::auth::AuthToken>) {
+        self.pool.set_session(token).await;
+    }
+
+    /// Frees the pool slot of a connection that was dropped instead of returned
+    pub fn forget_connection(&self) {
+        tracing::debug!("Discarding pooled connection");
+        self.pool.forget_connection();
+    }
+
+    /// Removes every idle connection from the pool
+    ///
+    /// Each connection must be handed back with `return_connection`.
+    pub async fn take_idle_connections(&self) -> Vec<PooledConnection> {
+        self.pool.take_idle_connections().await
+    }
+
     /// Performs health check on all nodes
     pub async fn health_check_all_nodes(&self) -> Result<Vec<NodeHealth>> {
         let mut results = Vec::new();
@@ -868,6 +1434,35 @@ mod tests {
         assert_eq!(health.consecutive_failures, 0);
     }
 
+    // StatementCache Tests
+    #[test]
+    fn test_statement_cache_evicts_least_recently_used() {
+        let mut cache = StatementCache::new(2);
+        assert!(cache.insert("a", 1).is_none());
+        assert!(cache.insert("b", 2).is_none());
+
+        // Using "a" leaves "b" as the least recently used
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.insert("c", 3), Some(2));
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.len(), 2);
+
+        // Re-preparing cached SQL replaces it, handing back the old id
+        assert_eq!(cache.insert("a", 4), Some(1));
+        assert_eq!(cache.get("a"), Some(4));
+        assert_eq!(cache.remove("c"), Some(3));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_statement_cache_with_zero_capacity_keeps_nothing() {
+        let mut cache = StatementCache::new(0);
+        assert!(!cache.is_enabled());
+        assert_eq!(cache.insert("a", 1), Some(1));
+        assert!(cache.get("a").is_none());
+        assert!(cache.is_empty());
+    }
+
     // PooledConnection Tests
     #[test]
     fn test_pooled_connection_idle_check() {
@@ -937,7 +1532,9 @@ mod tests {
 #[cfg(test)]
 mod property_tests {
     use super::*;
-    use crate::types::RetryConfig;
+    use crate::types::{
+        CompressionConfig, IntegrityConfig, PasswordPolicy, RetryConfig, TokenRefreshConfig,
+    };
     use proptest::prelude::*;
 
     // Strategy for generating valid configurations
@@ -953,14 +1550,25 @@ mod property_tests {
                 ConnectionConfig {
                     hosts,
                     username,
-                    password,
+                    password: password.map(crate::secret::SecretString::from),
                     certificate: None,
+                    credential_provider: None,
                     enable_tls,
                     timeout_ms,
                     pool_config: PoolConfig::default(),
                     retry_config: RetryConfig::default(),
                     compression_enabled: false,
                     compression_threshold: 1024,
+                    compression: CompressionConfig::default(),
+                    max_reassembled_size: 64 * 1024 * 1024,
+                    integrity: IntegrityConfig::default(),
+                    password_policy: PasswordPolicy::default(),
+                    token_refresh: TokenRefreshConfig::default(),
+                    token_verifier: None,
+                    permission_checks: false,
+                    statement_cache_capacity: 256,
+                    capture: None,
+                    transport_layer: None,
                     log_config: None,
                     tracing_config: None,
                 }
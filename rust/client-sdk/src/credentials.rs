@@ -0,0 +1,391 @@
+//! Credential providers for Q-Distributed-Database Client SDK
+//!
+//! An `AuthenticationManager` asks its `CredentialProvider` for credentials
+//! every time it logs in, so secrets rotated by a secret manager take effect
+//! at the next login without restarting the client. Tokens are refreshed
+//! without consulting the provider.
+//!
+//! File and command providers read credentials as `key=value` lines with the
+//! keys `username`, `password` and `token`. Blank lines and lines starting
+//! with `#` are ignored. The raw contents are zeroed once parsed.
+
+use crate::auth::Credentials;
+use crate::error::DatabaseError;
+use crate::Result;
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use zeroize::Zeroize;
+
+/// Future returned by `CredentialProvider::credentials`
+pub type CredentialsFuture<'a> = Pin<Box<dyn Future<Output = Result<Credentials>> + Send + 'a>>;
+
+/// Source of the credentials used to log in
+pub trait CredentialProvider: fmt::Debug + Send + Sync {
+    /// Returns the credentials to use for the next login
+    fn credentials(&self) -> CredentialsFuture<'_>;
+}
+
+/// Provides the same credentials for every login
+#[derive(Debug, Clone)]
+pub struct StaticCredentialProvider {
+    credentials: Credentials,
+}
+
+impl StaticCredentialProvider {
+    /// Creates a provider for fixed credentials
+    pub fn new(credentials: Credentials) -> Self {
+        Self { credentials }
+    }
+}
+
+impl CredentialProvider for StaticCredentialProvider {
+    fn credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(async move { Ok(self.credentials.clone()) })
+    }
+}
+
+/// Reads the username and password from environment variables at each login
+#[derive(Debug, Clone)]
+pub struct EnvCredentialProvider {
+    username_var: String,
+    password_var: String,
+}
+
+impl Default for EnvCredentialProvider {
+    /// Reads `QDB_USERNAME` and `QDB_PASSWORD`
+    fn default() -> Self {
+        Self::new("QDB_USERNAME", "QDB_PASSWORD")
+    }
+}
+
+impl EnvCredentialProvider {
+    /// Creates a provider reading the given variables
+    pub fn new(username_var: impl Into<String>, password_var: impl Into<String>) -> Self {
+        Self {
+            username_var: username_var.into(),
+            password_var: password_var.into(),
+        }
+    }
+
+    fn read(&self) -> Result<Credentials> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|e| DatabaseError::AuthenticationFailed {
+                reason: format!("Cannot read credentials from ${}: {}", name, e),
+            })
+        };
+        Ok(Credentials::new(
+            var(&self.username_var)?,
+            var(&self.password_var)?,
+        ))
+    }
+}
+
+impl CredentialProvider for EnvCredentialProvider {
+    fn credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(async move { self.read() })
+    }
+}
+
+/// Reads credentials from a file, re-reading it when it changes
+///
+/// The parsed credentials are cached until the file's modification time or
+/// length changes, which is how mounted secrets are rotated.
+#[derive(Debug)]
+pub struct FileCredentialProvider {
+    path: PathBuf,
+    /// Credentials parsed from the file, with the metadata they were read at
+    cache: Mutex<Option<(FileVersion, Credentials)>>,
+}
+
+/// Identifies the contents of a file without reading it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileVersion {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileCredentialProvider {
+    /// Creates a provider reading the given file
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            cache: Mutex::new(None),
+        }
+    }
+
+    async fn read(&self) -> Result<Credentials> {
+        let metadata = tokio::fs::metadata(&self.path)
+            .await
+            .map_err(|e| self.error(e))?;
+        let version = FileVersion {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        };
+
+        if let Some((cached, credentials)) = self.lock().as_ref() {
+            if *cached == version {
+                return Ok(credentials.clone());
+            }
+        }
+
+        let mut contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(|e| self.error(e))?;
+        let credentials = parse(&contents, &self.path.display().to_string());
+        contents.zeroize();
+        let credentials = credentials?;
+        *self.lock() = Some((version, credentials.clone()));
+        Ok(credentials)
+    }
+
+    fn error(&self, e: std::io::Error) -> DatabaseError {
+        DatabaseError::AuthenticationFailed {
+            reason: format!(
+                "Cannot read credentials from {}: {}",
+                self.path.display(),
+                e
+            ),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<(FileVersion, Credentials)>> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CredentialProvider for FileCredentialProvider {
+    fn credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(self.read())
+    }
+}
+
+/// Runs an external command at each login and reads credentials from its
+/// standard output
+///
+/// The command is run directly, not through a shell. It fails the login if it
+/// exits unsuccessfully or does not finish within the timeout (10 seconds by
+/// default).
+#[derive(Debug, Clone)]
+pub struct CommandCredentialProvider {
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+impl CommandCredentialProvider {
+    /// Creates a provider running the given program
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Sets the arguments passed to the program
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets how long the program may run
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn run(&self) -> Result<Credentials> {
+        let failed = |reason: String| DatabaseError::AuthenticationFailed {
+            reason: format!("Credential command `{}` {}", self.program, reason),
+        };
+
+        let output = tokio::process::Command::new(&self.program)
+            .args(&self.args)
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(self.timeout, output)
+            .await
+            .map_err(|_| failed(format!("timed out after {:?}", self.timeout)))?
+            .map_err(|e| failed(format!("could not be run: {}", e)))?;
+        if !output.status.success() {
+            return Err(failed(format!(
+                "failed with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let mut stdout = String::from_utf8(output.stdout).map_err(|e| {
+            e.into_bytes().zeroize();
+            failed("printed invalid UTF-8".to_string())
+        })?;
+        let credentials = parse(&stdout, &format!("command `{}`", self.program));
+        stdout.zeroize();
+        credentials
+    }
+}
+
+impl CredentialProvider for CommandCredentialProvider {
+    fn credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(self.run())
+    }
+}
+
+/// Parses `key=value` credential lines read from `source`
+fn parse(contents: &str, source: &str) -> Result<Credentials> {
+    let mut credentials = Credentials::with_username("");
+    for (index, line) in contents.lines().map(str::trim).enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = line.split_once('=').ok_or_else(|| invalid(source, index))?;
+        let value = value.trim();
+        match key.trim() {
+            "username" => credentials.username = value.to_string(),
+            "password" => credentials.password = Some(value.into()),
+            "token" => credentials.token = Some(value.into()),
+            _ => return Err(invalid(source, index)),
+        }
+    }
+
+    credentials.validate()?;
+    Ok(credentials)
+}
+
+fn invalid(source: &str, index: usize) -> DatabaseError {
+    // Only the line number is reported, so a malformed secret is never logged
+    DatabaseError::AuthenticationFailed {
+        reason: format!(
+            "Invalid credential entry on line {} of {}",
+            index + 1,
+            source
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret::SecretString;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("qdb-credentials-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_parse_credentials() {
+        let credentials = parse(
+            "# rotated daily\nusername = app\n\npassword=s3cr=t\n",
+            "test",
+        )
+        .unwrap();
+        assert_eq!(credentials.username, "app");
+        assert_eq!(
+            credentials.password.as_ref().map(SecretString::expose),
+            Some("s3cr=t")
+        );
+
+        assert!(parse("username=app\n", "test").is_err());
+        assert!(parse("username=app\npassword\n", "test").is_err());
+        assert!(parse("username=app\npasswd=x\n", "test").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_env_provider_reads_at_each_call() {
+        let provider = EnvCredentialProvider::new("QDB_TEST_ENV_USER", "QDB_TEST_ENV_PASSWORD");
+        assert!(provider.credentials().await.is_err());
+
+        std::env::set_var("QDB_TEST_ENV_USER", "app");
+        std::env::set_var("QDB_TEST_ENV_PASSWORD", "first");
+        assert_eq!(
+            provider
+                .credentials()
+                .await
+                .unwrap()
+                .password
+                .as_ref()
+                .map(SecretString::expose),
+            Some("first")
+        );
+
+        std::env::set_var("QDB_TEST_ENV_PASSWORD", "second");
+        assert_eq!(
+            provider
+                .credentials()
+                .await
+                .unwrap()
+                .password
+                .as_ref()
+                .map(SecretString::expose),
+            Some("second")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_provider_rereads_changed_file() {
+        let path = temp_path("file");
+        std::fs::write(&path, "username=app\npassword=first\n").unwrap();
+        let provider = FileCredentialProvider::new(&path);
+        assert_eq!(
+            provider
+                .credentials()
+                .await
+                .unwrap()
+                .password
+                .as_ref()
+                .map(SecretString::expose),
+            Some("first")
+        );
+
+        std::fs::write(&path, "username=app\npassword=rotated\n").unwrap();
+        assert_eq!(
+            provider
+                .credentials()
+                .await
+                .unwrap()
+                .password
+                .as_ref()
+                .map(SecretString::expose),
+            Some("rotated")
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            provider.credentials().await,
+            Err(DatabaseError::AuthenticationFailed { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_provider() {
+        let provider = CommandCredentialProvider::new("sh")
+            .with_args(["-c", "echo username=app; echo password=from-command"]);
+        let credentials = provider.credentials().await.unwrap();
+        assert_eq!(credentials.username, "app");
+        assert_eq!(
+            credentials.password.as_ref().map(SecretString::expose),
+            Some("from-command")
+        );
+
+        let failing =
+            CommandCredentialProvider::new("sh").with_args(["-c", "echo denied >&2; exit 3"]);
+        let error = failing.credentials().await.unwrap_err().to_string();
+        assert!(error.contains("denied"), "{}", error);
+
+        let slow = CommandCredentialProvider::new("sleep")
+            .with_args(["5"])
+            .with_timeout(Duration::from_millis(50));
+        assert!(slow.credentials().await.is_err());
+    }
+}
@@ -3,18 +3,33 @@
 //! This module implements the DataClient component that handles all CRUD
 //! (Create, Read, Update, Delete) operations on database tables.
 
-use crate::auth::AuthenticationManager;
-use crate::connection::{ConnectionManager, PooledConnection};
-use crate::error::DatabaseError;
+use crate::auth::{AuthToken, AuthenticationManager};
+use crate::connection::{execute_with_timeout, ConnectionManager, PooledConnection};
+use crate::error::{sqlstate, DatabaseError, ServerError};
+use crate::from_row::FromRow;
 use crate::metrics::MetricsCollector;
-use crate::protocol::{Message, MessageType};
+use crate::protocol::{BatchOperation, MessageType, Request, Response};
 use crate::result::{ColumnMetadata, QueryResult, Row};
-use crate::types::{StatementId, Value};
+use crate::row_serde;
+use crate::types::{Permission, StatementId, StreamId, Value};
 use crate::Result;
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::task::{Context, Poll};
+
+/// Default number of rows requested per stream fetch
+const DEFAULT_FETCH_SIZE: u32 = 1000;
+
+/// Default number of batches buffered ahead of a stream's consumer
+const DEFAULT_READ_AHEAD: u32 = 1;
+
+/// Default time allowed to cancel a stream before its connection is discarded
+const DEFAULT_DRAIN_TIMEOUT_MS: u64 = 1000;
 
 /// Result of an execute operation (INSERT, UPDATE, DELETE)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,88 +41,326 @@ pub struct ExecuteResult {
 }
 
 /// Prepared statement
+///
+/// Run it with `DataClient::execute_prepared` or `DataClient::query_prepared`
+/// from any connection: each connection prepares the SQL the first time it
+/// runs it and keeps the id it was given.
 #[derive(Debug, Clone)]
 pub struct PreparedStatement {
-    /// Statement identifier
-    pub statement_id: StatementId,
     /// Original SQL
     pub sql: String,
     /// Number of parameters
     pub param_count: usize,
 }
 
+/// A fetch in flight, which hands the connection back with its response
+type FetchFuture = Pin<Box<dyn Future<Output = (PooledConnection, Result<Response>)> + Send>>;
+
 /// Result stream for streaming large result sets
+///
+/// Implements `futures::Stream`. Rows are pulled from the server in batches
+/// of `fetch_size` with `Request::Fetch`, and the next batch is requested
+/// while up to `read_ahead` batches are still buffered. Nothing more is
+/// fetched until the consumer catches up, so a slow consumer buffers at most
+/// `fetch_size * (read_ahead + 1)` rows.
+///
+/// The connection goes back to the pool once the last row is read. A stream
+/// cancelled or dropped early first waits for any fetch in flight and
+/// cancels the stream on the server; if that takes longer than the drain
+/// timeout, the connection is discarded instead.
+///
+/// ```ignore
+/// use futures::StreamExt;
+///
+/// let mut stream = client.data().query_stream("SELECT * FROM events").await?;
+/// while let Some(row) = stream.next().await {
+///     let row = row?;
+/// }
+/// ```
 pub struct ResultStream {
-    /// Connection
-    connection: PooledConnection,
+    /// Connection, held by the fetch future while one is in flight
+    connection: Option<PooledConnection>,
+    /// Pool the connection is returned to
+    connection_manager: Arc<ConnectionManager>,
+    /// Server-side stream identifier
+    stream_id: StreamId,
     /// Column metadata
     columns: Arc<Vec<ColumnMetadata>>,
-    /// Whether the stream is finished
+    /// Rows fetched but not yet returned
+    buffer: VecDeque<Vec<Value>>,
+    /// Number of rows requested per fetch
+    fetch_size: u32,
+    /// Number of batches buffered ahead of the consumer
+    read_ahead: u32,
+    /// Fetch in flight
+    pending: Option<FetchFuture>,
+    /// Whether the server has sent the last batch
     finished: bool,
+    /// Time allowed to cancel the stream on the server
+    drain_timeout_ms: u64,
+    /// Time allowed for each fetch, the client's request timeout
+    timeout_ms: u64,
 }
 
 impl ResultStream {
     /// Creates a new result stream
-    fn new(connection: PooledConnection, columns: Arc<Vec<ColumnMetadata>>) -> Self {
+    fn new(
+        connection: PooledConnection,
+        connection_manager: Arc<ConnectionManager>,
+        stream_id: StreamId,
+        columns: Arc<Vec<ColumnMetadata>>,
+    ) -> Self {
+        let timeout_ms = connection_manager.config().timeout_ms;
         Self {
-            connection,
+            connection: Some(connection),
+            connection_manager,
+            stream_id,
             columns,
+            buffer: VecDeque::new(),
+            fetch_size: DEFAULT_FETCH_SIZE,
+            read_ahead: DEFAULT_READ_AHEAD,
+            pending: None,
             finished: false,
+            drain_timeout_ms: DEFAULT_DRAIN_TIMEOUT_MS,
+            timeout_ms,
         }
     }
 
-    /// Fetches the next row from the stream
-    pub async fn next(&mut self) -> Result<Option<Row>> {
-        if self.finished {
-            return Ok(None);
+    /// Cancels the stream and returns its connection to the pool
+    ///
+    /// Rows not yet read are discarded. Dropping the stream does the same in
+    /// the background.
+    pub async fn cancel(mut self) -> Result<()> {
+        match self.take_cleanup() {
+            Some(cleanup) => cleanup.await,
+            None => Ok(()),
         }
+    }
 
-        // Receive next message from server
-        let message = self.connection.connection_mut().receive_message().await?;
-
-        match message.message_type {
-            MessageType::Data => {
-                // Parse row data
-                let values: Vec<Value> = bincode::deserialize(&message.payload).map_err(|e| {
-                    DatabaseError::SerializationError {
-                        message: format!("Failed to deserialize row data: {}", e),
-                    }
-                })?;
-                Ok(Some(Row::new(self.columns.clone(), values)))
+    /// Takes the connection out of the stream with the work that frees it
+    ///
+    /// Returns `None` once the connection has been released.
+    fn take_cleanup(&mut self) -> Option<impl Future<Output = Result<()>> + Send + 'static> {
+        if self.connection.is_none() && self.pending.is_none() {
+            return None;
+        }
+        let connection = self.connection.take();
+        let pending = self.pending.take();
+        let connection_manager = self.connection_manager.clone();
+        let stream_id = self.stream_id;
+        let finished = self.finished;
+        let drain_timeout_ms = self.drain_timeout_ms;
+        let timeout_ms = self.timeout_ms;
+
+        Some(async move {
+            let drained = execute_with_timeout(
+                drain(connection, pending, stream_id, finished, timeout_ms),
+                drain_timeout_ms,
+                "drain_result_stream",
+            )
+            .await;
+            match drained {
+                Ok(connection) => {
+                    connection_manager.return_connection(connection).await;
+                    Ok(())
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Discarding connection of result stream {}: {}",
+                        stream_id,
+                        e
+                    );
+                    connection_manager.forget_connection();
+                    Err(e)
+                }
             }
-            MessageType::Ack => {
-                // End of stream
-                self.finished = true;
-                Ok(None)
+        })
+    }
+
+    /// Releases the connection in the background
+    fn release(&mut self) {
+        let Some(cleanup) = self.take_cleanup() else {
+            return;
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(cleanup);
             }
-            MessageType::Error => {
-                let error: String = bincode::deserialize(&message.payload).map_err(|e| {
-                    DatabaseError::SerializationError {
-                        message: format!("Failed to deserialize error: {}", e),
-                    }
-                })?;
-                Err(DatabaseError::InternalError {
-                    component: "ResultStream".to_string(),
-                    details: error,
-                })
+            Err(_) => {
+                // Without a runtime the connection cannot be drained
+                drop(cleanup);
+                self.connection_manager.forget_connection();
             }
-            _ => Err(DatabaseError::InternalError {
-                component: "ResultStream".to_string(),
-                details: "Unexpected message type in stream".to_string(),
-            }),
         }
     }
 
+    /// Requests the next batch of rows from the server
+    fn start_fetch(&mut self) {
+        let Some(mut connection) = self.connection.take() else {
+            return;
+        };
+        let request = Request::Fetch {
+            stream_id: self.stream_id,
+            max_rows: self.fetch_size,
+        };
+        let timeout_ms = self.timeout_ms;
+
+        self.pending = Some(Box::pin(async move {
+            let response = connection
+                .connection_mut()
+                .send_data_request(&request, timeout_ms)
+                .await;
+            (connection, response)
+        }));
+    }
+
+    /// Whether another batch should be requested
+    fn wants_fetch(&self) -> bool {
+        !self.finished
+            && self.pending.is_none()
+            && self.buffer.len() <= self.fetch_size as usize * self.read_ahead as usize
+    }
+
+    /// Sets the number of rows requested per fetch
+    pub fn set_fetch_size(&mut self, fetch_size: u32) {
+        self.fetch_size = fetch_size.max(1);
+    }
+
+    /// Sets how long cancelling may take before the connection is discarded
+    pub fn set_drain_timeout_ms(&mut self, timeout_ms: u64) {
+        self.drain_timeout_ms = timeout_ms;
+    }
+
+    /// Sets how many batches may be buffered ahead of the consumer
+    ///
+    /// With `0`, the next batch is only requested once the buffer is empty.
+    pub fn set_read_ahead(&mut self, batches: u32) {
+        self.read_ahead = batches;
+    }
+
+    /// Returns the number of rows fetched but not yet returned
+    pub fn buffered_rows(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns the server-side stream identifier
+    pub fn stream_id(&self) -> StreamId {
+        self.stream_id
+    }
+
     /// Returns the column metadata
     pub fn columns(&self) -> &[ColumnMetadata] {
         &self.columns
     }
+
+    /// Maps each row to `T` as it is read
+    ///
+    /// Dropping the typed stream releases the connection like dropping this one.
+    pub fn into_typed<T: FromRow>(self) -> impl Stream<Item = Result<T>> + Send + Unpin {
+        self.map(|row| row.and_then(|row| T::from_row(&row)))
+    }
+
+    /// Deserializes each row to `T` as it is read
+    pub fn into_deserialized<T: DeserializeOwned>(
+        self,
+    ) -> impl Stream<Item = Result<T>> + Send + Unpin {
+        self.map(|row| row.and_then(|row| row_serde::from_row(&row)))
+    }
 }
 
-/// Batch operation type
-#[derive(Debug, Clone, Serialize, Deserialize)]
-enum BatchOperation {
-    Execute { sql: String, params: Vec<Value> },
+impl Stream for ResultStream {
+    type Item = Result<Row>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(pending) = this.pending.as_mut() {
+                match pending.as_mut().poll(cx) {
+                    Poll::Ready((connection, response)) => {
+                        this.pending = None;
+                        this.connection = Some(connection);
+                        match response {
+                            Ok(Response::StreamRows { rows, done }) => {
+                                this.buffer.extend(rows);
+                                this.finished = done;
+                            }
+                            response => {
+                                // The connection may be out of step with the server,
+                                // and the stream may still be open there
+                                this.finished = true;
+                                this.connection = None;
+                                this.connection_manager.forget_connection();
+                                let error = match response {
+                                    Ok(other) => other.unexpected("ResultStream"),
+                                    Err(e) => e,
+                                };
+                                return Poll::Ready(Some(Err(error)));
+                            }
+                        }
+                    }
+                    Poll::Pending if this.buffer.is_empty() => return Poll::Pending,
+                    Poll::Pending => {}
+                }
+            }
+
+            // Poll a newly started fetch right away so it can make progress
+            if this.wants_fetch() {
+                this.start_fetch();
+                continue;
+            }
+
+            let row = this.buffer.pop_front();
+            if row.is_none() {
+                this.release();
+            }
+            return Poll::Ready(row.map(|values| Ok(Row::new(this.columns.clone(), values))));
+        }
+    }
+}
+
+impl Drop for ResultStream {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Brings a stream's connection back in step with the server
+///
+/// Waits for the response to a fetch in flight, then cancels the stream
+/// unless the server already sent its last batch. The cancel may take up to
+/// `timeout_ms`.
+async fn drain(
+    connection: Option<PooledConnection>,
+    pending: Option<FetchFuture>,
+    stream_id: StreamId,
+    mut finished: bool,
+    timeout_ms: u64,
+) -> Result<PooledConnection> {
+    let mut connection = match pending {
+        Some(pending) => {
+            let (connection, response) = pending.await;
+            if let Response::StreamRows { done: true, .. } = response? {
+                finished = true;
+            }
+            connection
+        }
+        None => connection.ok_or_else(|| DatabaseError::InternalError {
+            component: "ResultStream".to_string(),
+            details: "Stream has no connection".to_string(),
+        })?,
+    };
+
+    if !finished {
+        let response = connection
+            .connection_mut()
+            .send_data_request(&Request::Cancel { stream_id }, timeout_ms)
+            .await?;
+        if !matches!(response, Response::Cancelled) {
+            return Err(response.unexpected("ResultStream"));
+        }
+    }
+
+    Ok(connection)
 }
 
 /// Batch context for executing multiple operations atomically
@@ -130,46 +383,33 @@ impl BatchContext {
     }
 
     /// Executes all operations in the batch atomically
+    ///
+    /// A batch rejected because of its token is replayed once after
+    /// re-authenticating.
     pub async fn execute(mut self) -> Result<Vec<ExecuteResult>> {
-        // Get valid auth token
-        let token = self.auth_manager.get_valid_token().await?;
-
-        // Build batch request
-        let request = BatchRequest {
-            operations: self.operations,
-            auth_token: Some(token.signature.clone()),
-        };
-
-        // Serialize request
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize batch request: {}", e),
-            })?;
+        for operation in &self.operations {
+            let BatchOperation::Execute { sql, .. } = operation;
+            check_statement(&self.auth_manager, sql).await?;
+        }
 
         // Send request and receive response
+        let operations = self.operations;
         let response = self
-            .connection
-            .connection_mut()
-            .send_request(MessageType::Data, payload, 10000)
+            .auth_manager
+            .send_authenticated(
+                self.connection.connection_mut(),
+                |token| Request::Batch {
+                    operations: operations.clone(),
+                    auth_token: Some(token.signature.clone()),
+                },
+                10000,
+            )
             .await?;
 
-        // Parse response
-        let batch_response: BatchResponse =
-            bincode::deserialize(&response.payload).map_err(|e| {
-                DatabaseError::SerializationError {
-                    message: format!("Failed to deserialize batch response: {}", e),
-                }
-            })?;
-
-        // Check for errors
-        if let Some(error) = batch_response.error {
-            return Err(DatabaseError::InternalError {
-                component: "BatchContext".to_string(),
-                details: error,
-            });
+        match response {
+            Response::BatchExecuted(results) => Ok(results),
+            other => Err(other.unexpected("BatchContext")),
         }
-
-        Ok(batch_response.results)
     }
 }
 
@@ -177,6 +417,8 @@ impl BatchContext {
 ///
 /// Provides methods for executing queries, updates, and managing transactions.
 /// All operations are async and use the connection pool for efficient resource usage.
+/// Requests rejected because the token expired or was revoked are replayed once
+/// after re-authenticating; transaction operations are not.
 ///
 /// # Example
 ///
@@ -201,8 +443,6 @@ pub struct DataClient {
     connection_manager: Arc<ConnectionManager>,
     /// Authentication manager
     auth_manager: Arc<AuthenticationManager>,
-    /// Prepared statement cache
-    prepared_statements: Arc<RwLock<HashMap<String, PreparedStatement>>>,
     /// Metrics collector
     metrics: Arc<MetricsCollector>,
 }
@@ -217,7 +457,6 @@ impl DataClient {
         Self {
             connection_manager,
             auth_manager,
-            prepared_statements: Arc::new(RwLock::new(HashMap::new())),
             metrics,
         }
     }
@@ -229,65 +468,66 @@ impl DataClient {
 
     /// Executes a SQL statement with parameters
     pub async fn execute_with_params(&self, sql: &str, params: &[Value]) -> Result<ExecuteResult> {
+        self.run_execute(sql, params, false).await
+    }
+
+    /// Executes a prepared statement with parameters
+    pub async fn execute_prepared(
+        &self,
+        statement: &PreparedStatement,
+        params: &[Value],
+    ) -> Result<ExecuteResult> {
+        self.run_execute(&statement.sql, params, true).await
+    }
+
+    /// Executes a statement, by prepared statement id if `prepared` is set
+    async fn run_execute(
+        &self,
+        sql: &str,
+        params: &[Value],
+        prepared: bool,
+    ) -> Result<ExecuteResult> {
         tracing::debug!("Executing SQL: {}", sql);
+        check_statement(&self.auth_manager, sql).await?;
         let start = std::time::Instant::now();
 
         // Get connection from pool
         let mut conn = self.connection_manager.get_connection().await?;
 
-        // Get valid auth token
-        let token = self.auth_manager.get_valid_token().await?;
-
-        // Build execute request
-        let request = ExecuteRequest {
-            sql: sql.to_string(),
-            params: params.to_vec(),
-            prepared_statement_id: None,
-            auth_token: Some(token.signature.clone()),
-        };
-
-        // Serialize request
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize execute request: {}", e),
-            })?;
-
         // Send request and receive response
-        let response = conn
-            .connection_mut()
-            .send_request(MessageType::Data, payload, 5000)
+        let response = self
+            .send_statement(&mut conn, sql, prepared, |statement_id, token| {
+                Request::Execute {
+                    sql: sql.to_string(),
+                    params: params.to_vec(),
+                    prepared_statement_id: statement_id,
+                    transaction_id: None,
+                    auth_token: Some(token.signature.clone()),
+                }
+            })
             .await;
 
         let latency = start.elapsed().as_millis() as f64;
 
         let result = match response {
-            Ok(resp) => {
-                // Parse response
-                let execute_response: ExecuteResponse = bincode::deserialize(&resp.payload)
-                    .map_err(|e| DatabaseError::SerializationError {
-                        message: format!("Failed to deserialize execute response: {}", e),
-                    })?;
-
-                // Check for errors
-                if let Some(error) = execute_response.error {
-                    self.metrics.record_execute(false, latency).await;
-                    tracing::error!("Execute failed: {}", error);
-                    Err(DatabaseError::InternalError {
-                        component: "DataClient".to_string(),
-                        details: error,
-                    })
-                } else {
-                    self.metrics.record_execute(true, latency).await;
-                    tracing::debug!(
-                        "Execute successful: {} rows affected ({}ms)",
-                        execute_response.rows_affected,
-                        latency
-                    );
-                    Ok(ExecuteResult {
-                        rows_affected: execute_response.rows_affected,
-                        last_insert_id: execute_response.last_insert_id,
-                    })
-                }
+            Ok(Response::Executed {
+                rows_affected,
+                last_insert_id,
+            }) => {
+                self.metrics.record_execute(true, latency).await;
+                tracing::debug!(
+                    "Execute successful: {} rows affected ({}ms)",
+                    rows_affected,
+                    latency
+                );
+                Ok(ExecuteResult {
+                    rows_affected,
+                    last_insert_id,
+                })
+            }
+            Ok(other) => {
+                self.metrics.record_execute(false, latency).await;
+                Err(other.unexpected("DataClient"))
             }
             Err(e) => {
                 self.metrics.record_execute(false, latency).await;
@@ -309,74 +549,63 @@ impl DataClient {
 
     /// Executes a query with parameters
     pub async fn query_with_params(&self, sql: &str, params: &[Value]) -> Result<QueryResult> {
+        self.run_query(sql, params, false).await
+    }
+
+    /// Executes a prepared query with parameters
+    pub async fn query_prepared(
+        &self,
+        statement: &PreparedStatement,
+        params: &[Value],
+    ) -> Result<QueryResult> {
+        self.run_query(&statement.sql, params, true).await
+    }
+
+    /// Executes a query, by prepared statement id if `prepared` is set
+    async fn run_query(&self, sql: &str, params: &[Value], prepared: bool) -> Result<QueryResult> {
         tracing::debug!("Executing query: {}", sql);
+        check_statement(&self.auth_manager, sql).await?;
         let start = std::time::Instant::now();
 
         // Get connection from pool
         let mut conn = self.connection_manager.get_connection().await?;
 
-        // Get valid auth token
-        let token = self.auth_manager.get_valid_token().await?;
-
-        // Build query request
-        let request = QueryRequest {
-            sql: sql.to_string(),
-            params: params.to_vec(),
-            prepared_statement_id: None,
-            auth_token: Some(token.signature.clone()),
-            streaming: false,
-        };
-
-        // Serialize request
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize query request: {}", e),
-            })?;
-
         // Send request and receive response
-        let response = conn
-            .connection_mut()
-            .send_request(MessageType::Data, payload, 5000)
+        let response = self
+            .send_statement(&mut conn, sql, prepared, |statement_id, token| {
+                Request::Query {
+                    sql: sql.to_string(),
+                    params: params.to_vec(),
+                    prepared_statement_id: statement_id,
+                    transaction_id: None,
+                    auth_token: Some(token.signature.clone()),
+                    streaming: false,
+                }
+            })
             .await;
 
         let latency = start.elapsed().as_millis() as f64;
 
-        let result =
-            match response {
-                Ok(resp) => {
-                    // Parse response
-                    let query_response: QueryResponse = bincode::deserialize(&resp.payload)
-                        .map_err(|e| DatabaseError::SerializationError {
-                            message: format!("Failed to deserialize query response: {}", e),
-                        })?;
-
-                    // Check for errors
-                    if let Some(error) = query_response.error {
-                        self.metrics.record_query(false, latency).await;
-                        tracing::error!("Query failed: {}", error);
-                        Err(DatabaseError::InternalError {
-                            component: "DataClient".to_string(),
-                            details: error,
-                        })
-                    } else {
-                        self.metrics.record_query(true, latency).await;
-                        tracing::debug!(
-                            "Query successful: {} rows returned ({}ms)",
-                            query_response.rows.len(),
-                            latency
-                        );
-                        Ok(QueryResult::from_raw(
-                            query_response.columns,
-                            query_response.rows,
-                        ))
-                    }
-                }
-                Err(e) => {
-                    self.metrics.record_query(false, latency).await;
-                    tracing::error!("Query failed: {}", e);
-                    Err(e)
-                }
-            };
+        let result = match response {
+            Ok(Response::Rows { columns, rows }) => {
+                self.metrics.record_query(true, latency).await;
+                tracing::debug!(
+                    "Query successful: {} rows returned ({}ms)",
+                    rows.len(),
+                    latency
+                );
+                Ok(QueryResult::from_raw(columns, rows))
+            }
+            Ok(other) => {
+                self.metrics.record_query(false, latency).await;
+                Err(other.unexpected("DataClient"))
+            }
+            Err(e) => {
+                self.metrics.record_query(false, latency).await;
+                tracing::error!("Query failed: {}", e);
+                Err(e)
+            }
+        };
 
         // Return connection to pool
         self.connection_manager.return_connection(conn).await;
@@ -386,121 +615,207 @@ impl DataClient {
 
     /// Executes a streaming query for large result sets
     pub async fn query_stream(&self, sql: &str) -> Result<ResultStream> {
-        // Get connection from pool
-        let mut conn = self.connection_manager.get_connection().await?;
-
-        // Get valid auth token
-        let token = self.auth_manager.get_valid_token().await?;
-
-        // Build streaming query request
-        let request = QueryRequest {
-            sql: sql.to_string(),
-            params: vec![],
-            prepared_statement_id: None,
-            auth_token: Some(token.signature.clone()),
-            streaming: true,
-        };
+        self.query_stream_with_params(sql, &[]).await
+    }
 
-        // Serialize request
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize query request: {}", e),
-            })?;
+    /// Executes a streaming query with parameters
+    ///
+    /// The column metadata is available from the returned stream before any
+    /// row is fetched.
+    pub async fn query_stream_with_params(
+        &self,
+        sql: &str,
+        params: &[Value],
+    ) -> Result<ResultStream> {
+        check_statement(&self.auth_manager, sql).await?;
 
-        // Get node_id before borrowing connection mutably
-        let node_id = conn.node_id();
-        let timestamp = chrono::Utc::now().timestamp_millis();
-
-        // Send request
-        conn.connection_mut()
-            .send_message(Message::new(
-                0,
-                node_id,
-                0,
-                timestamp,
-                MessageType::Data,
-                payload,
-            ))
-            .await?;
+        // Get connection from pool
+        let mut conn = self.connection_manager.get_connection().await?;
 
-        // Receive first response with column metadata
-        let response = conn.connection_mut().receive_message().await?;
-        let query_response: QueryResponse =
-            bincode::deserialize(&response.payload).map_err(|e| {
-                DatabaseError::SerializationError {
-                    message: format!("Failed to deserialize query response: {}", e),
-                }
-            })?;
+        // Open the stream and receive column metadata
+        let response = self
+            .auth_manager
+            .send_authenticated(
+                conn.connection_mut(),
+                |token| Request::Query {
+                    sql: sql.to_string(),
+                    params: params.to_vec(),
+                    prepared_statement_id: None,
+                    transaction_id: None,
+                    auth_token: Some(token.signature.clone()),
+                    streaming: true,
+                },
+                self.connection_manager.config().timeout_ms,
+            )
+            .await;
 
-        // Check for errors
-        if let Some(error) = query_response.error {
-            return Err(DatabaseError::InternalError {
-                component: "DataClient".to_string(),
-                details: error,
-            });
+        match response {
+            Ok(Response::StreamOpened { stream_id, columns }) => Ok(ResultStream::new(
+                conn,
+                self.connection_manager.clone(),
+                stream_id,
+                Arc::new(columns),
+            )),
+            Ok(other) => {
+                self.connection_manager.return_connection(conn).await;
+                Err(other.unexpected("DataClient"))
+            }
+            Err(e) => {
+                self.connection_manager.return_connection(conn).await;
+                Err(e)
+            }
         }
-
-        // Create stream with column metadata
-        let columns = Arc::new(query_response.columns);
-        Ok(ResultStream::new(conn, columns))
     }
 
     /// Prepares a statement for reuse
+    ///
+    /// The statement is prepared on one pooled connection; other connections
+    /// prepare it when they first run it.
     pub async fn prepare(&self, sql: &str) -> Result<PreparedStatement> {
-        // Check cache first
-        {
-            let cache = self.prepared_statements.read().await;
-            if let Some(stmt) = cache.get(sql) {
-                return Ok(stmt.clone());
+        let mut conn = self.connection_manager.get_connection().await?;
+        let result = self.prepare_on(&mut conn, sql).await;
+        self.connection_manager.return_connection(conn).await;
+        let (_, param_count) = result?;
+        Ok(PreparedStatement {
+            sql: sql.to_string(),
+            param_count,
+        })
+    }
+
+    /// Closes a prepared statement on every idle connection that prepared it
+    ///
+    /// Connections in use keep the statement until it is evicted from their
+    /// cache or the connection is closed.
+    pub async fn close_prepared(&self, statement: &PreparedStatement) -> Result<()> {
+        let mut result = Ok(());
+        for mut conn in self.connection_manager.take_idle_connections().await {
+            if let Some(statement_id) = conn.connection_mut().statements().remove(&statement.sql) {
+                let closed = self.close_on(&mut conn, statement_id).await;
+                if result.is_ok() {
+                    result = closed;
+                }
             }
+            self.connection_manager.return_connection(conn).await;
         }
+        result
+    }
 
-        // Not in cache, prepare on server
-        let mut conn = self.connection_manager.get_connection().await?;
-        let token = self.auth_manager.get_valid_token().await?;
+    /// Sends the request `build` makes for `sql`, with the id of the
+    /// statement prepared on `conn` if `prepared` is set
+    ///
+    /// If the server no longer knows the statement, e.g. because it evicted
+    /// it or restarted, the statement is prepared again and the request
+    /// retried once.
+    async fn send_statement(
+        &self,
+        conn: &mut PooledConnection,
+        sql: &str,
+        prepared: bool,
+        build: impl Fn(Option<StatementId>, &AuthToken) -> Request,
+    ) -> Result<Response> {
+        let statement_id = self.statement_id(conn, sql, prepared).await?;
+        let response = self
+            .auth_manager
+            .send_authenticated(
+                conn.connection_mut(),
+                |token| build(statement_id, token),
+                self.connection_manager.config().timeout_ms,
+            )
+            .await;
 
-        let request = PrepareRequest {
-            sql: sql.to_string(),
-            auth_token: Some(token.signature.clone()),
-        };
+        match response {
+            Err(DatabaseError::ServerError {
+                sqlstate: state, ..
+            }) if statement_id.is_some() && state == sqlstate::INVALID_SQL_STATEMENT_NAME => {
+                tracing::debug!("Server lost prepared statement, preparing again: {}", sql);
+                conn.connection_mut().statements().remove(sql);
+                let statement_id = self.statement_id(conn, sql, prepared).await?;
+                self.auth_manager
+                    .send_authenticated(
+                        conn.connection_mut(),
+                        |token| build(statement_id, token),
+                        self.connection_manager.config().timeout_ms,
+                    )
+                    .await
+            }
+            response => response,
+        }
+    }
 
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize prepare request: {}", e),
-            })?;
+    /// Returns the id to run `sql` with on `conn`, if it should run prepared
+    ///
+    /// Statements run as plain SQL when the connection's cache is disabled.
+    async fn statement_id(
+        &self,
+        conn: &mut PooledConnection,
+        sql: &str,
+        prepared: bool,
+    ) -> Result<Option<StatementId>> {
+        let statements = conn.connection_mut().statements();
+        if !prepared || !statements.is_enabled() {
+            return Ok(None);
+        }
+        if let Some(statement_id) = statements.get(sql) {
+            return Ok(Some(statement_id));
+        }
+        Ok(Some(self.prepare_on(conn, sql).await?.0))
+    }
 
-        let response = conn
-            .connection_mut()
-            .send_request(MessageType::Data, payload, 5000)
+    /// Prepares `sql` on `conn`, returning its id and parameter count
+    ///
+    /// The statement replaces any earlier one for the same SQL in the
+    /// connection's cache. Statements dropped from the cache are closed on
+    /// the server.
+    async fn prepare_on(
+        &self,
+        conn: &mut PooledConnection,
+        sql: &str,
+    ) -> Result<(StatementId, usize)> {
+        let response = self
+            .auth_manager
+            .send_authenticated(
+                conn.connection_mut(),
+                |token| Request::Prepare {
+                    sql: sql.to_string(),
+                    auth_token: Some(token.signature.clone()),
+                },
+                self.connection_manager.config().timeout_ms,
+            )
             .await?;
 
-        let prepare_response: PrepareResponse =
-            bincode::deserialize(&response.payload).map_err(|e| {
-                DatabaseError::SerializationError {
-                    message: format!("Failed to deserialize prepare response: {}", e),
-                }
-            })?;
+        let (statement_id, param_count) = match response {
+            Response::Prepared {
+                statement_id,
+                param_count,
+            } => (statement_id, param_count),
+            other => return Err(other.unexpected("DataClient")),
+        };
 
-        if let Some(error) = prepare_response.error {
-            return Err(DatabaseError::InternalError {
-                component: "DataClient".to_string(),
-                details: error,
-            });
+        let dropped = conn.connection_mut().statements().insert(sql, statement_id);
+        if let Some(dropped) = dropped {
+            if let Err(e) = self.close_on(conn, dropped).await {
+                tracing::warn!("Failed to close evicted statement {}: {}", dropped, e);
+            }
         }
 
-        self.connection_manager.return_connection(conn).await;
-
-        let stmt = PreparedStatement {
-            statement_id: prepare_response.statement_id,
-            sql: sql.to_string(),
-            param_count: prepare_response.param_count,
-        };
+        Ok((statement_id, param_count))
+    }
 
-        // Add to cache
-        let mut cache = self.prepared_statements.write().await;
-        cache.insert(sql.to_string(), stmt.clone());
+    /// Closes a statement on the server side of `conn`
+    async fn close_on(&self, conn: &mut PooledConnection, statement_id: StatementId) -> Result<()> {
+        let response = self
+            .auth_manager
+            .send_authenticated(
+                conn.connection_mut(),
+                |_| Request::Close { statement_id },
+                self.connection_manager.config().timeout_ms,
+            )
+            .await?;
 
-        Ok(stmt)
+        match response {
+            Response::Closed => Ok(()),
+            other => Err(other.unexpected("DataClient")),
+        }
     }
 
     /// Creates a batch context for executing multiple operations atomically
@@ -523,6 +838,18 @@ impl DataClient {
         self.query_with_params(&sql, &params).await
     }
 
+    /// Pages through a SELECT query in the order of `keys`
+    ///
+    /// See `KeysetPaginator` for the requirements on the keys.
+    pub fn paginate(
+        &self,
+        builder: crate::query_builder::QueryBuilder,
+        keys: &[(&str, crate::query_builder::OrderDirection)],
+        page_size: u64,
+    ) -> Result<crate::pagination::KeysetPaginator> {
+        crate::pagination::KeysetPaginator::new(self.clone(), builder, keys, page_size)
+    }
+
     /// Executes a query builder for non-SELECT queries
     pub async fn execute_builder(
         &self,
@@ -550,12 +877,13 @@ impl DataClient {
         tracing::debug!("Beginning transaction");
         let start = std::time::Instant::now();
 
-        // 1. Acquire connection from pool
-        let mut connection = self.connection_manager.get_connection().await?;
-
-        // 2. Get valid auth token
+        // 1. Get valid auth token, before taking a connection the renewal
+        // may need from the pool
         let auth_token = self.auth_manager.get_valid_token().await?;
 
+        // 2. Acquire connection from pool
+        let mut connection = self.connection_manager.get_connection().await?;
+
         // 3. Generate unique transaction ID (using timestamp + random for uniqueness)
         let transaction_id = chrono::Utc::now().timestamp_millis() as u64;
 
@@ -573,17 +901,15 @@ impl DataClient {
         let response = connection
             .connection_mut()
             .send_request(MessageType::Transaction, payload, 5000)
-            .await;
+            .await
+            .and_then(|resp| resp.check_server_error(None, Some(transaction_id)));
 
         let latency = start.elapsed().as_millis() as f64;
 
         // 5. Verify success
         match response {
             Ok(resp) => {
-                let txn_response: TransactionResponse = bincode::deserialize(&resp.payload)
-                    .map_err(|e| DatabaseError::SerializationError {
-                        message: format!("Failed to deserialize transaction response: {}", e),
-                    })?;
+                let txn_response = TransactionResponse::from_payload(&resp.payload)?;
 
                 match txn_response {
                     TransactionResponse::BeginSuccess => {
@@ -600,10 +926,8 @@ impl DataClient {
                         tracing::error!("Failed to begin transaction: {}", message);
                         // Return connection to pool on error
                         self.connection_manager.return_connection(connection).await;
-                        Err(DatabaseError::InternalError {
-                            component: "DataClient".to_string(),
-                            details: format!("Failed to begin transaction: {}", message),
-                        })
+                        Err(ServerError::unclassified(message)
+                            .into_database_error(None, Some(transaction_id)))
                     }
                     _ => {
                         self.metrics.record_transaction(false, latency).await;
@@ -627,62 +951,166 @@ impl DataClient {
     }
 }
 
-// Request/Response types for serialization
-
-#[derive(Debug, Serialize, Deserialize)]
-struct ExecuteRequest {
-    sql: String,
-    params: Vec<Value>,
-    prepared_statement_id: Option<StatementId>,
-    auth_token: Option<Vec<u8>>,
+/// Fails if permission checks are enabled and the token lacks any of the
+/// permissions `sql` needs
+async fn check_statement(auth_manager: &AuthenticationManager, sql: &str) -> Result<()> {
+    for permission in required_permissions(sql) {
+        auth_manager.check_permission(permission).await?;
+    }
+    Ok(())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ExecuteResponse {
-    rows_affected: u64,
-    last_insert_id: Option<i64>,
-    error: Option<String>,
-}
+/// Returns the permissions the statements in `sql` need
+///
+/// A word only counts as a verb where a statement can start: first in a
+/// statement, first inside parentheses (a subquery or a common table
+/// expression's `AS (`), or, for `WITH`, right after the closing
+/// parenthesis of the last common table expression. Each statement needs
+/// the permission of its main verb plus `Write` or `Delete` for any data
+/// modification nested in it. Statements that cannot be classified need
+/// `Write`.
+fn required_permissions(sql: &str) -> Vec<Permission> {
+    let mut permissions = Vec::new();
+    let mut add = |permission| {
+        if !permissions.contains(&permission) {
+            permissions.push(permission);
+        }
+    };
 
-#[derive(Debug, Serialize, Deserialize)]
-struct QueryRequest {
-    sql: String,
-    params: Vec<Value>,
-    prepared_statement_id: Option<StatementId>,
-    auth_token: Option<Vec<u8>>,
-    streaming: bool,
-}
+    let statements = statement_words(sql);
+    if statements.is_empty() {
+        add(Permission::Write);
+    }
+    for words in statements {
+        let main = match words.first() {
+            Some(first) if first.text == "WITH" => words
+                .iter()
+                .filter(|word| word.depth == 0 && word.after == Some(')'))
+                .find_map(Word::verb_permission),
+            first => first.and_then(Word::verb_permission),
+        };
+        add(main.unwrap_or(Permission::Write));
 
-#[derive(Debug, Serialize, Deserialize)]
-struct QueryResponse {
-    columns: Vec<ColumnMetadata>,
-    rows: Vec<Vec<Value>>,
-    error: Option<String>,
+        for word in words.iter().filter(|word| word.after == Some('(')) {
+            if let Some(permission @ (Permission::Write | Permission::Delete)) =
+                word.verb_permission()
+            {
+                add(permission);
+            }
+        }
+    }
+    permissions
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct PrepareRequest {
-    sql: String,
-    auth_token: Option<Vec<u8>>,
+/// An upper-cased SQL word and the tokens around it
+struct Word {
+    text: String,
+    /// Depth of parentheses the word appears at
+    depth: usize,
+    /// `(` or `)` if the word directly follows one
+    after: Option<char>,
+    /// First character of the token directly following the word
+    before: Option<char>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct PrepareResponse {
-    statement_id: StatementId,
-    param_count: usize,
-    error: Option<String>,
+impl Word {
+    /// Returns the permission needed by a statement starting with this word
+    ///
+    /// Verbs other than `SELECT` and `VALUES` must be followed by a name,
+    /// so `REPLACE(name, 'a', 'b')` or a column called `update` is not
+    /// mistaken for a data modification.
+    fn verb_permission(&self) -> Option<Permission> {
+        let permission = match self.text.as_str() {
+            "SELECT" | "VALUES" => return Some(Permission::Read),
+            "INSERT" | "UPDATE" | "MERGE" | "UPSERT" | "REPLACE" => Permission::Write,
+            "DELETE" | "TRUNCATE" => Permission::Delete,
+            "CREATE" => Permission::CreateTable,
+            "DROP" => Permission::DropTable,
+            _ => return None,
+        };
+        self.before
+            .filter(|&c| c.is_alphabetic() || matches!(c, '_' | '"' | '`'))
+            .map(|_| permission)
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct BatchRequest {
-    operations: Vec<BatchOperation>,
-    auth_token: Option<Vec<u8>>,
-}
+/// Splits SQL into statements of words
+///
+/// Comments are skipped, and string literals and quoted identifiers are
+/// skipped apart from counting as the token after a word.
+fn statement_words(sql: &str) -> Vec<Vec<Word>> {
+    let mut statements: Vec<Vec<Word>> = vec![Vec::new()];
+    let mut depth = 0usize;
+    let mut after = None;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '-' if chars.peek() == Some(&'-') => {
+                chars.by_ref().find(|&c| c == '\n');
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                chars.by_ref().find(|&c| {
+                    let end = previous == '*' && c == '/';
+                    previous = c;
+                    end
+                });
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            _ => {}
+        }
+
+        let current = statements
+            .last_mut()
+            .expect("there is always a current statement");
+        if let Some(word) = current.last_mut().filter(|word| word.before.is_none()) {
+            word.before = Some(c);
+        }
+        let previous = after.take();
+
+        match c {
+            // A doubled quote reads as two adjacent literals, which is harmless
+            '\'' | '"' | '`' => {
+                chars.by_ref().find(|&quoted| quoted == c);
+            }
+            '(' => {
+                depth += 1;
+                after = Some('(');
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                after = Some(')');
+            }
+            ';' => {
+                statements.push(Vec::new());
+                depth = 0;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut text = c.to_ascii_uppercase().to_string();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_' || next == '$') {
+                        break;
+                    }
+                    text.push(next.to_ascii_uppercase());
+                    chars.next();
+                }
+                current.push(Word {
+                    text,
+                    depth,
+                    after: previous,
+                    before: None,
+                });
+            }
+            _ => {}
+        }
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct BatchResponse {
-    results: Vec<ExecuteResult>,
-    error: Option<String>,
+    statements.retain(|words| !words.is_empty());
+    statements
 }
 
 #[cfg(test)]
@@ -699,6 +1127,65 @@ mod tests {
         assert_eq!(result.last_insert_id, Some(42));
     }
 
+    #[test]
+    fn test_required_permission() {
+        assert_eq!(required_permissions("select * from t"), [Permission::Read]);
+        assert_eq!(
+            required_permissions("  INSERT INTO t VALUES (1)"),
+            [Permission::Write]
+        );
+        assert_eq!(
+            required_permissions("UPDATE t SET a = 1"),
+            [Permission::Write]
+        );
+        assert_eq!(required_permissions("DELETE FROM t"), [Permission::Delete]);
+        assert_eq!(
+            required_permissions("CREATE TABLE t (a INT)"),
+            [Permission::CreateTable]
+        );
+        assert_eq!(
+            required_permissions("DROP TABLE t"),
+            [Permission::DropTable]
+        );
+    }
+
+    #[test]
+    fn test_required_permission_looks_past_ctes_and_comments() {
+        use Permission::*;
+
+        let cases: [(&str, &[Permission]); 17] = [
+            ("WITH x AS (SELECT 1) DELETE FROM t", &[Delete]),
+            ("with x as (select id from s) update t set a = 1", &[Write]),
+            (
+                "WITH RECURSIVE x(n) AS (SELECT 1) INSERT INTO t SELECT n FROM x",
+                &[Write],
+            ),
+            (
+                "WITH d AS (DELETE FROM t RETURNING *) SELECT * FROM d",
+                &[Read, Delete],
+            ),
+            ("-- report\nDELETE FROM t", &[Delete]),
+            ("/* SELECT */ UPDATE t SET a = 1", &[Write]),
+            ("(SELECT 1) UNION (SELECT 2)", &[Read]),
+            ("SELECT 'delete', \"update\" FROM t", &[Read]),
+            ("SELECT * FROM t FOR UPDATE", &[Read]),
+            ("SELECT 1; DROP TABLE t", &[Read, DropTable]),
+            ("EXPLAIN ANALYZE DELETE FROM t", &[Write]),
+            ("SELECT REPLACE(name, 'a', 'b') FROM users", &[Read]),
+            ("SELECT merge, t.update FROM t WHERE replace = 1", &[Read]),
+            ("SELECT * FROM t WHERE (update = 1)", &[Read]),
+            ("WITH update AS (SELECT 1) SELECT * FROM update", &[Read]),
+            (
+                "SELECT * FROM (UPDATE t SET a = 1 RETURNING *) u",
+                &[Read, Write],
+            ),
+            ("  -- nothing", &[Write]),
+        ];
+        for (sql, expected) in cases {
+            assert_eq!(required_permissions(sql), expected, "{}", sql);
+        }
+    }
+
     #[test]
     fn test_query_builder_integration() {
         use crate::query_builder::{OrderDirection, QueryBuilder};
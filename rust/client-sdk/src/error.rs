@@ -4,6 +4,7 @@
 //! failure modes in the client SDK.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use thiserror::Error;
 
 /// The main error type for the Q-Distributed-Database Client SDK
@@ -55,6 +56,13 @@ pub enum DatabaseError {
     #[error("Invalid credentials")]
     InvalidCredentials,
 
+    /// Signed token failed verification
+    #[error("Invalid token: {reason}")]
+    InvalidToken {
+        /// Why the token was rejected
+        reason: String,
+    },
+
     // Query Errors
     /// SQL syntax error
     #[error("Syntax error in SQL at position {position}: {message}\nSQL: {sql}")]
@@ -149,6 +157,33 @@ pub enum DatabaseError {
         server_version: u8,
     },
 
+    /// Message authentication tag is missing or does not match
+    #[error("Message authentication failed: {reason}")]
+    MessageAuthenticationFailed {
+        /// Why the tag was rejected
+        reason: String,
+    },
+
+    /// Message sequence number was already seen or is outside the replay window
+    #[error("Replayed message rejected: sequence number {sequence_number} ({reason})")]
+    ReplayDetected {
+        /// The rejected sequence number
+        sequence_number: u64,
+        /// Why the sequence number was rejected
+        reason: String,
+    },
+
+    /// Message timestamp is too far from the local clock
+    #[error("Stale message rejected: timestamp {timestamp} is {skew_ms}ms from local clock (max: {max_skew_ms}ms)")]
+    StaleMessage {
+        /// The message timestamp in milliseconds
+        timestamp: i64,
+        /// Difference from the local clock in milliseconds
+        skew_ms: u64,
+        /// The maximum accepted difference in milliseconds
+        max_skew_ms: u64,
+    },
+
     // Network Errors
     /// Generic network error
     #[error("Network error: {details}")]
@@ -176,6 +211,18 @@ pub enum DatabaseError {
         details: String,
     },
 
+    // Server Errors
+    /// Server error without a more specific variant
+    #[error("Server error {sqlstate} (code {code}): {message}")]
+    ServerError {
+        /// SQLSTATE-like error code
+        sqlstate: String,
+        /// Server-specific numeric error code
+        code: u32,
+        /// The error message
+        message: String,
+    },
+
     // Admin Errors
     /// Node not found
     #[error("Node not found: {node_id}")]
@@ -243,21 +290,42 @@ pub enum DatabaseError {
         /// The maximum valid index
         max: usize,
     },
+
+    /// A column could not be mapped to a field of a typed row
+    #[error("Cannot map column {column} to {to}: {reason}")]
+    ColumnMappingError {
+        /// Column name, or index for positional fields
+        column: String,
+        /// Rust type of the field
+        to: &'static str,
+        /// Why the value could not be converted
+        reason: String,
+    },
 }
 
 impl DatabaseError {
+    /// Returns the error for a response of the wrong kind, named by `kind`
+    pub(crate) fn unexpected_response(component: &str, kind: &str) -> Self {
+        DatabaseError::InternalError {
+            component: component.to_string(),
+            details: format!("Unexpected response type: {}", kind),
+        }
+    }
+
     /// Returns true if this error is retryable
     ///
     /// Retryable errors are transient failures that may succeed on retry,
     /// such as network timeouts or temporary connection issues.
     pub fn is_retryable(&self) -> bool {
-        matches!(
-            self,
+        match self {
             DatabaseError::ConnectionTimeout { .. }
-                | DatabaseError::ConnectionLost { .. }
-                | DatabaseError::NetworkError { .. }
-                | DatabaseError::TimeoutError { .. }
-        )
+            | DatabaseError::ConnectionLost { .. }
+            | DatabaseError::NetworkError { .. }
+            | DatabaseError::TimeoutError { .. }
+            | DatabaseError::DeadlockDetected { .. } => true,
+            DatabaseError::ServerError { sqlstate, .. } => sqlstate::is_transient(sqlstate),
+            _ => false,
+        }
     }
 
     /// Returns true if this error is a connection error
@@ -277,10 +345,206 @@ impl DatabaseError {
             DatabaseError::AuthenticationFailed { .. }
                 | DatabaseError::TokenExpired { .. }
                 | DatabaseError::InvalidCredentials
+                | DatabaseError::InvalidToken { .. }
         )
     }
 }
 
+/// SQLSTATE-like codes reported by the server
+///
+/// The first two characters are the error class, the remaining three
+/// identify the condition within the class.
+pub mod sqlstate {
+    /// Connection exception class
+    pub const CLASS_CONNECTION_EXCEPTION: &str = "08";
+    /// Integrity constraint violation class
+    pub const CLASS_INTEGRITY_CONSTRAINT: &str = "23";
+    /// Invalid authorization specification class
+    pub const CLASS_INVALID_AUTHORIZATION: &str = "28";
+    /// Transaction rollback class
+    pub const CLASS_TRANSACTION_ROLLBACK: &str = "40";
+    /// Insufficient resources class
+    pub const CLASS_INSUFFICIENT_RESOURCES: &str = "53";
+
+    /// Invalid SQL statement name, e.g. an unknown or closed prepared statement
+    pub const INVALID_SQL_STATEMENT_NAME: &str = "26000";
+    /// Invalid authorization specification, e.g. an unknown or revoked token
+    pub const INVALID_AUTHORIZATION_SPECIFICATION: &str = "28000";
+    /// Invalid password
+    pub const INVALID_PASSWORD: &str = "28P01";
+    /// Serialization failure, the transaction can be retried
+    pub const SERIALIZATION_FAILURE: &str = "40001";
+    /// Deadlock detected
+    pub const DEADLOCK_DETECTED: &str = "40P01";
+    /// Insufficient privilege
+    pub const INSUFFICIENT_PRIVILEGE: &str = "42501";
+    /// SQL syntax error
+    pub const SYNTAX_ERROR: &str = "42601";
+    /// Undefined column
+    pub const UNDEFINED_COLUMN: &str = "42703";
+    /// Undefined table
+    pub const UNDEFINED_TABLE: &str = "42P01";
+    /// Internal error, also used for errors the server did not classify
+    pub const INTERNAL_ERROR: &str = "XX000";
+
+    /// Returns the two character class of a code
+    pub fn class(code: &str) -> &str {
+        code.get(..2).unwrap_or(code)
+    }
+
+    /// Returns true if the condition is transient and the operation may succeed on retry
+    pub fn is_transient(code: &str) -> bool {
+        matches!(
+            class(code),
+            CLASS_CONNECTION_EXCEPTION | CLASS_TRANSACTION_ROLLBACK | CLASS_INSUFFICIENT_RESOURCES
+        )
+    }
+}
+
+/// Structured error reported by the server
+///
+/// Sent as the payload of `MessageType::Error` frames. `details` carries named
+/// fields such as `table`, `column`, `constraint` or `permission`, which are
+/// used to fill in the matching `DatabaseError` variant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerError {
+    /// SQLSTATE-like error code, see the `sqlstate` module
+    pub sqlstate: String,
+    /// Server-specific numeric error code
+    pub code: u32,
+    /// Human readable error message
+    pub message: String,
+    /// Position in the SQL text the error refers to
+    pub position: Option<usize>,
+    /// Named details about the error
+    pub details: BTreeMap<String, String>,
+}
+
+impl ServerError {
+    /// Creates a server error with the given SQLSTATE-like code and message
+    pub fn new(sqlstate: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            sqlstate: sqlstate.into(),
+            code: 0,
+            message: message.into(),
+            position: None,
+            details: BTreeMap::new(),
+        }
+    }
+
+    /// Creates an error for a plain message from a server that does not
+    /// report structured errors
+    pub fn unclassified(message: impl Into<String>) -> Self {
+        Self::new(sqlstate::INTERNAL_ERROR, message)
+    }
+
+    /// Sets the server-specific numeric error code
+    pub fn with_code(mut self, code: u32) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// Sets the position in the SQL text
+    pub fn with_position(mut self, position: usize) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// Adds a named detail
+    pub fn with_detail(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.details.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns the two character error class
+    pub fn class(&self) -> &str {
+        sqlstate::class(&self.sqlstate)
+    }
+
+    /// Returns a named detail
+    pub fn detail(&self, key: &str) -> Option<&str> {
+        self.details.get(key).map(String::as_str)
+    }
+
+    /// Decodes the payload of an error frame
+    ///
+    /// Older servers send a bare bincode string; those messages are returned
+    /// as unclassified errors.
+    pub fn from_payload(payload: &[u8]) -> Result<Self, DatabaseError> {
+        if let Ok(error) = bincode::deserialize::<ServerError>(payload) {
+            return Ok(error);
+        }
+
+        bincode::deserialize::<String>(payload)
+            .map(Self::unclassified)
+            .map_err(|e| DatabaseError::SerializationError {
+                message: format!("Failed to deserialize server error: {}", e),
+            })
+    }
+
+    /// Maps the error to the matching `DatabaseError` variant
+    ///
+    /// `sql` and `transaction_id` describe the request that failed and fill in
+    /// the fields the server does not repeat back.
+    pub fn into_database_error(
+        self,
+        sql: Option<&str>,
+        transaction_id: Option<u64>,
+    ) -> DatabaseError {
+        let transaction_id =
+            transaction_id.or_else(|| self.detail("transaction_id").and_then(|id| id.parse().ok()));
+        let class = self.class().to_string();
+
+        match (self.sqlstate.as_str(), class.as_str()) {
+            (sqlstate::SYNTAX_ERROR, _) => DatabaseError::SyntaxError {
+                sql: sql.unwrap_or_default().to_string(),
+                position: self.position.unwrap_or(0),
+                message: self.message,
+            },
+            (sqlstate::UNDEFINED_TABLE, _) => DatabaseError::TableNotFound {
+                table_name: self.detail("table").unwrap_or(&self.message).to_string(),
+            },
+            (sqlstate::UNDEFINED_COLUMN, _) => DatabaseError::ColumnNotFound {
+                column_name: self.detail("column").unwrap_or(&self.message).to_string(),
+            },
+            (sqlstate::INSUFFICIENT_PRIVILEGE, _) => DatabaseError::InsufficientPermissions {
+                required: self
+                    .detail("permission")
+                    .unwrap_or(&self.message)
+                    .to_string(),
+            },
+            (sqlstate::INVALID_PASSWORD, _) => DatabaseError::InvalidCredentials,
+            (sqlstate::DEADLOCK_DETECTED, _) => DatabaseError::DeadlockDetected {
+                transaction_id: transaction_id.unwrap_or(0),
+            },
+            (_, sqlstate::CLASS_INTEGRITY_CONSTRAINT) => DatabaseError::ConstraintViolation {
+                constraint: self
+                    .detail("constraint")
+                    .unwrap_or(&self.sqlstate)
+                    .to_string(),
+                details: self.message,
+            },
+            (_, sqlstate::CLASS_INVALID_AUTHORIZATION) => DatabaseError::AuthenticationFailed {
+                reason: self.message,
+            },
+            // Serialization failures stay a retryable `ServerError`
+            (state, sqlstate::CLASS_TRANSACTION_ROLLBACK)
+                if state != sqlstate::SERIALIZATION_FAILURE && transaction_id.is_some() =>
+            {
+                DatabaseError::TransactionAborted {
+                    transaction_id: transaction_id.unwrap_or(0),
+                    reason: self.message,
+                }
+            }
+            _ => DatabaseError::ServerError {
+                sqlstate: self.sqlstate,
+                code: self.code,
+                message: self.message,
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -581,6 +845,178 @@ mod tests {
         .is_auth_error());
     }
 
+    // Server Error Mapping Tests
+    #[test]
+    fn test_server_error_display() {
+        let err = DatabaseError::ServerError {
+            sqlstate: "53100".to_string(),
+            code: 7,
+            message: "Disk full".to_string(),
+        };
+        assert_eq!(err.to_string(), "Server error 53100 (code 7): Disk full");
+    }
+
+    #[test]
+    fn test_server_error_class() {
+        let error = ServerError::new(sqlstate::UNDEFINED_TABLE, "missing");
+        assert_eq!(error.class(), "42");
+        assert_eq!(sqlstate::class("4"), "4");
+    }
+
+    #[test]
+    fn test_server_error_maps_syntax_error() {
+        let err = ServerError::new(sqlstate::SYNTAX_ERROR, "Expected FROM")
+            .with_position(9)
+            .into_database_error(Some("SELECT * FORM users"), None);
+
+        match err {
+            DatabaseError::SyntaxError {
+                sql,
+                position,
+                message,
+            } => {
+                assert_eq!(sql, "SELECT * FORM users");
+                assert_eq!(position, 9);
+                assert_eq!(message, "Expected FROM");
+            }
+            other => panic!("Expected SyntaxError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_server_error_maps_missing_objects() {
+        let err = ServerError::new(sqlstate::UNDEFINED_TABLE, "relation does not exist")
+            .with_detail("table", "users")
+            .into_database_error(None, None);
+        assert!(
+            matches!(err, DatabaseError::TableNotFound { table_name } if table_name == "users")
+        );
+
+        let err = ServerError::new(sqlstate::UNDEFINED_COLUMN, "column does not exist")
+            .with_detail("column", "email")
+            .into_database_error(None, None);
+        assert!(
+            matches!(err, DatabaseError::ColumnNotFound { column_name } if column_name == "email")
+        );
+    }
+
+    #[test]
+    fn test_server_error_maps_constraint_violation() {
+        let err = ServerError::new("23505", "duplicate key value")
+            .with_detail("constraint", "users_email_key")
+            .into_database_error(None, None);
+
+        match err {
+            DatabaseError::ConstraintViolation {
+                constraint,
+                details,
+            } => {
+                assert_eq!(constraint, "users_email_key");
+                assert_eq!(details, "duplicate key value");
+            }
+            other => panic!("Expected ConstraintViolation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_server_error_maps_transaction_errors() {
+        let err = ServerError::new(sqlstate::DEADLOCK_DETECTED, "deadlock")
+            .into_database_error(None, Some(12));
+        assert!(matches!(
+            err,
+            DatabaseError::DeadlockDetected { transaction_id: 12 }
+        ));
+        assert!(err.is_retryable());
+
+        let err = ServerError::new("40002", "integrity constraint in commit")
+            .into_database_error(None, Some(12));
+        assert!(matches!(
+            err,
+            DatabaseError::TransactionAborted {
+                transaction_id: 12,
+                ..
+            }
+        ));
+
+        let err = ServerError::new(sqlstate::SERIALIZATION_FAILURE, "could not serialize")
+            .into_database_error(None, Some(12));
+        assert!(matches!(err, DatabaseError::ServerError { .. }));
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn test_server_error_uses_transaction_id_detail() {
+        let err = ServerError::new(sqlstate::DEADLOCK_DETECTED, "deadlock")
+            .with_detail("transaction_id", "99")
+            .into_database_error(None, None);
+        assert!(matches!(
+            err,
+            DatabaseError::DeadlockDetected { transaction_id: 99 }
+        ));
+    }
+
+    #[test]
+    fn test_server_error_maps_auth_errors() {
+        let err = ServerError::new(sqlstate::INVALID_PASSWORD, "bad password")
+            .into_database_error(None, None);
+        assert!(matches!(err, DatabaseError::InvalidCredentials));
+
+        let err = ServerError::new("28000", "role is locked").into_database_error(None, None);
+        assert!(err.is_auth_error());
+
+        let err = ServerError::new(sqlstate::INSUFFICIENT_PRIVILEGE, "permission denied")
+            .with_detail("permission", "Write")
+            .into_database_error(None, None);
+        assert!(
+            matches!(err, DatabaseError::InsufficientPermissions { required } if required == "Write")
+        );
+    }
+
+    #[test]
+    fn test_server_error_fallback_variant() {
+        let err = ServerError::new("53200", "out of memory")
+            .with_code(1234)
+            .into_database_error(None, None);
+
+        match &err {
+            DatabaseError::ServerError {
+                sqlstate,
+                code,
+                message,
+            } => {
+                assert_eq!(sqlstate, "53200");
+                assert_eq!(*code, 1234);
+                assert_eq!(message, "out of memory");
+            }
+            other => panic!("Expected ServerError, got {:?}", other),
+        }
+        assert!(err.is_retryable());
+
+        let err = ServerError::unclassified("boom").into_database_error(None, None);
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_server_error_from_payload() {
+        let error = ServerError::new(sqlstate::UNDEFINED_TABLE, "missing")
+            .with_code(42)
+            .with_position(14)
+            .with_detail("table", "users");
+        let payload = bincode::serialize(&error).unwrap();
+        assert_eq!(ServerError::from_payload(&payload).unwrap(), error);
+
+        // Older servers send a bare string
+        let payload = bincode::serialize(&"something broke".to_string()).unwrap();
+        let legacy = ServerError::from_payload(&payload).unwrap();
+        assert_eq!(legacy.sqlstate, sqlstate::INTERNAL_ERROR);
+        assert_eq!(legacy.message, "something broke");
+
+        assert!(matches!(
+            ServerError::from_payload(&[0xff]),
+            Err(DatabaseError::SerializationError { .. })
+        ));
+    }
+
     // Error Cloning Tests
     #[test]
     fn test_error_clone() {
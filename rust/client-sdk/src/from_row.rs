@@ -0,0 +1,206 @@
+//! Typed row mapping
+//!
+//! `FromValue` converts a single column value and `FromRow` builds a value
+//! from a whole row. With the `derive` feature, `#[derive(FromRow)]`
+//! implements `FromRow` for structs whose fields implement `FromValue`:
+//!
+//! ```ignore
+//! #[derive(FromRow)]
+//! struct User {
+//!     id: i64,
+//!     #[row(rename = "user_name")]
+//!     name: String,
+//!     // NULL maps to None
+//!     email: Option<String>,
+//!     // Missing or NULL columns use Default::default()
+//!     #[row(default)]
+//!     login_count: i64,
+//!     // Built from the same row
+//!     #[row(flatten)]
+//!     audit: Audit,
+//! }
+//!
+//! let users: Vec<User> = client.data().query("SELECT * FROM users").await?.into_typed()?;
+//! ```
+//!
+//! Fields are looked up by name unless given `#[row(index = n)]`; fields of
+//! tuple structs are looked up by position.
+
+use crate::error::DatabaseError;
+use crate::result::Row;
+use crate::types::Value;
+use crate::Result;
+use chrono::{DateTime, Utc};
+
+/// Builds a value from a result row
+pub trait FromRow: Sized {
+    /// Maps the row, naming the failing column on error
+    fn from_row(row: &Row) -> Result<Self>;
+}
+
+/// Converts a column value to a Rust type
+pub trait FromValue: Sized {
+    /// Converts the value, failing on NULL unless `Self` is an `Option`
+    fn from_value(value: &Value) -> Result<Self>;
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> Result<Self> {
+        Ok(value.clone())
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &Value) -> Result<Self> {
+        value.as_i64()
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Result<Self> {
+        value.as_f64()
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: &Value) -> Result<Self> {
+        value.as_f64().map(|f| f as f32)
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<Self> {
+        value.as_bool_result()
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<Self> {
+        value.as_string()
+    }
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: &Value) -> Result<Self> {
+        value.as_bytes_vec()
+    }
+}
+
+impl FromValue for DateTime<Utc> {
+    fn from_value(value: &Value) -> Result<Self> {
+        value.as_timestamp_result()
+    }
+}
+
+/// Implements `FromValue` for integers narrower than the wire's i64
+macro_rules! narrow_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(value: &Value) -> Result<Self> {
+                    let wide = value.as_i64()?;
+                    <$ty>::try_from(wide).map_err(|_| DatabaseError::TypeConversionError {
+                        from: value.type_name().to_string(),
+                        to: stringify!($ty),
+                        value: wide.to_string(),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+narrow_integer!(i8, i16, i32, u8, u16, u32, u64);
+
+/// Gets a column by name, using `T::default()` when it is missing or NULL
+///
+/// Used by `#[row(default)]` fields.
+#[doc(hidden)]
+pub fn column_or_default<T: FromValue + Default>(row: &Row, name: &str) -> Result<T> {
+    match row.get_by_name(name) {
+        Ok(value) if !value.is_null() => row.try_get(name),
+        _ => Ok(T::default()),
+    }
+}
+
+/// Gets a column by index, using `T::default()` when it is missing or NULL
+///
+/// Used by positional `#[row(default)]` fields.
+#[doc(hidden)]
+pub fn column_at_or_default<T: FromValue + Default>(row: &Row, index: usize) -> Result<T> {
+    match row.get(index) {
+        Ok(value) if !value.is_null() => row.try_get_at(index),
+        _ => Ok(T::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::{ColumnMetadata, DataType};
+    use std::sync::Arc;
+
+    fn row(values: Vec<Value>) -> Row {
+        let columns = ["id", "name"]
+            .iter()
+            .enumerate()
+            .map(|(ordinal, name)| ColumnMetadata {
+                name: name.to_string(),
+                data_type: DataType::Int,
+                nullable: true,
+                ordinal,
+            })
+            .collect();
+        Row::new(Arc::new(columns), values)
+    }
+
+    #[test]
+    fn test_from_value_conversions() {
+        assert_eq!(i32::from_value(&Value::Int(-7)).unwrap(), -7);
+        assert!(u8::from_value(&Value::Int(300)).is_err());
+        assert!(u64::from_value(&Value::Int(-1)).is_err());
+        assert_eq!(f32::from_value(&Value::Int(2)).unwrap(), 2.0);
+        assert_eq!(Option::<i64>::from_value(&Value::Null).unwrap(), None);
+        assert_eq!(Option::<i64>::from_value(&Value::Int(1)).unwrap(), Some(1));
+        assert!(i64::from_value(&Value::Null).is_err());
+    }
+
+    #[test]
+    fn test_errors_name_the_column_and_type() {
+        let row = row(vec![Value::Int(1), Value::Null]);
+        assert_eq!(row.try_get::<i64>("id").unwrap(), 1);
+        assert_eq!(row.try_get_at::<Option<String>>(1).unwrap(), None);
+
+        let error = row.try_get::<String>("id").unwrap_err();
+        assert!(
+            matches!(
+                error,
+                DatabaseError::ColumnMappingError { ref column, to, .. }
+                    if column == "id" && to.contains("String")
+            ),
+            "{:?}",
+            error
+        );
+        let error = row.try_get_at::<i64>(1).unwrap_err();
+        assert!(
+            matches!(error, DatabaseError::ColumnMappingError { ref column, .. } if column == "name")
+        );
+        assert!(matches!(
+            row.try_get::<i64>("missing"),
+            Err(DatabaseError::ColumnNotFound { .. })
+        ));
+
+        assert_eq!(column_or_default::<i64>(&row, "missing").unwrap(), 0);
+        assert_eq!(column_or_default::<String>(&row, "name").unwrap(), "");
+        assert_eq!(column_at_or_default::<i64>(&row, 0).unwrap(), 1);
+    }
+}
@@ -0,0 +1,389 @@
+//! Message integrity module for Q-Distributed-Database Client SDK
+//!
+//! This module implements per-message authentication tags and replay
+//! protection for deployments that need frame integrity without TLS.
+//!
+//! Each frame carries an HMAC-SHA256 tag over its encoded message in the
+//! `mac` envelope extension. Tags are keyed from the session secret
+//! established at authentication and the nonces both sides sent during
+//! feature negotiation, so every connection has its own keys and a frame
+//! captured on one connection is rejected on any other. Each connection also
+//! has separate keys per direction so a frame cannot be reflected back to its
+//! sender. Because the tag covers the sequence number and timestamp, the
+//! receiver can then reject stale frames and replayed sequence numbers.
+
+use crate::error::DatabaseError;
+use crate::protocol::{Envelope, Message};
+use crate::types::IntegrityConfig;
+use crate::Result;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Envelope extension carrying the authentication tag
+pub const MAC_EXTENSION: &str = "mac";
+
+/// Number of sequence numbers below the highest received one that are still
+/// accepted, to tolerate reordering
+pub const REPLAY_WINDOW: u64 = 64;
+
+/// Length of the nonce each side sends during feature negotiation
+pub const NONCE_LENGTH: usize = 16;
+
+/// Key derivation label for the key of a single connection
+const CONNECTION_KEY: &[u8] = b"q-distributed-db frame auth connection";
+
+/// Key derivation label for frames sent by the client
+const CLIENT_TO_SERVER: &[u8] = b"q-distributed-db frame auth client-to-server";
+
+/// Key derivation label for frames sent by the server
+const SERVER_TO_CLIENT: &[u8] = b"q-distributed-db frame auth server-to-client";
+
+/// Signs outgoing frames and verifies incoming ones for a single connection
+///
+/// `connection_nonce` is the client nonce followed by the server nonce, as
+/// exchanged during feature negotiation. Verification keeps replay state, so
+/// each connection needs its own authenticator.
+pub struct FrameAuthenticator {
+    /// MAC keyed for frames this side sends
+    send_mac: HmacSha256,
+    /// MAC keyed for frames this side receives
+    receive_mac: HmacSha256,
+    /// Maximum accepted difference between a frame timestamp and the local clock
+    max_clock_skew_ms: u64,
+    /// Sequence numbers received so far
+    window: ReplayWindow,
+}
+
+impl FrameAuthenticator {
+    /// Creates the authenticator used by a client
+    pub fn client(session_key: &[u8], connection_nonce: &[u8], config: &IntegrityConfig) -> Self {
+        Self::new(
+            session_key,
+            connection_nonce,
+            CLIENT_TO_SERVER,
+            SERVER_TO_CLIENT,
+            config,
+        )
+    }
+
+    /// Creates the authenticator used by a server
+    pub fn server(session_key: &[u8], connection_nonce: &[u8], config: &IntegrityConfig) -> Self {
+        Self::new(
+            session_key,
+            connection_nonce,
+            SERVER_TO_CLIENT,
+            CLIENT_TO_SERVER,
+            config,
+        )
+    }
+
+    fn new(
+        session_key: &[u8],
+        connection_nonce: &[u8],
+        send_label: &[u8],
+        receive_label: &[u8],
+        config: &IntegrityConfig,
+    ) -> Self {
+        let connection_key = derive_key(session_key, &[CONNECTION_KEY, connection_nonce].concat());
+        Self {
+            send_mac: derive_mac(&connection_key, send_label),
+            receive_mac: derive_mac(&connection_key, receive_label),
+            max_clock_skew_ms: config.max_clock_skew_ms,
+            window: ReplayWindow::default(),
+        }
+    }
+
+    /// Wraps a message in an envelope carrying its authentication tag
+    pub fn sign(&self, message: Message) -> Result<Envelope> {
+        let tag = tag(&self.send_mac, &message)?;
+        Ok(Envelope::new(message).with_extension(MAC_EXTENSION, tag))
+    }
+
+    /// Verifies the tag, timestamp and sequence number of a received envelope
+    ///
+    /// The sequence number is only recorded once the tag and timestamp have
+    /// been accepted, so forged frames cannot advance the replay window.
+    pub fn verify(&mut self, envelope: &Envelope) -> Result<()> {
+        self.verify_at(envelope, chrono::Utc::now().timestamp_millis())
+    }
+
+    fn verify_at(&mut self, envelope: &Envelope, now_ms: i64) -> Result<()> {
+        let message = &envelope.message;
+
+        let received = envelope.extension(MAC_EXTENSION).ok_or_else(|| {
+            DatabaseError::MessageAuthenticationFailed {
+                reason: "missing authentication tag".to_string(),
+            }
+        })?;
+
+        let mut mac = self.receive_mac.clone();
+        mac.update(&encode(message)?);
+        mac.verify_slice(received)
+            .map_err(|_| DatabaseError::MessageAuthenticationFailed {
+                reason: "authentication tag mismatch".to_string(),
+            })?;
+
+        let skew_ms = now_ms.abs_diff(message.timestamp);
+        if skew_ms > self.max_clock_skew_ms {
+            return Err(DatabaseError::StaleMessage {
+                timestamp: message.timestamp,
+                skew_ms,
+                max_skew_ms: self.max_clock_skew_ms,
+            });
+        }
+
+        self.window.accept(message.sequence_number)
+    }
+}
+
+/// Returns a random nonce to send during feature negotiation
+pub fn connection_nonce() -> Result<Vec<u8>> {
+    let mut nonce = vec![0u8; NONCE_LENGTH];
+    getrandom::getrandom(&mut nonce).map_err(|e| DatabaseError::InternalError {
+        component: "FrameAuthenticator".to_string(),
+        details: format!("no randomness for the connection nonce: {}", e),
+    })?;
+    Ok(nonce)
+}
+
+/// Derives a key from `secret` for the purpose named by `label`
+fn derive_key(secret: &[u8], label: &[u8]) -> Vec<u8> {
+    let mut kdf = HmacSha256::new_from_slice(secret).expect("HMAC accepts any key length");
+    kdf.update(label);
+    kdf.finalize().into_bytes().to_vec()
+}
+
+/// Derives the MAC for one direction from the connection key
+fn derive_mac(connection_key: &[u8], label: &[u8]) -> HmacSha256 {
+    HmacSha256::new_from_slice(&derive_key(connection_key, label))
+        .expect("HMAC accepts any key length")
+}
+
+/// Computes the tag of a message
+fn tag(mac: &HmacSha256, message: &Message) -> Result<Vec<u8>> {
+    let mut mac = mac.clone();
+    mac.update(&encode(message)?);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+/// Encodes the message fields covered by the tag
+fn encode(message: &Message) -> Result<Vec<u8>> {
+    bincode::serialize(message).map_err(|e| DatabaseError::SerializationError {
+        message: format!("Failed to encode message for authentication: {}", e),
+    })
+}
+
+/// Sliding window of received sequence numbers
+///
+/// Tracks the highest sequence number seen and a bitmap of the
+/// `REPLAY_WINDOW` sequence numbers below it.
+#[derive(Debug, Default)]
+struct ReplayWindow {
+    /// Highest sequence number accepted so far
+    highest: Option<u64>,
+    /// Bit `n` is set if `highest - n` has been accepted
+    seen: u64,
+}
+
+impl ReplayWindow {
+    /// Records a sequence number, rejecting duplicates and stale numbers
+    fn accept(&mut self, sequence_number: u64) -> Result<()> {
+        let Some(highest) = self.highest else {
+            self.highest = Some(sequence_number);
+            self.seen = 1;
+            return Ok(());
+        };
+
+        if sequence_number > highest {
+            let shift = sequence_number - highest;
+            self.seen = if shift >= REPLAY_WINDOW {
+                0
+            } else {
+                self.seen << shift
+            } | 1;
+            self.highest = Some(sequence_number);
+            return Ok(());
+        }
+
+        let offset = highest - sequence_number;
+        let reason = if offset >= REPLAY_WINDOW {
+            "outside replay window"
+        } else if self.seen & (1 << offset) != 0 {
+            "already received"
+        } else {
+            self.seen |= 1 << offset;
+            return Ok(());
+        };
+
+        Err(DatabaseError::ReplayDetected {
+            sequence_number,
+            reason: reason.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{MessageCodec, MessageType, WireFormat};
+
+    const NOW: i64 = 1704067200000;
+
+    fn message(sequence_number: u64, timestamp: i64) -> Message {
+        Message::new(
+            1,
+            0,
+            sequence_number,
+            timestamp,
+            MessageType::Data,
+            vec![1, 2, 3],
+        )
+    }
+
+    const NONCE: &[u8] = b"client nonce server nonce";
+
+    fn pair() -> (FrameAuthenticator, FrameAuthenticator) {
+        let config = IntegrityConfig::new();
+        (
+            FrameAuthenticator::client(b"session secret", NONCE, &config),
+            FrameAuthenticator::server(b"session secret", NONCE, &config),
+        )
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let (mut client, server) = pair();
+        let envelope = server.sign(message(1, NOW)).unwrap();
+        assert_eq!(envelope.extension(MAC_EXTENSION).unwrap().len(), 32);
+        assert!(client.verify_at(&envelope, NOW).is_ok());
+    }
+
+    #[test]
+    fn test_verify_rejects_missing_tag() {
+        let (mut client, _) = pair();
+        let result = client.verify_at(&Envelope::new(message(1, NOW)), NOW);
+        assert!(matches!(
+            result,
+            Err(DatabaseError::MessageAuthenticationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_tampered_message() {
+        let (mut client, server) = pair();
+        let mut envelope = server.sign(message(1, NOW)).unwrap();
+        envelope.message.payload[0] ^= 0xFF;
+        assert!(matches!(
+            client.verify_at(&envelope, NOW),
+            Err(DatabaseError::MessageAuthenticationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_key() {
+        let (_, server) = pair();
+        let mut client =
+            FrameAuthenticator::client(b"other secret", NONCE, &IntegrityConfig::new());
+        let envelope = server.sign(message(1, NOW)).unwrap();
+        assert!(client.verify_at(&envelope, NOW).is_err());
+    }
+
+    #[test]
+    fn test_verify_rejects_frame_from_another_connection() {
+        // Same session, but a connection that negotiated different nonces
+        let (client, _) = pair();
+        let mut other = FrameAuthenticator::server(
+            b"session secret",
+            b"other client nonce other server nonce",
+            &IntegrityConfig::new(),
+        );
+        let envelope = client.sign(message(1, NOW)).unwrap();
+        assert!(matches!(
+            other.verify_at(&envelope, NOW),
+            Err(DatabaseError::MessageAuthenticationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_reflected_frame() {
+        let (mut client, _) = pair();
+        let envelope = client.sign(message(1, NOW)).unwrap();
+        assert!(matches!(
+            client.verify_at(&envelope, NOW),
+            Err(DatabaseError::MessageAuthenticationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_stale_timestamp() {
+        let (mut client, server) = pair();
+        let envelope = server.sign(message(1, NOW - 60_000)).unwrap();
+        assert!(matches!(
+            client.verify_at(&envelope, NOW),
+            Err(DatabaseError::StaleMessage {
+                skew_ms: 60_000,
+                max_skew_ms: 30_000,
+                ..
+            })
+        ));
+
+        // The rejected frame did not consume its sequence number
+        let envelope = server.sign(message(1, NOW)).unwrap();
+        assert!(client.verify_at(&envelope, NOW).is_ok());
+    }
+
+    #[test]
+    fn test_verify_rejects_replayed_frame() {
+        let (mut client, server) = pair();
+        let envelope = server.sign(message(7, NOW)).unwrap();
+        client.verify_at(&envelope, NOW).unwrap();
+        assert!(matches!(
+            client.verify_at(&envelope, NOW),
+            Err(DatabaseError::ReplayDetected {
+                sequence_number: 7,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_signed_frame_round_trip() {
+        let (mut client, server) = pair();
+        let codec = MessageCodec::new().with_wire_format(WireFormat::Versioned);
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let mut buffer = Vec::new();
+        let envelope = server.sign(message(1, now)).unwrap();
+        codec.write_envelope(&mut buffer, &envelope).await.unwrap();
+
+        let mut cursor = &buffer[..];
+        let received = codec.read_envelope(&mut cursor).await.unwrap();
+        assert!(client.verify(&received).is_ok());
+    }
+
+    #[test]
+    fn test_replay_window_accepts_reordering() {
+        let mut window = ReplayWindow::default();
+        for sequence_number in [5, 3, 10, 4, 6] {
+            window.accept(sequence_number).unwrap();
+        }
+        assert!(window.accept(3).is_err());
+        assert!(window.accept(7).is_ok());
+    }
+
+    #[test]
+    fn test_replay_window_rejects_old_sequence_numbers() {
+        let mut window = ReplayWindow::default();
+        window.accept(0).unwrap();
+        window.accept(REPLAY_WINDOW + 10).unwrap();
+
+        let result = window.accept(5);
+        assert!(matches!(
+            result,
+            Err(DatabaseError::ReplayDetected { ref reason, .. }) if reason == "outside replay window"
+        ));
+        assert!(window.accept(11).is_ok());
+    }
+}
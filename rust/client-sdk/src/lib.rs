@@ -32,35 +32,65 @@
 
 pub mod admin_client;
 pub mod auth;
+pub mod capture;
 pub mod client;
 pub mod connection;
+pub mod credentials;
 pub mod data_client;
 pub mod error;
+pub mod from_row;
+pub mod integrity;
 pub mod metrics;
+pub mod pagination;
 pub mod protocol;
 pub mod query_builder;
 pub mod result;
+pub mod row_serde;
+pub mod scram;
+pub mod secret;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod token;
 pub mod transaction;
+pub mod transport;
 pub mod types;
 
 pub use admin_client::AdminClient;
-pub use auth::{AuthToken, AuthenticationManager, Certificate, Credentials};
-pub use client::{Client, ClusterHealth};
+pub use auth::{
+    AuthRequest, AuthResponse, AuthToken, AuthenticationManager, Certificate, Credentials,
+    TokenRefresher,
+};
+pub use capture::{CaptureDirection, CaptureReader, CaptureRecord, CaptureSink};
+pub use client::{Client, ClusterHealth, UserClient, UserIdentity};
 pub use connection::{
     execute_with_timeout, Connection, ConnectionManager, ConnectionPool, NodeHealth,
-    PooledConnection, ProtocolType,
+    PooledConnection, ProtocolType, StatementCache,
+};
+pub use credentials::{
+    CommandCredentialProvider, CredentialProvider, CredentialsFuture, EnvCredentialProvider,
+    FileCredentialProvider, StaticCredentialProvider,
 };
 pub use data_client::{BatchContext, DataClient, ExecuteResult, PreparedStatement, ResultStream};
-pub use error::DatabaseError;
+pub use error::{sqlstate, DatabaseError, ServerError};
+pub use from_row::{FromRow, FromValue};
+pub use integrity::FrameAuthenticator;
 pub use metrics::{
     ClientMetrics, ConnectionMetrics, MetricsCollector, OperationMetrics, Percentiles,
 };
+pub use pagination::{KeysetPaginator, Page, PageCursor};
 pub use protocol::{
-    AdminRequest, AdminResponse, Message, MessageCodec, MessageType, Request, Response,
+    AdminRequest, AdminResponse, BatchOperation, Envelope, Extensions, FrameHeader, Message,
+    MessageCodec, MessageType, Request, Response, WireFormat,
 };
+#[cfg(feature = "derive")]
+pub use q_distributed_db_client_derive::FromRow;
 pub use query_builder::{OrderDirection, QueryBuilder, QueryType};
 pub use result::{ColumnMetadata, DataType, QueryResult, Row};
+pub use scram::{ScramClient, ScramCredentials, ScramServer, ScramVerifier};
+pub use secret::{SecretBytes, SecretString};
+pub use token::{TokenClaims, TokenSigner, TokenVerifier};
 pub use transaction::{IsolationLevel, Transaction, TransactionRequest, TransactionResponse};
+pub use transport::{Transport, TransportLayer};
 pub use types::*;
 pub use types::{Feature, FeatureNegotiation, LogConfig, LogFormat, LogLevel, TracingConfig};
 
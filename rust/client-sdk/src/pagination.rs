@@ -0,0 +1,234 @@
+//! Keyset pagination
+//!
+//! Pages are selected with a seek predicate on ordered key columns instead of
+//! an OFFSET, so a deep page costs as much as the first one and rows written
+//! between requests do not shift page boundaries. Where a page ends is
+//! captured in a `PageCursor`, whose token can be handed to API clients and
+//! resumed later.
+
+use crate::data_client::DataClient;
+use crate::error::DatabaseError;
+use crate::query_builder::{OrderBy, OrderDirection, QueryBuilder};
+use crate::result::QueryResult;
+use crate::types::Value;
+use crate::Result;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Largest number of rows a page can hold
+pub const MAX_PAGE_SIZE: u64 = u32::MAX as u64;
+
+/// Position after the last row of a page
+///
+/// Only valid for a paginator with the same key columns and directions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageCursor {
+    /// Key columns and directions, e.g. `"id ASC"`
+    keys: Vec<String>,
+    /// Key values of the last row
+    values: Vec<Value>,
+}
+
+impl PageCursor {
+    /// Encodes the cursor as an opaque, URL-safe token
+    pub fn to_token(&self) -> String {
+        // Serializing plain strings and values cannot fail
+        URL_SAFE_NO_PAD.encode(bincode::serialize(self).unwrap_or_default())
+    }
+
+    /// Decodes a token produced by `to_token`
+    pub fn from_token(token: &str) -> Result<Self> {
+        let invalid = |details: String| DatabaseError::InternalError {
+            component: "PageCursor".to_string(),
+            details,
+        };
+        let bytes = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|e| invalid(format!("Invalid cursor token: {}", e)))?;
+        bincode::deserialize(&bytes).map_err(|e| invalid(format!("Invalid cursor token: {}", e)))
+    }
+}
+
+/// One page of a paginated query
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// Rows of the page
+    pub result: QueryResult,
+    /// Resumes after this page; `None` on the last page
+    pub cursor: Option<PageCursor>,
+}
+
+/// Reads a SELECT query page by page in key order
+///
+/// The keys must be selected by the query, must not be NULL, and together
+/// must identify a row; end them with a unique column such as the primary key.
+///
+/// # Example
+///
+/// ```ignore
+/// let query = QueryBuilder::select(&["id", "created", "name"]).from("events");
+/// let mut pages = client.data().paginate(
+///     query,
+///     &[("created", OrderDirection::Desc), ("id", OrderDirection::Asc)],
+///     100,
+/// )?;
+/// while let Some(page) = pages.next_page().await? {
+///     // page.cursor.map(|c| c.to_token()) resumes after this page
+/// }
+/// ```
+#[derive(Clone)]
+pub struct KeysetPaginator {
+    /// Client the pages are queried with
+    data_client: DataClient,
+    /// Query without ORDER BY, LIMIT or OFFSET
+    query: QueryBuilder,
+    /// Key columns in sort order
+    keys: Vec<OrderBy>,
+    /// Maximum number of rows per page
+    page_size: u64,
+    /// Position after the last page returned
+    cursor: Option<PageCursor>,
+    /// Whether the last page has been returned
+    finished: bool,
+}
+
+impl KeysetPaginator {
+    /// Creates a paginator starting at the first row
+    ///
+    /// `page_size` must be between 1 and `MAX_PAGE_SIZE`.
+    pub fn new(
+        data_client: DataClient,
+        query: QueryBuilder,
+        keys: &[(&str, OrderDirection)],
+        page_size: u64,
+    ) -> Result<Self> {
+        if keys.is_empty() || page_size == 0 {
+            return Err(DatabaseError::InternalError {
+                component: "KeysetPaginator".to_string(),
+                details: "Pagination needs at least one key and a non-zero page size".to_string(),
+            });
+        }
+        // Keeps the extra row fetched per page and the page length in range
+        if page_size > MAX_PAGE_SIZE {
+            return Err(DatabaseError::InternalError {
+                component: "KeysetPaginator".to_string(),
+                details: format!("Page size must be at most {}", MAX_PAGE_SIZE),
+            });
+        }
+        let keys: Vec<OrderBy> = keys
+            .iter()
+            .map(|(column, direction)| OrderBy {
+                column: column.to_string(),
+                direction: direction.clone(),
+            })
+            .collect();
+
+        // Reject queries that cannot be paginated before any page is requested
+        query.clone().build_keyset_page(&keys, None, page_size)?;
+
+        Ok(Self {
+            data_client,
+            query,
+            keys,
+            page_size,
+            cursor: None,
+            finished: false,
+        })
+    }
+
+    /// Continues after the page the cursor was taken from
+    pub fn resume(mut self, cursor: PageCursor) -> Result<Self> {
+        if cursor.keys != self.key_names() || cursor.values.len() != self.keys.len() {
+            return Err(DatabaseError::InternalError {
+                component: "KeysetPaginator".to_string(),
+                details: "Cursor was taken from a query with different keys".to_string(),
+            });
+        }
+        self.cursor = Some(cursor);
+        self.finished = false;
+        Ok(self)
+    }
+
+    /// Returns the position after the last page returned
+    pub fn cursor(&self) -> Option<&PageCursor> {
+        self.cursor.as_ref()
+    }
+
+    /// Fetches the next page, or `None` once every row has been returned
+    pub async fn next_page(&mut self) -> Result<Option<Page>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        // One extra row tells whether another page follows
+        let after = self.cursor.as_ref().map(|cursor| cursor.values.as_slice());
+        let (sql, params) =
+            self.query
+                .clone()
+                .build_keyset_page(&self.keys, after, self.page_size + 1)?;
+        let mut result = self.data_client.query_with_params(&sql, &params).await?;
+        let has_more = result.rows.len() as u64 > self.page_size;
+        result.rows.truncate(self.page_size as usize);
+
+        let Some(last) = result.rows.last() else {
+            self.finished = true;
+            return Ok(None);
+        };
+        let mut values = Vec::with_capacity(self.keys.len());
+        for key in &self.keys {
+            let value = last.get_by_name(&key.column)?;
+            if value.is_null() {
+                return Err(DatabaseError::InternalError {
+                    component: "KeysetPaginator".to_string(),
+                    details: format!("Key column {} is NULL", key.column),
+                });
+            }
+            values.push(value.clone());
+        }
+
+        let cursor = PageCursor {
+            keys: self.key_names(),
+            values,
+        };
+        self.cursor = Some(cursor.clone());
+        self.finished = !has_more;
+        Ok(Some(Page {
+            result,
+            cursor: has_more.then_some(cursor),
+        }))
+    }
+
+    /// Describes the keys, as recorded in cursors
+    fn key_names(&self) -> Vec<String> {
+        self.keys
+            .iter()
+            .map(|key| match key.direction {
+                OrderDirection::Asc => format!("{} ASC", key.column),
+                OrderDirection::Desc => format!("{} DESC", key.column),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_token_round_trip() {
+        let cursor = PageCursor {
+            keys: vec!["created DESC".to_string(), "id ASC".to_string()],
+            values: vec![
+                Value::Int(1_700_000_000),
+                Value::String("a/b+c".to_string()),
+            ],
+        };
+        let token = cursor.to_token();
+        assert!(token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(PageCursor::from_token(&token).unwrap(), cursor);
+        assert!(PageCursor::from_token("not a token").is_err());
+    }
+}
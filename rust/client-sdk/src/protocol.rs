@@ -2,15 +2,25 @@
 //!
 //! This module implements the message protocol with bincode serialization,
 //! CRC32 checksum validation, and length-prefixed framing.
+//!
+//! Frames are either legacy `[algorithm_id][bincode message]` frames or
+//! versioned frames with a `FrameHeader` and an `Envelope` body. Bincode
+//! structs are positional, so their layout is frozen: readers tolerate
+//! trailing bytes, but new optional fields must travel as envelope extensions.
 
 use crate::connection::ProtocolType;
-use crate::error::DatabaseError;
+use crate::data_client::ExecuteResult;
+use crate::error::{DatabaseError, ServerError};
+use crate::result::ColumnMetadata;
+use crate::secret::{SecretBytes, SecretString};
 use crate::types::{
-    ClusterMetrics, ClusterNodeInfo, NodeHealthMetrics, NodeId, Permission, Role, Timestamp,
-    UserId, UserInfo, UserUpdate,
+    ClusterMetrics, ClusterNodeInfo, CompressionAlgorithm, CompressionConfig, NodeHealthMetrics,
+    NodeId, Permission, Role, StatementId, StreamId, Timestamp, TransactionId, UserId, UserInfo,
+    UserUpdate, Value,
 };
 use crc32fast::Hasher;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 /// Message type enumeration
@@ -42,6 +52,8 @@ pub enum MessageType {
     Admin,
     /// Feature negotiation message
     FeatureNegotiation,
+    /// Authentication message
+    Auth,
 }
 
 /// Message structure
@@ -116,6 +128,7 @@ impl Message {
             MessageType::Transaction => 9u8,
             MessageType::Admin => 10u8,
             MessageType::FeatureNegotiation => 11u8,
+            MessageType::Auth => 12u8,
         };
         hasher.update(&[type_discriminant]);
 
@@ -132,12 +145,319 @@ impl Message {
         let calculated = self.calculate_checksum();
         self.checksum == calculated
     }
+
+    /// Converts an error frame into the matching `DatabaseError`
+    ///
+    /// Messages of any other type are returned unchanged. `sql` and
+    /// `transaction_id` describe the request the message answers.
+    pub fn check_server_error(
+        self,
+        sql: Option<&str>,
+        transaction_id: Option<TransactionId>,
+    ) -> Result<Message, DatabaseError> {
+        if self.message_type != MessageType::Error {
+            return Ok(self);
+        }
+
+        let error = ServerError::from_payload(&self.payload)?;
+        Err(error.into_database_error(sql, transaction_id))
+    }
+}
+
+// ============================================================================
+// Versioned Wire Envelope
+// ============================================================================
+
+/// Magic bytes that open every versioned frame
+///
+/// The first magic byte is never a valid compression algorithm id, which lets a
+/// decoder tell versioned frames apart from legacy `[algorithm_id][data]` frames.
+pub const FRAME_MAGIC: [u8; 2] = *b"QD";
+
+/// Wire format version written by this client
+pub const FRAME_VERSION: u8 = 1;
+
+/// Length in bytes of the version 1 frame header
+pub const FRAME_HEADER_LEN: usize = 7;
+
+/// Frame flag: the body carries an extension map after the message
+pub const FLAG_EXTENSIONS: u16 = 0x0001;
+
+/// Frame flag: the frame is one fragment of a larger logical frame
+///
+/// This is a required flag, so peers that cannot reassemble reject the frame
+/// instead of misreading it.
+pub const FLAG_FRAGMENT: u16 = 0x0100;
+
+/// Length in bytes of a fragment frame header
+///
+/// The version 1 header is followed by
+/// `[fragment_index: u32 BE][total_len: u64 BE][fragment_crc32: u32 BE]`.
+pub const FRAGMENT_HEADER_LEN: usize = FRAME_HEADER_LEN + 16;
+
+/// Default cap on the size of a logical frame reassembled from fragments (64MB)
+pub const DEFAULT_MAX_REASSEMBLED_SIZE: usize = 64 * 1024 * 1024;
+
+/// Flags in this mask must be understood by the reader
+///
+/// Unknown bits in the low byte are ignored, so optional behaviour can be
+/// added without breaking older peers. Unknown bits in the high byte mark an
+/// incompatible change and cause the frame to be rejected.
+pub const REQUIRED_FLAGS_MASK: u16 = 0xFF00;
+
+/// Flags understood by this implementation
+const KNOWN_FLAGS: u16 = FLAG_EXTENSIONS | FLAG_FRAGMENT;
+
+/// Wire format used when writing frames
+///
+/// Decoding always accepts both formats. Connections write legacy frames until
+/// the server has agreed to `Feature::VersionedFraming`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WireFormat {
+    /// `[algorithm_id: u8][bincode message]`, understood by every peer
+    #[default]
+    Legacy,
+    /// Versioned frame header followed by a message envelope
+    Versioned,
+}
+
+/// Header at the start of every versioned frame
+///
+/// Layout (version 1):
+/// `[magic: 2 bytes][version: u8][header_len: u8][flags: u16 BE][algorithm_id: u8]`
+///
+/// `header_len` covers the whole header including the magic, so later versions
+/// can append header fields that older readers skip over. Newer versions are
+/// accepted; incompatible changes must be signalled with a required flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// Wire format version of the sender
+    pub version: u8,
+    /// Frame flags
+    pub flags: u16,
+    /// Compression applied to the body
+    pub compression: CompressionAlgorithm,
+}
+
+impl FrameHeader {
+    /// Creates a header for the current wire format version
+    pub fn new(flags: u16, compression: CompressionAlgorithm) -> Self {
+        Self {
+            version: FRAME_VERSION,
+            flags,
+            compression,
+        }
+    }
+
+    /// Returns true if the given flag is set
+    pub fn has_flag(&self, flag: u16) -> bool {
+        self.flags & flag == flag
+    }
+
+    /// Returns the header length written for this header
+    pub fn header_len(&self) -> usize {
+        if self.has_flag(FLAG_FRAGMENT) {
+            FRAGMENT_HEADER_LEN
+        } else {
+            FRAME_HEADER_LEN
+        }
+    }
+
+    /// Appends the encoded header to a buffer
+    ///
+    /// Fragment headers are completed by `FragmentHeader::write_to`.
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&FRAME_MAGIC);
+        buf.push(self.version);
+        buf.push(self.header_len() as u8);
+        buf.extend_from_slice(&self.flags.to_be_bytes());
+        buf.push(self.compression.id());
+    }
+
+    /// Parses a header from the start of a frame
+    ///
+    /// Returns the header and the offset at which the body starts.
+    pub fn parse(data: &[u8]) -> Result<(Self, usize), DatabaseError> {
+        if data.len() < FRAME_HEADER_LEN {
+            return Err(DatabaseError::SerializationError {
+                message: format!("Truncated frame header: {} bytes", data.len()),
+            });
+        }
+
+        if data[..2] != FRAME_MAGIC {
+            return Err(DatabaseError::SerializationError {
+                message: "Invalid frame magic".to_string(),
+            });
+        }
+
+        let version = data[2];
+        if version == 0 {
+            return Err(DatabaseError::SerializationError {
+                message: "Invalid frame version: 0".to_string(),
+            });
+        }
+
+        let header_len = data[3] as usize;
+        if header_len < FRAME_HEADER_LEN || header_len > data.len() {
+            return Err(DatabaseError::SerializationError {
+                message: format!("Invalid frame header length: {}", header_len),
+            });
+        }
+
+        let flags = u16::from_be_bytes([data[4], data[5]]);
+        let unknown_required = flags & REQUIRED_FLAGS_MASK & !KNOWN_FLAGS;
+        if unknown_required != 0 {
+            return Err(DatabaseError::SerializationError {
+                message: format!(
+                    "Unsupported required frame flags: {:#06x}",
+                    unknown_required
+                ),
+            });
+        }
+
+        let compression = CompressionAlgorithm::from_id(data[6]).ok_or_else(|| {
+            DatabaseError::SerializationError {
+                message: format!("Unknown compression algorithm id: {}", data[6]),
+            }
+        })?;
+
+        Ok((
+            Self {
+                version,
+                flags,
+                compression,
+            },
+            header_len,
+        ))
+    }
+}
+
+/// Header fields of a fragment frame
+///
+/// A logical frame larger than `max_message_size` is split into fragments that
+/// are sent back to back on the connection. Each fragment carries its index,
+/// the total length of the logical frame and a CRC32 of its own data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentHeader {
+    /// Position of the fragment, starting at 0
+    pub index: u32,
+    /// Length of the reassembled logical frame
+    pub total_len: u64,
+    /// CRC32 of the fragment data
+    pub checksum: u32,
+}
+
+impl FragmentHeader {
+    /// Appends the fragment fields to a buffer, after the frame header
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.index.to_be_bytes());
+        buf.extend_from_slice(&self.total_len.to_be_bytes());
+        buf.extend_from_slice(&self.checksum.to_be_bytes());
+    }
+
+    /// Splits a frame into its fragment header and data
+    ///
+    /// Returns `None` for frames that are not fragments. The fragment checksum
+    /// is verified before the data is returned.
+    pub fn parse(frame: &[u8]) -> Result<Option<(Self, &[u8])>, DatabaseError> {
+        if frame.first() != Some(&FRAME_MAGIC[0]) {
+            return Ok(None);
+        }
+
+        let (header, body_offset) = FrameHeader::parse(frame)?;
+        if !header.has_flag(FLAG_FRAGMENT) {
+            return Ok(None);
+        }
+
+        if body_offset < FRAGMENT_HEADER_LEN {
+            return Err(DatabaseError::SerializationError {
+                message: format!("Invalid fragment header length: {}", body_offset),
+            });
+        }
+
+        let field = |offset: usize, len: usize| &frame[FRAME_HEADER_LEN + offset..][..len];
+        let fragment = Self {
+            index: u32::from_be_bytes(field(0, 4).try_into().unwrap_or_default()),
+            total_len: u64::from_be_bytes(field(4, 8).try_into().unwrap_or_default()),
+            checksum: u32::from_be_bytes(field(12, 4).try_into().unwrap_or_default()),
+        };
+
+        let data = &frame[body_offset..];
+        let actual = crc32fast::hash(data);
+        if actual != fragment.checksum {
+            return Err(DatabaseError::ChecksumMismatch {
+                expected: fragment.checksum,
+                actual,
+            });
+        }
+
+        Ok(Some((fragment, data)))
+    }
+}
+
+/// A logical frame being reassembled from fragments
+struct Reassembly {
+    /// Length of the complete logical frame
+    total_len: usize,
+    /// Index expected for the next fragment
+    next_index: u32,
+    /// Data received so far
+    data: Vec<u8>,
+}
+
+/// Named extension fields carried alongside a message
+///
+/// Readers ignore keys they don't recognise. New optional fields belong here
+/// rather than in `Message` or in positional request structs, whose layout is
+/// frozen for compatibility with older peers.
+pub type Extensions = BTreeMap<String, Vec<u8>>;
+
+/// A message together with its extension fields
+///
+/// Body layout (before compression):
+/// `[message_len: u32 BE][bincode message][bincode extensions, if FLAG_EXTENSIONS]`
+///
+/// Bytes after the known sections are ignored, so a newer peer may append
+/// sections without breaking this reader.
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    /// The wrapped message
+    pub message: Message,
+    /// Extension fields
+    pub extensions: Extensions,
+}
+
+impl Envelope {
+    /// Wraps a message with no extensions
+    pub fn new(message: Message) -> Self {
+        Self {
+            message,
+            extensions: Extensions::new(),
+        }
+    }
+
+    /// Adds an extension field
+    pub fn with_extension(mut self, key: impl Into<String>, value: Vec<u8>) -> Self {
+        self.extensions.insert(key.into(), value);
+        self
+    }
+
+    /// Returns the value of an extension field
+    pub fn extension(&self, key: &str) -> Option<&[u8]> {
+        self.extensions.get(key).map(Vec::as_slice)
+    }
+}
+
+impl From<Message> for Envelope {
+    fn from(message: Message) -> Self {
+        Self::new(message)
+    }
 }
 
 /// Message codec for serialization and deserialization
 ///
 /// Handles encoding/decoding messages with bincode, length-prefixed framing,
-/// message size validation, and optional LZ4 compression.
+/// message size validation, and optional compression (LZ4, zstd or Snappy).
 pub struct MessageCodec {
     /// Maximum allowed message size in bytes
     max_message_size: usize,
@@ -145,34 +465,34 @@ pub struct MessageCodec {
     pub compression_enabled: bool,
     /// Compression threshold in bytes
     compression_threshold: usize,
+    /// Algorithms available for outgoing messages, in order of preference
+    compression_algorithms: Vec<CompressionAlgorithm>,
+    /// Zstandard compression level
+    zstd_level: i32,
+    /// Size above which the high-ratio algorithm is preferred
+    high_ratio_threshold: usize,
+    /// Format used for outgoing frames
+    wire_format: WireFormat,
+    /// Whether oversized messages are split into fragment frames
+    fragmentation_enabled: bool,
+    /// Maximum size of a logical frame reassembled from fragments
+    max_reassembled_size: usize,
 }
 
 impl MessageCodec {
     /// Creates a new message codec with the default maximum message size (1MB)
     pub fn new() -> Self {
-        Self {
-            max_message_size: 1024 * 1024, // 1MB default
-            compression_enabled: false,
-            compression_threshold: 1024,
-        }
+        Self::with_settings(1024 * 1024, false, 1024)
     }
 
     /// Creates a new message codec with a custom maximum message size
     pub fn with_max_size(max_message_size: usize) -> Self {
-        Self {
-            max_message_size,
-            compression_enabled: false,
-            compression_threshold: 1024,
-        }
+        Self::with_settings(max_message_size, false, 1024)
     }
 
     /// Creates a new message codec with compression settings
     pub fn with_compression(compression_enabled: bool, compression_threshold: usize) -> Self {
-        Self {
-            max_message_size: 1024 * 1024,
-            compression_enabled,
-            compression_threshold,
-        }
+        Self::with_settings(1024 * 1024, compression_enabled, compression_threshold)
     }
 
     /// Creates a new message codec with all settings
@@ -181,104 +501,511 @@ impl MessageCodec {
         compression_enabled: bool,
         compression_threshold: usize,
     ) -> Self {
+        let defaults = CompressionConfig::default();
         Self {
             max_message_size,
             compression_enabled,
             compression_threshold,
+            compression_algorithms: defaults.algorithms,
+            zstd_level: defaults.zstd_level,
+            high_ratio_threshold: defaults.high_ratio_threshold,
+            wire_format: WireFormat::default(),
+            fragmentation_enabled: false,
+            max_reassembled_size: DEFAULT_MAX_REASSEMBLED_SIZE,
         }
     }
 
-    /// Encodes a message to bytes using bincode with optional LZ4 compression
+    /// Applies a compression algorithm configuration
+    pub fn with_compression_config(mut self, config: &CompressionConfig) -> Self {
+        self.compression_algorithms = config.algorithms.clone();
+        self.zstd_level = config.zstd_level;
+        self.high_ratio_threshold = config.high_ratio_threshold;
+        self
+    }
+
+    /// Sets the format used for outgoing frames
+    pub fn with_wire_format(mut self, wire_format: WireFormat) -> Self {
+        self.wire_format = wire_format;
+        self
+    }
+
+    /// Switches the format used for outgoing frames
+    pub fn set_wire_format(&mut self, wire_format: WireFormat) {
+        self.wire_format = wire_format;
+    }
+
+    /// Returns the format used for outgoing frames
+    pub fn wire_format(&self) -> WireFormat {
+        self.wire_format
+    }
+
+    /// Sets the maximum size of a logical frame reassembled from fragments
+    ///
+    /// This caps incoming fragmented messages regardless of whether outgoing
+    /// fragmentation is enabled.
+    pub fn with_max_reassembled_size(mut self, max_reassembled_size: usize) -> Self {
+        self.max_reassembled_size = max_reassembled_size;
+        self
+    }
+
+    /// Enables or disables splitting oversized outgoing messages into fragments
+    ///
+    /// Fragmentation only applies to the versioned wire format.
+    pub fn set_fragmentation_enabled(&mut self, enabled: bool) {
+        self.fragmentation_enabled = enabled;
+    }
+
+    /// Returns true if oversized outgoing messages are split into fragments
+    pub fn fragmentation_enabled(&self) -> bool {
+        self.fragmentation_enabled && self.wire_format == WireFormat::Versioned
+    }
+
+    /// Restricts outgoing compression to the given algorithms
+    ///
+    /// Used after feature negotiation to keep only the algorithms the peer
+    /// understands. An empty list disables compression entirely.
+    pub fn set_compression_algorithms(&mut self, algorithms: Vec<CompressionAlgorithm>) {
+        if algorithms.is_empty() {
+            self.compression_enabled = false;
+        }
+        self.compression_algorithms = algorithms;
+    }
+
+    /// Returns the algorithms available for outgoing messages
+    pub fn compression_algorithms(&self) -> &[CompressionAlgorithm] {
+        &self.compression_algorithms
+    }
+
+    /// Selects the compression algorithm for a serialized message of the given size
+    ///
+    /// Small messages are sent uncompressed, large messages prefer zstd when it
+    /// is available, and everything in between uses the first fast algorithm.
+    pub fn select_compression(&self, size: usize) -> CompressionAlgorithm {
+        if !self.compression_enabled || size <= self.compression_threshold {
+            return CompressionAlgorithm::None;
+        }
+
+        let available = |algorithm: &CompressionAlgorithm| {
+            *algorithm != CompressionAlgorithm::None
+                && self.compression_algorithms.contains(algorithm)
+        };
+
+        if size > self.high_ratio_threshold && available(&CompressionAlgorithm::Zstd) {
+            return CompressionAlgorithm::Zstd;
+        }
+
+        self.compression_algorithms
+            .iter()
+            .find(|a| a.is_fast())
+            .or_else(|| self.compression_algorithms.iter().find(|a| available(a)))
+            .copied()
+            .unwrap_or(CompressionAlgorithm::None)
+    }
+
+    /// Encodes a message to bytes using bincode with optional compression
     ///
     /// Returns an error if serialization fails or if the message exceeds the size limit.
-    /// Messages larger than the compression threshold will be compressed if compression is enabled.
-    /// Format: [compression_flag: u8][data: bytes]
-    /// - compression_flag: 0 = uncompressed, 1 = LZ4 compressed
+    /// The compression algorithm is chosen per message by `select_compression`.
+    /// Legacy format: [algorithm_id: u8][data: bytes]
+    /// - algorithm_id: 0 = uncompressed, 1 = LZ4, 2 = zstd, 3 = Snappy
+    ///
+    /// Versioned format: [FrameHeader][envelope body], see `FrameHeader` and `Envelope`.
     pub fn encode(&self, message: &Message) -> Result<Vec<u8>, DatabaseError> {
+        self.encode_parts(message, &Extensions::new(), None, self.max_message_size)
+    }
+
+    /// Encodes a message into one or more frames
+    ///
+    /// When fragmentation is enabled, messages up to the reassembly cap are
+    /// encoded as a single logical frame and split into fragment frames whose
+    /// bodies fit within `max_message_size`. Otherwise this is `encode`.
+    pub fn encode_frames(&self, message: &Message) -> Result<Vec<Vec<u8>>, DatabaseError> {
+        self.encode_parts_frames(message, &Extensions::new())
+    }
+
+    /// Encodes an envelope into one or more frames
+    ///
+    /// See `encode_frames`.
+    pub fn encode_envelope_frames(
+        &self,
+        envelope: &Envelope,
+    ) -> Result<Vec<Vec<u8>>, DatabaseError> {
+        self.encode_parts_frames(&envelope.message, &envelope.extensions)
+    }
+
+    /// Encodes a message and extensions into one or more frames
+    fn encode_parts_frames(
+        &self,
+        message: &Message,
+        extensions: &Extensions,
+    ) -> Result<Vec<Vec<u8>>, DatabaseError> {
+        if !self.fragmentation_enabled() {
+            return Ok(vec![self.encode_parts(
+                message,
+                extensions,
+                None,
+                self.max_message_size,
+            )?]);
+        }
+
+        let frame = self.encode_parts(message, extensions, None, self.max_reassembled_size)?;
+        if frame.len() <= FRAME_HEADER_LEN + self.max_message_size {
+            return Ok(vec![frame]);
+        }
+
+        Ok(self.fragment(&frame))
+    }
+
+    /// Splits a logical frame into fragment frames
+    fn fragment(&self, frame: &[u8]) -> Vec<Vec<u8>> {
+        let header = FrameHeader::new(FLAG_FRAGMENT, CompressionAlgorithm::None);
+
+        frame
+            .chunks(self.max_message_size.max(1))
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut fragment = Vec::with_capacity(FRAGMENT_HEADER_LEN + chunk.len());
+                header.write_to(&mut fragment);
+                FragmentHeader {
+                    index: index as u32,
+                    total_len: frame.len() as u64,
+                    checksum: crc32fast::hash(chunk),
+                }
+                .write_to(&mut fragment);
+                fragment.extend_from_slice(chunk);
+                fragment
+            })
+            .collect()
+    }
+
+    /// Encodes a message using an explicitly chosen compression algorithm
+    ///
+    /// Bypasses the size-based selection, e.g. to force zstd for a known
+    /// highly compressible payload.
+    pub fn encode_with_compression(
+        &self,
+        message: &Message,
+        algorithm: CompressionAlgorithm,
+    ) -> Result<Vec<u8>, DatabaseError> {
+        self.encode_parts(
+            message,
+            &Extensions::new(),
+            Some(algorithm),
+            self.max_message_size,
+        )
+    }
+
+    /// Encodes a message together with its extension fields
+    ///
+    /// Extensions can only be carried by the versioned wire format.
+    pub fn encode_envelope(&self, envelope: &Envelope) -> Result<Vec<u8>, DatabaseError> {
+        self.encode_parts(
+            &envelope.message,
+            &envelope.extensions,
+            None,
+            self.max_message_size,
+        )
+    }
+
+    /// Encodes a message in the configured wire format
+    ///
+    /// `max_size` bounds the uncompressed body.
+    fn encode_parts(
+        &self,
+        message: &Message,
+        extensions: &Extensions,
+        algorithm: Option<CompressionAlgorithm>,
+        max_size: usize,
+    ) -> Result<Vec<u8>, DatabaseError> {
+        let encoded = self.serialize(message, max_size)?;
+
+        match self.wire_format {
+            WireFormat::Legacy => {
+                if !extensions.is_empty() {
+                    return Err(DatabaseError::SerializationError {
+                        message: "Extensions require the versioned wire format".to_string(),
+                    });
+                }
+
+                let algorithm = algorithm.unwrap_or_else(|| self.select_compression(encoded.len()));
+                let mut frame = Vec::with_capacity(encoded.len() + 1);
+                frame.push(algorithm.id());
+                self.compress_into(&mut frame, &encoded, algorithm)?;
+                Ok(frame)
+            }
+            WireFormat::Versioned => {
+                let mut flags = 0;
+                let mut body = Vec::with_capacity(encoded.len() + 4);
+                body.extend_from_slice(&(encoded.len() as u32).to_be_bytes());
+                body.extend_from_slice(&encoded);
+
+                if !extensions.is_empty() {
+                    flags |= FLAG_EXTENSIONS;
+                    bincode::serialize_into(&mut body, extensions).map_err(|e| {
+                        DatabaseError::SerializationError {
+                            message: format!("Failed to serialize extensions: {}", e),
+                        }
+                    })?;
+                }
+
+                if body.len() > max_size {
+                    return Err(DatabaseError::MessageTooLarge {
+                        size: body.len(),
+                        max_size,
+                    });
+                }
+
+                let algorithm = algorithm.unwrap_or_else(|| self.select_compression(body.len()));
+                let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + body.len());
+                FrameHeader::new(flags, algorithm).write_to(&mut frame);
+                self.compress_into(&mut frame, &body, algorithm)?;
+                Ok(frame)
+            }
+        }
+    }
+
+    /// Serializes a message with bincode and enforces the size limit
+    fn serialize(&self, message: &Message, max_size: usize) -> Result<Vec<u8>, DatabaseError> {
         let encoded =
             bincode::serialize(message).map_err(|e| DatabaseError::SerializationError {
                 message: format!("Failed to serialize message: {}", e),
             })?;
 
         // Check message size before compression
-        if encoded.len() > self.max_message_size {
+        if encoded.len() > max_size {
             return Err(DatabaseError::MessageTooLarge {
                 size: encoded.len(),
-                max_size: self.max_message_size,
+                max_size,
             });
         }
 
-        // Compress if enabled and above threshold
-        if self.compression_enabled && encoded.len() > self.compression_threshold {
-            let compressed = lz4_flex::compress_prepend_size(&encoded);
-            // Prepend compression flag (1 = compressed)
-            let mut result = Vec::with_capacity(compressed.len() + 1);
-            result.push(1u8);
-            result.extend_from_slice(&compressed);
-            Ok(result)
-        } else {
-            // Prepend compression flag (0 = uncompressed)
-            let mut result = Vec::with_capacity(encoded.len() + 1);
-            result.push(0u8);
-            result.extend_from_slice(&encoded);
-            Ok(result)
+        Ok(encoded)
+    }
+
+    /// Compresses data with the given algorithm and appends it to a frame
+    fn compress_into(
+        &self,
+        frame: &mut Vec<u8>,
+        data: &[u8],
+        algorithm: CompressionAlgorithm,
+    ) -> Result<(), DatabaseError> {
+        match algorithm {
+            CompressionAlgorithm::None => frame.extend_from_slice(data),
+            CompressionAlgorithm::Lz4 => {
+                frame.extend_from_slice(&lz4_flex::compress_prepend_size(data))
+            }
+            CompressionAlgorithm::Zstd => {
+                let compressed = zstd::bulk::compress(data, self.zstd_level).map_err(|e| {
+                    DatabaseError::SerializationError {
+                        message: format!("Failed to compress message with zstd: {}", e),
+                    }
+                })?;
+                frame.extend_from_slice(&compressed);
+            }
+            CompressionAlgorithm::Snappy => {
+                let compressed = snap::raw::Encoder::new().compress_vec(data).map_err(|e| {
+                    DatabaseError::SerializationError {
+                        message: format!("Failed to compress message with snappy: {}", e),
+                    }
+                })?;
+                frame.extend_from_slice(&compressed);
+            }
         }
+        Ok(())
     }
 
-    /// Decodes a message from bytes using bincode with optional LZ4 decompression
+    /// Decompresses a frame body into at most `max_size` bytes
+    ///
+    /// Peers are untrusted, so the decompressed size is bounded before the
+    /// output is allocated: the size prefixes of LZ4 and Snappy bodies are
+    /// checked up front and zstd output is read incrementally and cut off
+    /// past the limit.
+    fn decompress(
+        &self,
+        payload: &[u8],
+        algorithm: CompressionAlgorithm,
+        max_size: usize,
+    ) -> Result<Vec<u8>, DatabaseError> {
+        let too_large = |size: usize| DatabaseError::MessageTooLarge { size, max_size };
+
+        match algorithm {
+            CompressionAlgorithm::None => Ok(payload.to_vec()),
+            CompressionAlgorithm::Lz4 => {
+                let size = payload
+                    .get(..4)
+                    .map(|prefix| u32::from_le_bytes(prefix.try_into().unwrap()) as usize)
+                    .ok_or_else(|| DatabaseError::SerializationError {
+                        message: "Failed to decompress message: missing size prefix".to_string(),
+                    })?;
+                if size > max_size {
+                    return Err(too_large(size));
+                }
+
+                lz4_flex::decompress_size_prepended(payload).map_err(|e| {
+                    DatabaseError::SerializationError {
+                        message: format!("Failed to decompress message: {}", e),
+                    }
+                })
+            }
+            CompressionAlgorithm::Zstd => {
+                use std::io::Read;
+
+                let zstd_error = |e: std::io::Error| DatabaseError::SerializationError {
+                    message: format!("Failed to decompress zstd message: {}", e),
+                };
+                let decoder =
+                    zstd::stream::read::Decoder::with_buffer(payload).map_err(zstd_error)?;
+
+                // Read one byte past the limit to tell a full body from an oversized one
+                let mut decompressed = Vec::new();
+                decoder
+                    .take(max_size as u64 + 1)
+                    .read_to_end(&mut decompressed)
+                    .map_err(zstd_error)?;
+                if decompressed.len() > max_size {
+                    return Err(too_large(decompressed.len()));
+                }
+                Ok(decompressed)
+            }
+            CompressionAlgorithm::Snappy => {
+                let snappy_error = |e: snap::Error| DatabaseError::SerializationError {
+                    message: format!("Failed to decompress snappy message: {}", e),
+                };
+                let size = snap::raw::decompress_len(payload).map_err(snappy_error)?;
+                if size > max_size {
+                    return Err(too_large(size));
+                }
+
+                snap::raw::Decoder::new()
+                    .decompress_vec(payload)
+                    .map_err(snappy_error)
+            }
+        }
+    }
+
+    /// Deserializes a bincode message and verifies its checksum
+    fn deserialize(&self, data: &[u8]) -> Result<Message, DatabaseError> {
+        let message: Message =
+            bincode::deserialize(data).map_err(|e| DatabaseError::SerializationError {
+                message: format!("Failed to deserialize message: {}", e),
+            })?;
+
+        // Verify checksum
+        if !message.verify_checksum() {
+            let expected = message.calculate_checksum();
+            return Err(DatabaseError::ChecksumMismatch {
+                expected,
+                actual: message.checksum,
+            });
+        }
+
+        Ok(message)
+    }
+
+    /// Decodes a message from bytes using bincode with optional decompression
     ///
     /// Returns an error if deserialization fails or if checksum validation fails.
-    /// Automatically detects and decompresses compressed messages based on the compression flag.
-    /// Format: [compression_flag: u8][data: bytes]
+    /// Both legacy and versioned frames are accepted, and any known compression
+    /// algorithm is accepted regardless of the local preference list; unknown
+    /// algorithm ids are rejected. Extension fields are discarded, use
+    /// `decode_envelope` to keep them.
     pub fn decode(&self, data: &[u8]) -> Result<Message, DatabaseError> {
-        // Check minimum size (at least 1 byte for compression flag)
+        self.decode_envelope(data).map(|envelope| envelope.message)
+    }
+
+    /// Decodes a frame into a message and its extension fields
+    ///
+    /// Legacy frames always decode with an empty extension map.
+    pub fn decode_envelope(&self, data: &[u8]) -> Result<Envelope, DatabaseError> {
+        // Check minimum size (at least 1 byte for the algorithm id or magic)
         if data.is_empty() {
             return Err(DatabaseError::SerializationError {
                 message: "Empty data buffer".to_string(),
             });
         }
 
+        if data[0] == FRAME_MAGIC[0] {
+            self.decode_versioned(data, self.max_message_size)
+        } else {
+            self.decode_legacy(data).map(Envelope::new)
+        }
+    }
+
+    /// Decodes a legacy `[algorithm_id][data]` frame
+    fn decode_legacy(&self, data: &[u8]) -> Result<Message, DatabaseError> {
         // Check message size
         if data.len() > self.max_message_size + 1 {
-            // +1 for compression flag
+            // +1 for the algorithm id
             return Err(DatabaseError::MessageTooLarge {
                 size: data.len() - 1,
                 max_size: self.max_message_size,
             });
         }
 
-        // Read compression flag
-        let compression_flag = data[0];
-        let payload = &data[1..];
+        // Read algorithm id
+        let algorithm = CompressionAlgorithm::from_id(data[0]).ok_or_else(|| {
+            DatabaseError::SerializationError {
+                message: format!("Unknown compression algorithm id: {}", data[0]),
+            }
+        })?;
+
+        let decompressed = self.decompress(&data[1..], algorithm, self.max_message_size)?;
+        self.deserialize(&decompressed)
+    }
+
+    /// Decodes a versioned frame whose body is at most `max_size` bytes
+    fn decode_versioned(&self, data: &[u8], max_size: usize) -> Result<Envelope, DatabaseError> {
+        let (header, body_offset) = FrameHeader::parse(data)?;
+        if header.has_flag(FLAG_FRAGMENT) {
+            return Err(DatabaseError::SerializationError {
+                message: "Fragment frames must be reassembled before decoding".to_string(),
+            });
+        }
+
+        let body = &data[body_offset..];
+        if body.len() > max_size {
+            return Err(DatabaseError::MessageTooLarge {
+                size: body.len(),
+                max_size,
+            });
+        }
+
+        let body = self.decompress(body, header.compression, max_size)?;
+        if body.len() < 4 {
+            return Err(DatabaseError::SerializationError {
+                message: "Truncated envelope: missing message length".to_string(),
+            });
+        }
+
+        let message_len = u32::from_be_bytes([body[0], body[1], body[2], body[3]]) as usize;
+        let rest = &body[4..];
+        if message_len > rest.len() {
+            return Err(DatabaseError::SerializationError {
+                message: format!(
+                    "Truncated envelope: message length {} exceeds body of {} bytes",
+                    message_len,
+                    rest.len()
+                ),
+            });
+        }
+
+        let message = self.deserialize(&rest[..message_len])?;
 
-        // Decompress if needed
-        let decompressed = if compression_flag == 1 {
-            // Compressed data
-            lz4_flex::decompress_size_prepended(payload).map_err(|e| {
+        // Anything after the known sections comes from a newer peer and is ignored
+        let extensions = if header.has_flag(FLAG_EXTENSIONS) {
+            bincode::deserialize(&rest[message_len..]).map_err(|e| {
                 DatabaseError::SerializationError {
-                    message: format!("Failed to decompress message: {}", e),
+                    message: format!("Failed to deserialize extensions: {}", e),
                 }
             })?
         } else {
-            // Uncompressed data
-            payload.to_vec()
+            Extensions::new()
         };
 
-        let message: Message =
-            bincode::deserialize(&decompressed).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to deserialize message: {}", e),
-            })?;
-
-        // Verify checksum
-        if !message.verify_checksum() {
-            let expected = message.calculate_checksum();
-            return Err(DatabaseError::ChecksumMismatch {
-                expected,
-                actual: message.checksum,
-            });
-        }
-
-        Ok(message)
+        Ok(Envelope {
+            message,
+            extensions,
+        })
     }
 
     /// Encodes a message with a 4-byte big-endian length prefix
@@ -298,10 +1025,82 @@ impl MessageCodec {
     /// Reads a message from an async reader
     ///
     /// Reads the 4-byte length prefix first, then reads the message data.
+    /// Fragment frames are collected until the logical frame is complete; its
+    /// declared size is checked against the reassembly cap before any data is
+    /// buffered.
     pub async fn read_message<R: AsyncRead + Unpin>(
         &self,
         reader: &mut R,
     ) -> Result<Message, DatabaseError> {
+        self.read_envelope(reader)
+            .await
+            .map(|envelope| envelope.message)
+    }
+
+    /// Reads a message and its extensions from an async reader
+    ///
+    /// See `read_message`.
+    pub async fn read_envelope<R: AsyncRead + Unpin>(
+        &self,
+        reader: &mut R,
+    ) -> Result<Envelope, DatabaseError> {
+        let mut reassembly: Option<Reassembly> = None;
+
+        loop {
+            let frame = self.read_frame(reader).await?;
+
+            let Some((fragment, data)) = FragmentHeader::parse(&frame)? else {
+                if reassembly.is_some() {
+                    return Err(DatabaseError::SerializationError {
+                        message: "Fragmented message interrupted by another frame".to_string(),
+                    });
+                }
+                return self.decode_envelope(&frame);
+            };
+
+            let total_len = fragment.total_len as usize;
+            if total_len > self.max_reassembled_size {
+                return Err(DatabaseError::MessageTooLarge {
+                    size: total_len,
+                    max_size: self.max_reassembled_size,
+                });
+            }
+
+            // Grow with the fragments actually received rather than trusting
+            // the announced total up front
+            let state = reassembly.get_or_insert_with(|| Reassembly {
+                total_len,
+                next_index: 0,
+                data: Vec::with_capacity(total_len.min(self.max_message_size)),
+            });
+
+            if fragment.index != state.next_index
+                || total_len != state.total_len
+                || data.is_empty()
+                || state.data.len() + data.len() > state.total_len
+            {
+                return Err(DatabaseError::SerializationError {
+                    message: format!(
+                        "Invalid fragment {} of {}-byte message (expected fragment {})",
+                        fragment.index, total_len, state.next_index
+                    ),
+                });
+            }
+
+            state.data.extend_from_slice(data);
+            state.next_index += 1;
+
+            if state.data.len() == state.total_len {
+                return self.decode_versioned(&state.data, self.max_reassembled_size);
+            }
+        }
+    }
+
+    /// Reads a single length-prefixed frame
+    async fn read_frame<R: AsyncRead + Unpin>(
+        &self,
+        reader: &mut R,
+    ) -> Result<Vec<u8>, DatabaseError> {
         // Read 4-byte length prefix
         let mut length_bytes = [0u8; 4];
         reader
@@ -313,8 +1112,8 @@ impl MessageCodec {
 
         let length = u32::from_be_bytes(length_bytes) as usize;
 
-        // Validate message size before allocating
-        if length > self.max_message_size {
+        // Validate message size before allocating, allowing for the frame header
+        if length > self.max_message_size + FRAGMENT_HEADER_LEN {
             return Err(DatabaseError::MessageTooLarge {
                 size: length,
                 max_size: self.max_message_size,
@@ -330,26 +1129,50 @@ impl MessageCodec {
                 details: format!("Failed to read message data: {}", e),
             })?;
 
-        // Decode message
-        self.decode(&data)
+        Ok(data)
     }
 
     /// Writes a message to an async writer
     ///
-    /// Writes the 4-byte length prefix followed by the message data.
+    /// Writes the 4-byte length prefix followed by the message data, once per
+    /// frame when the message is fragmented.
     pub async fn write_message<W: AsyncWrite + Unpin>(
         &self,
         writer: &mut W,
         message: &Message,
     ) -> Result<(), DatabaseError> {
-        let encoded = self.encode_with_length(message)?;
+        let frames = self.encode_frames(message)?;
+        self.write_frames(writer, frames).await
+    }
 
-        writer
-            .write_all(&encoded)
-            .await
-            .map_err(|e| DatabaseError::NetworkError {
-                details: format!("Failed to write message: {}", e),
-            })?;
+    /// Writes a message and its extensions to an async writer
+    ///
+    /// See `write_message`.
+    pub async fn write_envelope<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        envelope: &Envelope,
+    ) -> Result<(), DatabaseError> {
+        let frames = self.encode_envelope_frames(envelope)?;
+        self.write_frames(writer, frames).await
+    }
+
+    /// Writes length-prefixed frames and flushes the writer
+    async fn write_frames<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        frames: Vec<Vec<u8>>,
+    ) -> Result<(), DatabaseError> {
+        let write_error = |e: std::io::Error| DatabaseError::NetworkError {
+            details: format!("Failed to write message: {}", e),
+        };
+        for frame in frames {
+            writer
+                .write_all(&(frame.len() as u32).to_be_bytes())
+                .await
+                .map_err(write_error)?;
+            writer.write_all(&frame).await.map_err(write_error)?;
+        }
 
         writer
             .flush()
@@ -439,7 +1262,7 @@ pub enum AdminRequest {
     /// Create a new user
     CreateUser {
         username: String,
-        password: String,
+        password: SecretString,
         roles: Vec<Role>,
     },
     /// List all users
@@ -460,6 +1283,26 @@ pub enum AdminRequest {
     },
 }
 
+impl AdminRequest {
+    /// Returns the permission needed to make the request
+    pub fn required_permission(&self) -> Permission {
+        match self {
+            AdminRequest::ListNodes
+            | AdminRequest::GetNodeHealth { .. }
+            | AdminRequest::AddNode { .. }
+            | AdminRequest::RemoveNode { .. }
+            | AdminRequest::RebalancePartitions
+            | AdminRequest::GetClusterMetrics => Permission::ManageCluster,
+            AdminRequest::CreateUser { .. }
+            | AdminRequest::ListUsers
+            | AdminRequest::UpdateUser { .. }
+            | AdminRequest::DeleteUser { .. }
+            | AdminRequest::GrantPermission { .. }
+            | AdminRequest::RevokePermission { .. } => Permission::ManageUsers,
+        }
+    }
+}
+
 /// Admin response types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AdminResponse {
@@ -489,20 +1332,243 @@ pub enum AdminResponse {
     PermissionRevoked,
 }
 
+impl AdminResponse {
+    /// Returns the error for an admin response of an unexpected type
+    pub fn unexpected(&self) -> DatabaseError {
+        DatabaseError::unexpected_response("AdminClient", self.kind())
+    }
+
+    /// Returns the name of the response variant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AdminResponse::NodeList(_) => "NodeList",
+            AdminResponse::NodeHealth(_) => "NodeHealth",
+            AdminResponse::NodeAdded(_) => "NodeAdded",
+            AdminResponse::NodeRemoved => "NodeRemoved",
+            AdminResponse::PartitionsRebalanced => "PartitionsRebalanced",
+            AdminResponse::ClusterMetrics(_) => "ClusterMetrics",
+            AdminResponse::UserCreated(_) => "UserCreated",
+            AdminResponse::UserList(_) => "UserList",
+            AdminResponse::UserUpdated => "UserUpdated",
+            AdminResponse::UserDeleted => "UserDeleted",
+            AdminResponse::PermissionGranted => "PermissionGranted",
+            AdminResponse::PermissionRevoked => "PermissionRevoked",
+        }
+    }
+}
+
+// ============================================================================
+// Data Protocol Types
+// ============================================================================
+
+/// Operation within a batch request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BatchOperation {
+    /// Execute a statement
+    Execute { sql: String, params: Vec<Value> },
+}
+
 /// Request envelope for all request types
+///
+/// Sent as the payload of `MessageType::Data` frames. The bincode variant
+/// index is the request tag, so new variants must only ever be appended.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Request {
     /// Admin request
     Admin(AdminRequest),
+    /// Execute a statement (INSERT, UPDATE, DELETE, DDL)
+    Execute {
+        sql: String,
+        params: Vec<Value>,
+        prepared_statement_id: Option<StatementId>,
+        transaction_id: Option<TransactionId>,
+        auth_token: Option<SecretBytes>,
+    },
+    /// Run a query; with `streaming` set the server answers `StreamOpened`
+    /// and rows are pulled with `Fetch`
+    Query {
+        sql: String,
+        params: Vec<Value>,
+        prepared_statement_id: Option<StatementId>,
+        transaction_id: Option<TransactionId>,
+        auth_token: Option<SecretBytes>,
+        streaming: bool,
+    },
+    /// Prepare a statement for reuse
+    Prepare {
+        sql: String,
+        auth_token: Option<SecretBytes>,
+    },
+    /// Execute several operations atomically
+    Batch {
+        operations: Vec<BatchOperation>,
+        auth_token: Option<SecretBytes>,
+    },
+    /// Fetch the next rows of an open result stream
+    Fetch { stream_id: StreamId, max_rows: u32 },
+    /// Cancel an open result stream
+    Cancel { stream_id: StreamId },
+    /// Close a prepared statement
+    Close { statement_id: StatementId },
+    /// Admin request, run with the permissions of the token's user
+    AuthenticatedAdmin {
+        request: AdminRequest,
+        auth_token: Option<SecretBytes>,
+    },
 }
 
 /// Response envelope for all response types
+///
+/// Like `Request`, variants are tagged by index and must only be appended.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Response {
     /// Admin response
     Admin(AdminResponse),
-    /// Error response
+    /// Error response from servers that do not report structured errors
     Error(String),
+    /// Statement executed
+    Executed {
+        rows_affected: u64,
+        last_insert_id: Option<i64>,
+    },
+    /// Complete query result
+    Rows {
+        columns: Vec<ColumnMetadata>,
+        rows: Vec<Vec<Value>>,
+    },
+    /// Statement prepared
+    Prepared {
+        statement_id: StatementId,
+        param_count: usize,
+    },
+    /// Batch executed, one result per operation
+    BatchExecuted(Vec<ExecuteResult>),
+    /// Result stream opened
+    StreamOpened {
+        stream_id: StreamId,
+        columns: Vec<ColumnMetadata>,
+    },
+    /// Next rows of a result stream; `done` is set on the last batch
+    StreamRows { rows: Vec<Vec<Value>>, done: bool },
+    /// Result stream cancelled
+    Cancelled,
+    /// Prepared statement closed
+    Closed,
+    /// Structured error
+    ServerError(ServerError),
+}
+
+impl Request {
+    /// Returns the SQL text of the request, if any
+    pub fn sql(&self) -> Option<&str> {
+        match self {
+            Request::Execute { sql, .. }
+            | Request::Query { sql, .. }
+            | Request::Prepare { sql, .. } => Some(sql),
+            _ => None,
+        }
+    }
+
+    /// Returns the auth token the request carries, if any
+    pub fn auth_token(&self) -> Option<&[u8]> {
+        match self {
+            Request::Execute { auth_token, .. }
+            | Request::Query { auth_token, .. }
+            | Request::Prepare { auth_token, .. }
+            | Request::Batch { auth_token, .. }
+            | Request::AuthenticatedAdmin { auth_token, .. } => {
+                auth_token.as_ref().map(SecretBytes::expose)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the admin request, with or without a token
+    pub fn admin_request(&self) -> Option<&AdminRequest> {
+        match self {
+            Request::Admin(request) | Request::AuthenticatedAdmin { request, .. } => Some(request),
+            _ => None,
+        }
+    }
+
+    /// Returns the transaction the request runs in, if any
+    pub fn transaction_id(&self) -> Option<TransactionId> {
+        match self {
+            Request::Execute { transaction_id, .. } | Request::Query { transaction_id, .. } => {
+                *transaction_id
+            }
+            _ => None,
+        }
+    }
+
+    /// Serializes the request into a message payload
+    pub fn to_payload(&self) -> Result<Vec<u8>, DatabaseError> {
+        bincode::serialize(self).map_err(|e| DatabaseError::SerializationError {
+            message: format!("Failed to serialize request: {}", e),
+        })
+    }
+
+    /// Deserializes a request from a message payload
+    pub fn from_payload(payload: &[u8]) -> Result<Self, DatabaseError> {
+        bincode::deserialize(payload).map_err(|e| DatabaseError::SerializationError {
+            message: format!("Failed to deserialize request: {}", e),
+        })
+    }
+}
+
+impl Response {
+    /// Serializes the response into a message payload
+    pub fn to_payload(&self) -> Result<Vec<u8>, DatabaseError> {
+        bincode::serialize(self).map_err(|e| DatabaseError::SerializationError {
+            message: format!("Failed to serialize response: {}", e),
+        })
+    }
+
+    /// Deserializes a response from a message payload
+    pub fn from_payload(payload: &[u8]) -> Result<Self, DatabaseError> {
+        bincode::deserialize(payload).map_err(|e| DatabaseError::SerializationError {
+            message: format!("Failed to deserialize response: {}", e),
+        })
+    }
+
+    /// Converts error responses into the matching `DatabaseError`
+    ///
+    /// `sql` and `transaction_id` describe the request the response answers.
+    pub fn into_result(
+        self,
+        sql: Option<&str>,
+        transaction_id: Option<TransactionId>,
+    ) -> Result<Self, DatabaseError> {
+        match self {
+            Response::ServerError(error) => Err(error.into_database_error(sql, transaction_id)),
+            Response::Error(message) => {
+                Err(ServerError::unclassified(message).into_database_error(sql, transaction_id))
+            }
+            response => Ok(response),
+        }
+    }
+
+    /// Returns the error for a response of an unexpected type
+    pub fn unexpected(&self, component: &str) -> DatabaseError {
+        DatabaseError::unexpected_response(component, self.kind())
+    }
+
+    /// Returns the name of the response variant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Response::Admin(_) => "Admin",
+            Response::Error(_) => "Error",
+            Response::Executed { .. } => "Executed",
+            Response::Rows { .. } => "Rows",
+            Response::Prepared { .. } => "Prepared",
+            Response::BatchExecuted(_) => "BatchExecuted",
+            Response::StreamOpened { .. } => "StreamOpened",
+            Response::StreamRows { .. } => "StreamRows",
+            Response::Cancelled => "Cancelled",
+            Response::Closed => "Closed",
+            Response::ServerError(_) => "ServerError",
+        }
+    }
 }
 
 #[cfg(test)]
@@ -583,6 +1649,177 @@ mod tests {
         assert_ne!(msg1.checksum, msg2.checksum);
     }
 
+    #[test]
+    fn test_message_check_server_error() {
+        let msg = create_test_message();
+        assert!(msg.check_server_error(None, None).is_ok());
+
+        let payload = bincode::serialize(
+            &ServerError::new(crate::error::sqlstate::SYNTAX_ERROR, "Unexpected token")
+                .with_position(7),
+        )
+        .unwrap();
+        let msg = Message::new(1, 2, 100, 1704067200000, MessageType::Error, payload);
+
+        let result = msg.check_server_error(Some("SELECT FROM"), None);
+        assert!(matches!(
+            result,
+            Err(DatabaseError::SyntaxError { position: 7, .. })
+        ));
+    }
+
+    // Data Request/Response Tests
+    fn execute_request() -> Request {
+        Request::Execute {
+            sql: "UPDATE users SET name = ? WHERE id = ?".to_string(),
+            params: vec![Value::from("Alice"), Value::Int(1)],
+            prepared_statement_id: None,
+            transaction_id: Some(7),
+            auth_token: Some(SecretBytes::new(vec![0xaa; 4])),
+        }
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let requests = vec![
+            Request::Admin(AdminRequest::ListNodes),
+            execute_request(),
+            Request::Query {
+                sql: "SELECT * FROM users".to_string(),
+                params: vec![],
+                prepared_statement_id: Some(3),
+                transaction_id: None,
+                auth_token: None,
+                streaming: true,
+            },
+            Request::Prepare {
+                sql: "SELECT * FROM users WHERE id = ?".to_string(),
+                auth_token: None,
+            },
+            Request::Batch {
+                operations: vec![BatchOperation::Execute {
+                    sql: "DELETE FROM users".to_string(),
+                    params: vec![],
+                }],
+                auth_token: None,
+            },
+            Request::Fetch {
+                stream_id: 9,
+                max_rows: 100,
+            },
+            Request::Cancel { stream_id: 9 },
+            Request::Close { statement_id: 3 },
+            Request::AuthenticatedAdmin {
+                request: AdminRequest::ListNodes,
+                auth_token: Some(SecretBytes::new(vec![0xbb; 4])),
+            },
+        ];
+
+        for request in requests {
+            let payload = request.to_payload().unwrap();
+            let decoded = Request::from_payload(&payload).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", request));
+        }
+    }
+
+    #[test]
+    fn test_request_tags_are_stable() {
+        // The leading u32 is the variant tag the server dispatches on
+        let tags = [
+            (Request::Admin(AdminRequest::ListNodes), 0u32),
+            (execute_request(), 1),
+            (
+                Request::Fetch {
+                    stream_id: 1,
+                    max_rows: 1,
+                },
+                5,
+            ),
+            (Request::Cancel { stream_id: 1 }, 6),
+            (Request::Close { statement_id: 1 }, 7),
+            (
+                Request::AuthenticatedAdmin {
+                    request: AdminRequest::ListNodes,
+                    auth_token: None,
+                },
+                8,
+            ),
+        ];
+
+        for (request, tag) in tags {
+            let payload = request.to_payload().unwrap();
+            assert_eq!(&payload[..4], &tag.to_le_bytes(), "{:?}", request);
+        }
+
+        let payload = Response::Error("x".to_string()).to_payload().unwrap();
+        assert_eq!(&payload[..4], &1u32.to_le_bytes());
+        let payload = Response::Closed.to_payload().unwrap();
+        assert_eq!(&payload[..4], &9u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_request_context_accessors() {
+        let request = execute_request();
+        assert_eq!(
+            request.sql(),
+            Some("UPDATE users SET name = ? WHERE id = ?")
+        );
+        assert_eq!(request.transaction_id(), Some(7));
+
+        let request = Request::Cancel { stream_id: 1 };
+        assert_eq!(request.sql(), None);
+        assert_eq!(request.transaction_id(), None);
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let response = Response::StreamRows {
+            rows: vec![vec![Value::Int(1), Value::from("a")]],
+            done: true,
+        };
+        let payload = response.to_payload().unwrap();
+
+        match Response::from_payload(&payload).unwrap() {
+            Response::StreamRows { rows, done } => {
+                assert_eq!(rows, vec![vec![Value::Int(1), Value::from("a")]]);
+                assert!(done);
+            }
+            other => panic!("Unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_response_into_result_maps_errors() {
+        let response = Response::ServerError(
+            ServerError::new(crate::error::sqlstate::UNDEFINED_TABLE, "missing")
+                .with_detail("table", "users"),
+        );
+        assert!(matches!(
+            response.into_result(None, None),
+            Err(DatabaseError::TableNotFound { .. })
+        ));
+
+        let response = Response::Error("legacy failure".to_string());
+        assert!(matches!(
+            response.into_result(None, None),
+            Err(DatabaseError::ServerError { .. })
+        ));
+
+        assert!(matches!(
+            Response::Cancelled.into_result(None, None),
+            Ok(Response::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_response_unexpected() {
+        let err = Response::Closed.unexpected("DataClient");
+        assert!(
+            matches!(err, DatabaseError::InternalError { component, details }
+                if component == "DataClient" && details.contains("Closed"))
+        );
+    }
+
     // MessageCodec Tests
     #[test]
     fn test_codec_creation() {
@@ -751,6 +1988,56 @@ mod tests {
         assert_eq!(length, buffer.len() - 4);
     }
 
+    #[tokio::test]
+    async fn test_codec_write_stops_when_length_prefix_fails() {
+        /// Fails the first write and records the rest
+        struct FailFirstWrite {
+            failed: bool,
+            written: Vec<u8>,
+        }
+
+        impl tokio::io::AsyncWrite for FailFirstWrite {
+            fn poll_write(
+                mut self: std::pin::Pin<&mut Self>,
+                _cx: &mut std::task::Context<'_>,
+                buf: &[u8],
+            ) -> std::task::Poll<std::io::Result<usize>> {
+                if !self.failed {
+                    self.failed = true;
+                    return std::task::Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()));
+                }
+                self.written.extend_from_slice(buf);
+                std::task::Poll::Ready(Ok(buf.len()))
+            }
+
+            fn poll_flush(
+                self: std::pin::Pin<&mut Self>,
+                _cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<std::io::Result<()>> {
+                std::task::Poll::Ready(Ok(()))
+            }
+
+            fn poll_shutdown(
+                self: std::pin::Pin<&mut Self>,
+                _cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<std::io::Result<()>> {
+                std::task::Poll::Ready(Ok(()))
+            }
+        }
+
+        let codec = MessageCodec::new();
+        let mut writer = FailFirstWrite {
+            failed: false,
+            written: Vec::new(),
+        };
+
+        let result = codec
+            .write_message(&mut writer, &create_test_message())
+            .await;
+        assert!(matches!(result, Err(DatabaseError::NetworkError { .. })));
+        assert!(writer.written.is_empty());
+    }
+
     // ProtocolNegotiation Tests
     #[test]
     fn test_protocol_negotiation_creation() {
@@ -872,6 +2159,566 @@ mod tests {
         assert_eq!(msg.payload, decoded.payload);
         assert_eq!(msg.checksum, decoded.checksum);
     }
+
+    #[test]
+    fn test_codec_zstd_round_trip() {
+        let config = CompressionConfig::new(vec![CompressionAlgorithm::Zstd]).with_zstd_level(9);
+        let codec = MessageCodec::with_compression(true, 50).with_compression_config(&config);
+
+        let payload = vec![7u8; 4096];
+        let msg = Message::new(1, 2, 100, 1704067200000, MessageType::Data, payload);
+
+        let encoded = codec.encode(&msg).unwrap();
+        assert_eq!(encoded[0], CompressionAlgorithm::Zstd.id());
+        assert!(encoded.len() < msg.payload.len());
+
+        let decoded = codec.decode(&encoded).unwrap();
+        assert_eq!(msg.payload, decoded.payload);
+        assert_eq!(msg.checksum, decoded.checksum);
+    }
+
+    #[test]
+    fn test_codec_snappy_round_trip() {
+        let config = CompressionConfig::new(vec![CompressionAlgorithm::Snappy]);
+        let codec = MessageCodec::with_compression(true, 50).with_compression_config(&config);
+
+        let payload = vec![9u8; 2048];
+        let msg = Message::new(1, 2, 100, 1704067200000, MessageType::Data, payload);
+
+        let encoded = codec.encode(&msg).unwrap();
+        assert_eq!(encoded[0], CompressionAlgorithm::Snappy.id());
+
+        let decoded = codec.decode(&encoded).unwrap();
+        assert_eq!(msg.payload, decoded.payload);
+    }
+
+    #[test]
+    fn test_decode_rejects_decompression_bombs() {
+        let codec = MessageCodec::with_max_size(1024 * 1024);
+        let zeros = vec![0u8; 2 * 1024 * 1024];
+
+        for algorithm in [
+            CompressionAlgorithm::Lz4,
+            CompressionAlgorithm::Zstd,
+            CompressionAlgorithm::Snappy,
+        ] {
+            let mut frame = vec![algorithm.id()];
+            codec.compress_into(&mut frame, &zeros, algorithm).unwrap();
+            assert!(frame.len() <= 1024 * 1024);
+
+            let result = codec.decode(&frame);
+            assert!(
+                matches!(result, Err(DatabaseError::MessageTooLarge { .. })),
+                "{:?} accepted an oversized body",
+                algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_forged_lz4_size_prefix() {
+        let codec = MessageCodec::new();
+        let mut frame = vec![CompressionAlgorithm::Lz4.id()];
+        frame.extend_from_slice(&u32::MAX.to_le_bytes());
+        frame.extend_from_slice(&[0u8; 16]);
+
+        match codec.decode(&frame) {
+            Err(DatabaseError::MessageTooLarge { size, max_size }) => {
+                assert_eq!(size, u32::MAX as usize);
+                assert_eq!(max_size, codec.max_message_size);
+            }
+            other => panic!("expected MessageTooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_codec_select_compression_by_size() {
+        let config = CompressionConfig::new(vec![
+            CompressionAlgorithm::Zstd,
+            CompressionAlgorithm::Snappy,
+            CompressionAlgorithm::Lz4,
+        ])
+        .with_high_ratio_threshold(10_000);
+        let codec = MessageCodec::with_compression(true, 100).with_compression_config(&config);
+
+        assert_eq!(codec.select_compression(50), CompressionAlgorithm::None);
+        assert_eq!(codec.select_compression(100), CompressionAlgorithm::None);
+        assert_eq!(
+            codec.select_compression(5_000),
+            CompressionAlgorithm::Snappy
+        );
+        assert_eq!(codec.select_compression(20_000), CompressionAlgorithm::Zstd);
+    }
+
+    #[test]
+    fn test_codec_select_compression_only_zstd() {
+        let config = CompressionConfig::new(vec![CompressionAlgorithm::Zstd]);
+        let codec = MessageCodec::with_compression(true, 100).with_compression_config(&config);
+
+        // With no fast algorithm available, mid-size messages still use zstd
+        assert_eq!(codec.select_compression(500), CompressionAlgorithm::Zstd);
+    }
+
+    #[test]
+    fn test_codec_select_compression_disabled() {
+        let codec = MessageCodec::with_compression(false, 100);
+        assert_eq!(
+            codec.select_compression(1_000_000),
+            CompressionAlgorithm::None
+        );
+    }
+
+    #[test]
+    fn test_codec_set_empty_algorithms_disables_compression() {
+        let mut codec = MessageCodec::with_compression(true, 100);
+        codec.set_compression_algorithms(vec![]);
+
+        assert!(!codec.compression_enabled);
+        assert_eq!(codec.select_compression(10_000), CompressionAlgorithm::None);
+    }
+
+    #[test]
+    fn test_codec_encode_with_explicit_compression() {
+        let codec = MessageCodec::new();
+        let msg = create_test_message();
+
+        for algorithm in [
+            CompressionAlgorithm::None,
+            CompressionAlgorithm::Lz4,
+            CompressionAlgorithm::Zstd,
+            CompressionAlgorithm::Snappy,
+        ] {
+            let encoded = codec.encode_with_compression(&msg, algorithm).unwrap();
+            assert_eq!(encoded[0], algorithm.id());
+
+            let decoded = codec.decode(&encoded).unwrap();
+            assert_eq!(msg.payload, decoded.payload);
+        }
+    }
+
+    #[test]
+    fn test_codec_decode_rejects_unknown_algorithm() {
+        let codec = MessageCodec::new();
+        let mut encoded = codec.encode(&create_test_message()).unwrap();
+        encoded[0] = 0xEE;
+
+        let result = codec.decode(&encoded);
+        assert!(matches!(
+            result,
+            Err(DatabaseError::SerializationError { .. })
+        ));
+    }
+
+    // Wire Envelope Tests
+    //
+    // The golden fixtures below pin the on-wire layout of `create_test_message()`.
+    // If one of these tests fails, the wire format changed in a way that older
+    // peers will not understand.
+
+    /// bincode encoding of `create_test_message()`
+    const GOLDEN_MESSAGE: [u8; 53] = [
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sender
+        0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // recipient
+        0x64, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sequence_number
+        0x00, 0xf4, 0x51, 0xc2, 0x8c, 0x01, 0x00, 0x00, // timestamp
+        0x02, 0x00, 0x00, 0x00, // message_type (Data)
+        0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // payload length
+        0x01, 0x02, 0x03, 0x04, 0x05, // payload
+        0xbc, 0x00, 0x5f, 0xd0, // checksum
+    ];
+
+    /// Legacy frame header: uncompressed
+    const GOLDEN_LEGACY_HEADER: [u8; 1] = [0x00];
+
+    /// Version 1 frame header: no flags, uncompressed
+    const GOLDEN_V1_HEADER: [u8; 7] = [0x51, 0x44, 0x01, 0x07, 0x00, 0x00, 0x00];
+
+    /// Version 1 frame header: extensions flag, uncompressed
+    const GOLDEN_V1_EXT_HEADER: [u8; 7] = [0x51, 0x44, 0x01, 0x07, 0x00, 0x01, 0x00];
+
+    /// Envelope message length prefix for `GOLDEN_MESSAGE`
+    const GOLDEN_MESSAGE_LEN: [u8; 4] = [0x00, 0x00, 0x00, 0x35];
+
+    /// bincode encoding of the extension map `{"trace": [0xab, 0xcd]}`
+    const GOLDEN_EXTENSIONS: [u8; 31] = [
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // entry count
+        0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // key length
+        0x74, 0x72, 0x61, 0x63, 0x65, // "trace"
+        0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // value length
+        0xab, 0xcd, // value
+    ];
+
+    fn golden_frame(parts: &[&[u8]]) -> Vec<u8> {
+        parts.concat()
+    }
+
+    fn versioned_codec() -> MessageCodec {
+        MessageCodec::new().with_wire_format(WireFormat::Versioned)
+    }
+
+    fn assert_is_test_message(decoded: &Message) {
+        let expected = create_test_message();
+        assert_eq!(decoded.sender, expected.sender);
+        assert_eq!(decoded.recipient, expected.recipient);
+        assert_eq!(decoded.sequence_number, expected.sequence_number);
+        assert_eq!(decoded.timestamp, expected.timestamp);
+        assert_eq!(decoded.message_type, expected.message_type);
+        assert_eq!(decoded.payload, expected.payload);
+        assert_eq!(decoded.checksum, expected.checksum);
+    }
+
+    #[test]
+    fn test_codec_defaults_to_legacy_wire_format() {
+        assert_eq!(MessageCodec::new().wire_format(), WireFormat::Legacy);
+    }
+
+    #[test]
+    fn test_golden_legacy_frame() {
+        let expected = golden_frame(&[&GOLDEN_LEGACY_HEADER, &GOLDEN_MESSAGE]);
+        let codec = MessageCodec::new();
+
+        assert_eq!(codec.encode(&create_test_message()).unwrap(), expected);
+        assert_is_test_message(&codec.decode(&expected).unwrap());
+    }
+
+    #[test]
+    fn test_golden_versioned_frame() {
+        let expected = golden_frame(&[&GOLDEN_V1_HEADER, &GOLDEN_MESSAGE_LEN, &GOLDEN_MESSAGE]);
+        let codec = versioned_codec();
+
+        assert_eq!(codec.encode(&create_test_message()).unwrap(), expected);
+
+        let envelope = codec.decode_envelope(&expected).unwrap();
+        assert_is_test_message(&envelope.message);
+        assert!(envelope.extensions.is_empty());
+    }
+
+    #[test]
+    fn test_golden_versioned_frame_with_extensions() {
+        let expected = golden_frame(&[
+            &GOLDEN_V1_EXT_HEADER,
+            &GOLDEN_MESSAGE_LEN,
+            &GOLDEN_MESSAGE,
+            &GOLDEN_EXTENSIONS,
+        ]);
+        let codec = versioned_codec();
+        let envelope =
+            Envelope::new(create_test_message()).with_extension("trace", vec![0xab, 0xcd]);
+
+        assert_eq!(codec.encode_envelope(&envelope).unwrap(), expected);
+
+        let decoded = codec.decode_envelope(&expected).unwrap();
+        assert_is_test_message(&decoded.message);
+        assert_eq!(decoded.extension("trace"), Some(&[0xab, 0xcd][..]));
+    }
+
+    #[test]
+    fn test_decode_accepts_both_wire_formats() {
+        let legacy = golden_frame(&[&GOLDEN_LEGACY_HEADER, &GOLDEN_MESSAGE]);
+        let versioned = golden_frame(&[&GOLDEN_V1_HEADER, &GOLDEN_MESSAGE_LEN, &GOLDEN_MESSAGE]);
+
+        for codec in [MessageCodec::new(), versioned_codec()] {
+            assert_is_test_message(&codec.decode(&legacy).unwrap());
+            assert_is_test_message(&codec.decode(&versioned).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_decode_tolerates_frame_from_newer_peer() {
+        // Version 2 with two extra header bytes, an unknown optional flag, a
+        // message with an appended field, and a trailing body section
+        let header = [0x51, 0x44, 0x02, 0x09, 0x00, 0x05, 0x00, 0xee, 0xee];
+        let message_len = [0x00, 0x00, 0x00, 0x37];
+        let appended_field = [0x2a, 0x2a];
+        let trailing_section = [0xff, 0xff, 0xff];
+        let frame = golden_frame(&[
+            &header,
+            &message_len,
+            &GOLDEN_MESSAGE,
+            &appended_field,
+            &GOLDEN_EXTENSIONS,
+            &trailing_section,
+        ]);
+
+        let envelope = versioned_codec().decode_envelope(&frame).unwrap();
+        assert_is_test_message(&envelope.message);
+        assert_eq!(envelope.extension("trace"), Some(&[0xab, 0xcd][..]));
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_required_flag() {
+        let mut frame = golden_frame(&[&GOLDEN_V1_HEADER, &GOLDEN_MESSAGE_LEN, &GOLDEN_MESSAGE]);
+        frame[4] = 0x02; // required flag 0x0200, which is not defined
+        assert_eq!(
+            u16::from_be_bytes([frame[4], frame[5]]) & KNOWN_FLAGS,
+            0,
+            "the flag under test must be undefined"
+        );
+
+        let result = MessageCodec::new().decode(&frame);
+        assert!(
+            matches!(
+                &result,
+                Err(DatabaseError::SerializationError { message }) if message.contains("0x0200")
+            ),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_versioned_header() {
+        let codec = MessageCodec::new();
+        let valid = golden_frame(&[&GOLDEN_V1_HEADER, &GOLDEN_MESSAGE_LEN, &GOLDEN_MESSAGE]);
+
+        // Truncated header
+        assert!(codec.decode(&valid[..4]).is_err());
+
+        // Bad second magic byte
+        let mut frame = valid.clone();
+        frame[1] = 0x00;
+        assert!(codec.decode(&frame).is_err());
+
+        // Version 0
+        let mut frame = valid.clone();
+        frame[2] = 0x00;
+        assert!(codec.decode(&frame).is_err());
+
+        // Header length shorter than version 1 header
+        let mut frame = valid.clone();
+        frame[3] = 0x03;
+        assert!(codec.decode(&frame).is_err());
+
+        // Message length beyond the body
+        let mut frame = valid;
+        frame[10] = 0x50;
+        assert!(codec.decode(&frame).is_err());
+    }
+
+    #[test]
+    fn test_legacy_encode_rejects_extensions() {
+        let envelope = Envelope::new(create_test_message()).with_extension("trace", vec![1]);
+
+        let result = MessageCodec::new().encode_envelope(&envelope);
+        assert!(matches!(
+            result,
+            Err(DatabaseError::SerializationError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_versioned_compression_round_trip() {
+        let mut codec =
+            MessageCodec::with_compression(true, 16).with_wire_format(WireFormat::Versioned);
+        let msg = Message::new(1, 2, 100, 1704067200000, MessageType::Data, vec![7; 4096]);
+
+        for algorithm in [
+            CompressionAlgorithm::Lz4,
+            CompressionAlgorithm::Zstd,
+            CompressionAlgorithm::Snappy,
+        ] {
+            codec.set_compression_algorithms(vec![algorithm]);
+            let envelope = Envelope::new(msg.clone()).with_extension("k", vec![1, 2, 3]);
+            let encoded = codec.encode_envelope(&envelope).unwrap();
+
+            let (header, _) = FrameHeader::parse(&encoded).unwrap();
+            assert_eq!(header.compression, algorithm);
+            assert!(header.has_flag(FLAG_EXTENSIONS));
+
+            let decoded = codec.decode_envelope(&encoded).unwrap();
+            assert_eq!(decoded.message.payload, msg.payload);
+            assert_eq!(decoded.extension("k"), Some(&[1, 2, 3][..]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_codec_read_write_versioned_message() {
+        let codec = versioned_codec();
+        let mut buffer = Vec::new();
+        codec
+            .write_message(&mut buffer, &create_test_message())
+            .await
+            .unwrap();
+        assert_eq!(&buffer[4..6], &FRAME_MAGIC);
+
+        let mut cursor = &buffer[..];
+        let decoded = MessageCodec::new().read_message(&mut cursor).await.unwrap();
+        assert_is_test_message(&decoded);
+    }
+
+    fn fragmenting_codec(max_message_size: usize) -> MessageCodec {
+        let mut codec = MessageCodec::with_settings(max_message_size, false, 0)
+            .with_wire_format(WireFormat::Versioned);
+        codec.set_fragmentation_enabled(true);
+        codec
+    }
+
+    fn large_message(size: usize) -> Message {
+        let payload = (0..size).map(|i| (i % 251) as u8).collect();
+        Message::new(1, 2, 100, 1704067200000, MessageType::Data, payload)
+    }
+
+    /// Prefixes each frame with its length, as `write_message` does
+    fn length_prefixed(frames: &[Vec<u8>]) -> Vec<u8> {
+        frames
+            .iter()
+            .flat_map(|frame| {
+                let mut out = (frame.len() as u32).to_be_bytes().to_vec();
+                out.extend_from_slice(frame);
+                out
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fragmentation_requires_versioned_format() {
+        let mut codec = MessageCodec::new();
+        codec.set_fragmentation_enabled(true);
+        assert!(!codec.fragmentation_enabled());
+        assert!(codec
+            .with_wire_format(WireFormat::Versioned)
+            .fragmentation_enabled());
+    }
+
+    #[test]
+    fn test_encode_frames_splits_oversized_message() {
+        let codec = fragmenting_codec(256);
+        let frames = codec.encode_frames(&large_message(1000)).unwrap();
+        assert!(frames.len() > 1);
+
+        for (index, frame) in frames.iter().enumerate() {
+            assert!(frame.len() <= FRAGMENT_HEADER_LEN + 256);
+            let (fragment, _) = FragmentHeader::parse(frame).unwrap().unwrap();
+            assert_eq!(fragment.index, index as u32);
+        }
+
+        // A standalone fragment cannot be decoded as a message
+        assert!(matches!(
+            codec.decode(&frames[0]),
+            Err(DatabaseError::SerializationError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_encode_frames_keeps_small_message_whole() {
+        let codec = fragmenting_codec(256);
+        let frames = codec.encode_frames(&create_test_message()).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_is_test_message(&codec.decode(&frames[0]).unwrap());
+    }
+
+    #[test]
+    fn test_encode_frames_without_fragmentation_rejects_oversized() {
+        let codec =
+            MessageCodec::with_settings(256, false, 0).with_wire_format(WireFormat::Versioned);
+        assert!(matches!(
+            codec.encode_frames(&large_message(1000)),
+            Err(DatabaseError::MessageTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_encode_frames_rejects_message_over_reassembly_cap() {
+        let codec = fragmenting_codec(256).with_max_reassembled_size(512);
+        assert!(matches!(
+            codec.encode_frames(&large_message(1000)),
+            Err(DatabaseError::MessageTooLarge { max_size: 512, .. })
+        ));
+    }
+
+    #[test]
+    fn test_fragment_parse_detects_checksum_mismatch() {
+        let codec = fragmenting_codec(256);
+        let mut frames = codec.encode_frames(&large_message(1000)).unwrap();
+        let last = frames[1].len() - 1;
+        frames[1][last] ^= 0xFF;
+
+        assert!(matches!(
+            FragmentHeader::parse(&frames[1]),
+            Err(DatabaseError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_write_fragmented_message() {
+        let codec = fragmenting_codec(256);
+        let message = large_message(1000);
+        let mut buffer = Vec::new();
+        codec.write_message(&mut buffer, &message).await.unwrap();
+
+        // The reader reassembles even when it does not fragment itself
+        let reader = MessageCodec::with_settings(256, false, 0);
+        let mut cursor = &buffer[..];
+        let decoded = reader.read_message(&mut cursor).await.unwrap();
+        assert_eq!(decoded.payload, message.payload);
+        assert!(cursor.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_fragmented_compressed_message() {
+        let mut codec = MessageCodec::with_settings(256, true, 0)
+            .with_wire_format(WireFormat::Versioned)
+            .with_compression_config(&CompressionConfig {
+                algorithms: vec![CompressionAlgorithm::Snappy],
+                ..CompressionConfig::default()
+            });
+        codec.set_fragmentation_enabled(true);
+
+        // Pseudo-random payload that does not compress below the frame limit
+        let payload = (0u32..4000)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        let message = Message::new(1, 2, 100, 1704067200000, MessageType::Data, payload);
+        let frames = codec.encode_frames(&message).unwrap();
+        assert!(frames.len() > 1);
+
+        let mut cursor = &length_prefixed(&frames)[..];
+        let decoded = codec.read_message(&mut cursor).await.unwrap();
+        assert_eq!(decoded.payload, message.payload);
+    }
+
+    #[tokio::test]
+    async fn test_read_rejects_fragments_over_reassembly_cap() {
+        let frames = fragmenting_codec(256)
+            .encode_frames(&large_message(1000))
+            .unwrap();
+        let reader = MessageCodec::with_settings(256, false, 0).with_max_reassembled_size(512);
+
+        let mut cursor = &length_prefixed(&frames)[..];
+        assert!(matches!(
+            reader.read_message(&mut cursor).await,
+            Err(DatabaseError::MessageTooLarge { max_size: 512, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_rejects_out_of_order_fragments() {
+        let mut frames = fragmenting_codec(256)
+            .encode_frames(&large_message(1000))
+            .unwrap();
+        frames.swap(1, 2);
+
+        let mut cursor = &length_prefixed(&frames)[..];
+        assert!(matches!(
+            MessageCodec::with_settings(256, false, 0)
+                .read_message(&mut cursor)
+                .await,
+            Err(DatabaseError::SerializationError { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_rejects_interrupted_fragments() {
+        let codec = fragmenting_codec(256);
+        let mut frames = codec.encode_frames(&large_message(1000)).unwrap();
+        frames[1] = codec.encode(&create_test_message()).unwrap();
+
+        let mut cursor = &length_prefixed(&frames)[..];
+        assert!(matches!(
+            codec.read_message(&mut cursor).await,
+            Err(DatabaseError::SerializationError { .. })
+        ));
+    }
 }
 
 // Property-Based Tests
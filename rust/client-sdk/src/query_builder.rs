@@ -4,8 +4,10 @@
 //! SQL injection prevention through parameterization.
 
 use crate::error::DatabaseError;
+use crate::row_serde;
 use crate::types::Value;
 use crate::Result;
+use serde::Serialize;
 
 /// Type of SQL query
 #[derive(Debug, Clone, PartialEq)]
@@ -261,6 +263,36 @@ impl QueryBuilder {
         self
     }
 
+    /// Adds a row of values for INSERT queries from a `Serialize` struct
+    ///
+    /// The first row sets the columns from its field names; later rows must
+    /// have the same fields in the same order.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let query = QueryBuilder::insert_into("users")
+    ///     .values_from(&alice)?
+    ///     .values_from(&bob)?;
+    /// ```
+    pub fn values_from<T: Serialize + ?Sized>(mut self, row: &T) -> Result<Self> {
+        let (columns, values): (Vec<String>, Vec<Value>) =
+            row_serde::to_params(row)?.into_iter().unzip();
+        if self.columns.is_empty() && self.values.is_empty() {
+            self.columns = columns;
+        } else if self.columns != columns {
+            return Err(DatabaseError::InternalError {
+                component: "QueryBuilder".to_string(),
+                details: format!(
+                    "Row fields ({}) do not match the INSERT columns ({})",
+                    columns.join(", "),
+                    self.columns.join(", ")
+                ),
+            });
+        }
+        self.values.push(values);
+        Ok(self)
+    }
+
     /// Adds a SET clause for UPDATE queries
     ///
     /// # Example
@@ -355,6 +387,70 @@ impl QueryBuilder {
         Ok((sql, all_params))
     }
 
+    /// Builds one page of a keyset-paginated SELECT
+    ///
+    /// Rows are ordered by `keys`, and with `after` set only rows that sort
+    /// after those key values are selected. The query must not set its own
+    /// ORDER BY, LIMIT or OFFSET.
+    pub(crate) fn build_keyset_page(
+        mut self,
+        keys: &[OrderBy],
+        after: Option<&[Value]>,
+        limit: u64,
+    ) -> Result<(String, Vec<Value>)> {
+        if self.query_type != QueryType::Select {
+            return Err(DatabaseError::InternalError {
+                component: "QueryBuilder".to_string(),
+                details: "Keyset pagination requires a SELECT query".to_string(),
+            });
+        }
+        if !self.order_by.is_empty() || self.limit.is_some() || self.offset.is_some() {
+            return Err(DatabaseError::InternalError {
+                component: "QueryBuilder".to_string(),
+                details: "Keyset pagination sets ORDER BY and LIMIT itself".to_string(),
+            });
+        }
+
+        if let Some(values) = after {
+            let (clause, params) = seek_predicate(keys, values);
+            // Group the existing conditions so an OR cannot escape the seek
+            if self.conditions.len() > 1 {
+                self.conditions = vec![Condition {
+                    clause: format!("({})", self.where_conditions()),
+                    operator: LogicalOperator::None,
+                }];
+            }
+            self.conditions.push(Condition {
+                clause,
+                operator: if self.conditions.is_empty() {
+                    LogicalOperator::None
+                } else {
+                    LogicalOperator::And
+                },
+            });
+            self.params.extend(params);
+        }
+        self.order_by = keys.to_vec();
+        self.limit = Some(limit);
+        self.build()
+    }
+
+    /// Joins the WHERE conditions with their logical operators
+    fn where_conditions(&self) -> String {
+        let mut sql = String::new();
+        for (i, condition) in self.conditions.iter().enumerate() {
+            if i > 0 {
+                match condition.operator {
+                    LogicalOperator::And => sql.push_str(" AND "),
+                    LogicalOperator::Or => sql.push_str(" OR "),
+                    LogicalOperator::None => {}
+                }
+            }
+            sql.push_str(&condition.clause);
+        }
+        sql
+    }
+
     /// Builds a SELECT query
     fn build_select(&self) -> Result<String> {
         let table = self.table.as_ref().ok_or(DatabaseError::InternalError {
@@ -373,16 +469,7 @@ impl QueryBuilder {
         // Add WHERE clause
         if !self.conditions.is_empty() {
             sql.push_str(" WHERE ");
-            for (i, condition) in self.conditions.iter().enumerate() {
-                if i > 0 {
-                    match condition.operator {
-                        LogicalOperator::And => sql.push_str(" AND "),
-                        LogicalOperator::Or => sql.push_str(" OR "),
-                        LogicalOperator::None => {}
-                    }
-                }
-                sql.push_str(&condition.clause);
-            }
+            sql.push_str(&self.where_conditions());
         }
 
         // Add ORDER BY clause
@@ -437,11 +524,10 @@ impl QueryBuilder {
             .map(|_| "?")
             .collect::<Vec<_>>()
             .join(", ");
+        // One placeholder group per row, matching the parameters from build()
+        let rows = vec![format!("({})", placeholders); self.values.len()].join(", ");
 
-        let sql = format!(
-            "INSERT INTO {} ({}) VALUES ({})",
-            table, columns, placeholders
-        );
+        let sql = format!("INSERT INTO {} ({}) VALUES {}", table, columns, rows);
 
         Ok(sql)
     }
@@ -471,16 +557,7 @@ impl QueryBuilder {
         // Add WHERE clause
         if !self.conditions.is_empty() {
             sql.push_str(" WHERE ");
-            for (i, condition) in self.conditions.iter().enumerate() {
-                if i > 0 {
-                    match condition.operator {
-                        LogicalOperator::And => sql.push_str(" AND "),
-                        LogicalOperator::Or => sql.push_str(" OR "),
-                        LogicalOperator::None => {}
-                    }
-                }
-                sql.push_str(&condition.clause);
-            }
+            sql.push_str(&self.where_conditions());
         }
 
         Ok(sql)
@@ -498,22 +575,61 @@ impl QueryBuilder {
         // Add WHERE clause
         if !self.conditions.is_empty() {
             sql.push_str(" WHERE ");
-            for (i, condition) in self.conditions.iter().enumerate() {
-                if i > 0 {
-                    match condition.operator {
-                        LogicalOperator::And => sql.push_str(" AND "),
-                        LogicalOperator::Or => sql.push_str(" OR "),
-                        LogicalOperator::None => {}
-                    }
-                }
-                sql.push_str(&condition.clause);
-            }
+            sql.push_str(&self.where_conditions());
         }
 
         Ok(sql)
     }
 }
 
+/// Returns the condition selecting rows that sort after `values` by `keys`
+///
+/// Keys sharing one direction compare as a row value, `(a, b) > (?, ?)`;
+/// mixed directions expand to `a > ? OR (a = ? AND b < ?)`.
+fn seek_predicate(keys: &[OrderBy], values: &[Value]) -> (String, Vec<Value>) {
+    let operator = |direction: &OrderDirection| match direction {
+        OrderDirection::Asc => ">",
+        OrderDirection::Desc => "<",
+    };
+
+    let first = &keys[0];
+    if keys.len() == 1 {
+        return (
+            format!("{} {} ?", first.column, operator(&first.direction)),
+            values[..1].to_vec(),
+        );
+    }
+    if keys.iter().all(|key| key.direction == first.direction) {
+        let columns: Vec<&str> = keys.iter().map(|key| key.column.as_str()).collect();
+        return (
+            format!(
+                "({}) {} ({})",
+                columns.join(", "),
+                operator(&first.direction),
+                vec!["?"; keys.len()].join(", ")
+            ),
+            values.to_vec(),
+        );
+    }
+
+    let mut branches = Vec::new();
+    let mut params = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        let mut terms: Vec<String> = keys[..i]
+            .iter()
+            .map(|key| format!("{} = ?", key.column))
+            .collect();
+        terms.push(format!("{} {} ?", key.column, operator(&key.direction)));
+        params.extend(values[..=i].iter().cloned());
+        branches.push(if i == 0 {
+            terms.remove(0)
+        } else {
+            format!("({})", terms.join(" AND "))
+        });
+    }
+    (format!("({})", branches.join(" OR ")), params)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -612,6 +728,104 @@ mod tests {
         assert_eq!(sql, "SELECT * FROM users LIMIT 10 OFFSET 20");
     }
 
+    #[test]
+    fn test_insert_values_from_struct() {
+        #[derive(serde::Serialize)]
+        struct User<'a> {
+            name: &'a str,
+            age: Option<u8>,
+        }
+
+        let (sql, params) = QueryBuilder::insert_into("users")
+            .values_from(&User {
+                name: "alice",
+                age: Some(30),
+            })
+            .unwrap()
+            .values_from(&User {
+                name: "bob",
+                age: None,
+            })
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(sql, "INSERT INTO users (name, age) VALUES (?, ?), (?, ?)");
+        assert_eq!(
+            params,
+            vec![
+                Value::String("alice".to_string()),
+                Value::Int(30),
+                Value::String("bob".to_string()),
+                Value::Null,
+            ]
+        );
+
+        let mismatched = QueryBuilder::insert_into("users")
+            .columns(&["age"])
+            .values_from(&User {
+                name: "carol",
+                age: None,
+            });
+        assert!(mismatched.is_err());
+    }
+
+    #[test]
+    fn test_keyset_page() {
+        let keys = [
+            OrderBy {
+                column: "created".to_string(),
+                direction: OrderDirection::Desc,
+            },
+            OrderBy {
+                column: "id".to_string(),
+                direction: OrderDirection::Asc,
+            },
+        ];
+        let query = QueryBuilder::select(&["*"])
+            .from("events")
+            .where_clause("kind = ?", Value::Int(1))
+            .or("kind = ?", Value::Int(2));
+
+        let (sql, params) = query.clone().build_keyset_page(&keys, None, 11).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM events WHERE kind = ? OR kind = ? ORDER BY created DESC, id ASC LIMIT 11"
+        );
+        assert_eq!(params.len(), 2);
+
+        let after = [Value::Int(50), Value::Int(7)];
+        let (sql, params) = query.build_keyset_page(&keys, Some(&after), 11).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM events WHERE (kind = ? OR kind = ?) AND (created < ? OR (created = ? AND id > ?)) ORDER BY created DESC, id ASC LIMIT 11"
+        );
+        assert_eq!(params, [1, 2, 50, 50, 7].map(Value::Int).to_vec());
+
+        let (sql, params) = QueryBuilder::select(&["*"])
+            .from("events")
+            .build_keyset_page(&keys[1..], Some(&after[1..]), 10)
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM events WHERE id > ? ORDER BY id ASC LIMIT 10"
+        );
+        assert_eq!(params, vec![Value::Int(7)]);
+
+        let ascending = [keys[1].clone(), keys[1].clone()];
+        let (sql, _) = QueryBuilder::select(&["*"])
+            .from("events")
+            .build_keyset_page(&ascending, Some(&after), 10)
+            .unwrap();
+        assert!(sql.contains("WHERE (id, id) > (?, ?)"));
+
+        assert!(QueryBuilder::select(&["*"])
+            .from("events")
+            .limit(5)
+            .build_keyset_page(&keys, None, 10)
+            .is_err());
+    }
+
     #[test]
     fn test_insert_basic() {
         let (sql, params) = QueryBuilder::insert_into("users")
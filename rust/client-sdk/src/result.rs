@@ -4,9 +4,12 @@
 //! Row and QueryResult structs, type conversion, and streaming support.
 
 use crate::error::DatabaseError;
+use crate::from_row::{FromRow, FromValue};
+use crate::row_serde;
 use crate::types::Value;
 use crate::Result;
 use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -94,6 +97,11 @@ impl Row {
         &self.values
     }
 
+    /// Returns the column metadata
+    pub fn columns(&self) -> &[ColumnMetadata] {
+        &self.columns
+    }
+
     /// Gets a value and converts it to i64
     pub fn get_i64(&self, index: usize) -> Result<i64> {
         let value = self.get(index)?;
@@ -129,6 +137,24 @@ impl Row {
         let value = self.get(index)?;
         value.as_timestamp_result()
     }
+
+    /// Gets a value by column name and converts it to `T`
+    ///
+    /// Conversion failures are reported as `ColumnMappingError` naming the
+    /// column and `T`.
+    pub fn try_get<T: FromValue>(&self, name: &str) -> Result<T> {
+        T::from_value(self.get_by_name(name)?).map_err(|e| mapping_error::<T>(name, e))
+    }
+
+    /// Gets a value by column index and converts it to `T`
+    pub fn try_get_at<T: FromValue>(&self, index: usize) -> Result<T> {
+        let column = self
+            .columns
+            .get(index)
+            .map(|column| column.name.clone())
+            .unwrap_or_else(|| index.to_string());
+        T::from_value(self.get(index)?).map_err(|e| mapping_error::<T>(&column, e))
+    }
 }
 
 /// Query result containing column metadata and rows
@@ -170,6 +196,16 @@ impl QueryResult {
     pub fn iter(&self) -> impl Iterator<Item = &Row> {
         self.rows.iter()
     }
+
+    /// Maps every row to `T`
+    pub fn into_typed<T: FromRow>(self) -> Result<Vec<T>> {
+        self.rows.iter().map(T::from_row).collect()
+    }
+
+    /// Deserializes every row to `T`, matching fields to column names
+    pub fn into_deserialized<T: DeserializeOwned>(self) -> Result<Vec<T>> {
+        self.rows.iter().map(row_serde::from_row).collect()
+    }
 }
 
 impl IntoIterator for QueryResult {
@@ -181,6 +217,15 @@ impl IntoIterator for QueryResult {
     }
 }
 
+/// Wraps a conversion failure with the column and target type
+fn mapping_error<T>(column: &str, error: DatabaseError) -> DatabaseError {
+    DatabaseError::ColumnMappingError {
+        column: column.to_string(),
+        to: std::any::type_name::<T>(),
+        reason: error.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,840 @@
+//! Serde support for rows and parameters
+//!
+//! `from_row` decodes any `Deserialize` type from a row, matching struct
+//! fields to column names, so types that already derive serde need no
+//! `FromRow` mapping. `to_params` goes the other way and turns a `Serialize`
+//! struct into named parameter values, which `QueryBuilder::values_from`
+//! uses for INSERT columns and values.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct User {
+//!     id: i64,
+//!     #[serde(rename = "user_name")]
+//!     name: String,
+//!     email: Option<String>,
+//! }
+//!
+//! let query = QueryBuilder::insert_into("users").values_from(&user)?;
+//! let users: Vec<User> = client.data().query("SELECT * FROM users").await?.into_deserialized()?;
+//! ```
+//!
+//! Rows also deserialize as maps and, by position, as tuples. Timestamps are
+//! exposed as RFC 3339 strings, which is what chrono's serde support expects.
+//! Serializing only supports flat structs and maps whose fields are scalars,
+//! options, unit enum variants or newtypes of those.
+//!
+//! chrono serializes `DateTime` as a string, so a plain `DateTime<Utc>` field
+//! is bound as text. Mark it with [`timestamp`] to bind it as a timestamp:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Event {
+//!     #[serde(with = "row_serde::timestamp")]
+//!     created: DateTime<Utc>,
+//! }
+//! ```
+
+use crate::error::DatabaseError;
+use crate::result::Row;
+use crate::types::Value;
+use crate::Result;
+use chrono::{DateTime, Utc};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::ser::{self, Impossible, Serialize};
+use std::fmt::Display;
+
+impl de::Error for DatabaseError {
+    fn custom<T: Display>(msg: T) -> Self {
+        DatabaseError::SerializationError {
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl ser::Error for DatabaseError {
+    fn custom<T: Display>(msg: T) -> Self {
+        DatabaseError::SerializationError {
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Deserializes a row into `T`
+///
+/// Errors for a column name the column and the type it was decoded into.
+pub fn from_row<T: DeserializeOwned>(row: &Row) -> Result<T> {
+    T::deserialize(RowDeserializer { row })
+}
+
+/// Serializes a struct or map into `(name, value)` parameters in field order
+pub fn to_params<T: Serialize + ?Sized>(value: &T) -> Result<Vec<(String, Value)>> {
+    value.serialize(ParamsSerializer)
+}
+
+/// Serializes a `DateTime<Utc>` field as a timestamp column
+///
+/// For use with `#[serde(with = "row_serde::timestamp")]`. Other serde
+/// formats see the RFC 3339 string chrono would write.
+pub mod timestamp {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Name of the newtype `to_params` turns into `Value::Timestamp`
+    pub(super) const MARKER: &str = "$row_serde::timestamp";
+
+    /// Serializes `value` as a timestamp
+    pub fn serialize<S: Serializer>(
+        value: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(MARKER, &value.to_rfc3339())
+    }
+
+    /// Deserializes a timestamp, or its RFC 3339 string
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        DateTime::deserialize(deserializer)
+    }
+}
+
+/// Deserializer over the columns of a row
+struct RowDeserializer<'a> {
+    row: &'a Row,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for RowDeserializer<'a> {
+    type Error = DatabaseError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(ColumnAccess {
+            row: self.row,
+            next: 0,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(ColumnAccess {
+            row: self.row,
+            next: 0,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct enum identifier ignored_any
+    }
+}
+
+/// Visits the columns of a row as map entries or sequence elements
+struct ColumnAccess<'a> {
+    row: &'a Row,
+    next: usize,
+}
+
+impl<'a> ColumnAccess<'a> {
+    /// Decodes the next column, naming it and the target type on error
+    fn next_column<'de, S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value> {
+        let index = self.next;
+        self.next += 1;
+        let value = self.row.get(index)?;
+        seed.deserialize(ValueDeserializer { value }).map_err(|e| {
+            DatabaseError::ColumnMappingError {
+                column: self
+                    .row
+                    .columns()
+                    .get(index)
+                    .map(|column| column.name.clone())
+                    .unwrap_or_else(|| index.to_string()),
+                to: std::any::type_name::<S::Value>(),
+                reason: match e {
+                    DatabaseError::SerializationError { message } => message,
+                    e => e.to_string(),
+                },
+            }
+        })
+    }
+}
+
+impl<'de, 'a> MapAccess<'de> for ColumnAccess<'a> {
+    type Error = DatabaseError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.row.columns().get(self.next) {
+            Some(column) => seed
+                .deserialize(column.name.as_str().into_deserializer())
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        self.next_column(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.row.len() - self.next)
+    }
+}
+
+impl<'de, 'a> SeqAccess<'de> for ColumnAccess<'a> {
+    type Error = DatabaseError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.next >= self.row.len() {
+            return Ok(None);
+        }
+        self.next_column(seed).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.row.len() - self.next)
+    }
+}
+
+/// Deserializer over a single column value
+struct ValueDeserializer<'a> {
+    value: &'a Value,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ValueDeserializer<'a> {
+    type Error = DatabaseError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(*b),
+            Value::Int(i) => visitor.visit_i64(*i),
+            Value::Float(f) => visitor.visit_f64(*f),
+            Value::String(s) => visitor.visit_str(s),
+            Value::Bytes(b) => visitor.visit_bytes(b),
+            Value::Timestamp(t) => visitor.visit_string(t.to_rfc3339()),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        // Unit variants are stored by name
+        match self.value {
+            Value::String(s) => visitor.visit_enum(s.as_str().into_deserializer()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Serializes the top-level struct or map into parameters
+struct ParamsSerializer;
+
+/// Collects serialized fields
+struct ParamsBuilder {
+    params: Vec<(String, Value)>,
+    key: Option<String>,
+}
+
+fn params_unsupported(kind: &str) -> DatabaseError {
+    DatabaseError::SerializationError {
+        message: format!("Parameters must be a struct or map, got {}", kind),
+    }
+}
+
+impl ser::Serializer for ParamsSerializer {
+    type Ok = Vec<(String, Value)>;
+    type Error = DatabaseError;
+    type SerializeSeq = Impossible<Self::Ok, DatabaseError>;
+    type SerializeTuple = Impossible<Self::Ok, DatabaseError>;
+    type SerializeTupleStruct = Impossible<Self::Ok, DatabaseError>;
+    type SerializeTupleVariant = Impossible<Self::Ok, DatabaseError>;
+    type SerializeMap = ParamsBuilder;
+    type SerializeStruct = ParamsBuilder;
+    type SerializeStructVariant = Impossible<Self::Ok, DatabaseError>;
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<ParamsBuilder> {
+        Ok(ParamsBuilder {
+            params: Vec::with_capacity(len),
+            key: None,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<ParamsBuilder> {
+        Ok(ParamsBuilder {
+            params: Vec::with_capacity(len.unwrap_or_default()),
+            key: None,
+        })
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok> {
+        Err(params_unsupported("a boolean"))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok> {
+        Err(params_unsupported("an integer"))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok> {
+        Err(params_unsupported("an integer"))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok> {
+        Err(params_unsupported("a float"))
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Self::Ok> {
+        Err(params_unsupported("a string"))
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok> {
+        Err(params_unsupported("a string"))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
+        Err(params_unsupported("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Err(params_unsupported("None"))
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Err(params_unsupported("()"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
+        Err(params_unsupported(name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Err(params_unsupported(name))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok> {
+        Err(params_unsupported(name))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(params_unsupported("a sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(params_unsupported("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(params_unsupported(name))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(params_unsupported(name))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(params_unsupported(name))
+    }
+}
+
+impl ParamsBuilder {
+    /// Serializes a field value, naming the field on error
+    fn push<T: Serialize + ?Sized>(&mut self, name: String, value: &T) -> Result<()> {
+        let value =
+            value
+                .serialize(ValueSerializer)
+                .map_err(|e| DatabaseError::SerializationError {
+                    message: format!("Field {}: {}", name, e),
+                })?;
+        self.params.push((name, value));
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for ParamsBuilder {
+    type Ok = Vec<(String, Value)>;
+    type Error = DatabaseError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.push(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.params)
+    }
+}
+
+impl ser::SerializeMap for ParamsBuilder {
+    type Ok = Vec<(String, Value)>;
+    type Error = DatabaseError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        match key.serialize(ValueSerializer)? {
+            Value::String(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            key => Err(DatabaseError::SerializationError {
+                message: format!("Parameter names must be strings, got {}", key.type_name()),
+            }),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| DatabaseError::SerializationError {
+                message: "Map value serialized before its key".to_string(),
+            })?;
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.params)
+    }
+}
+
+/// Serializes a single field into a `Value`
+struct ValueSerializer;
+
+fn value_unsupported(kind: &str) -> DatabaseError {
+    DatabaseError::SerializationError {
+        message: format!("{} cannot be stored in a column", kind),
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = DatabaseError;
+    type SerializeSeq = Impossible<Value, DatabaseError>;
+    type SerializeTuple = Impossible<Value, DatabaseError>;
+    type SerializeTupleStruct = Impossible<Value, DatabaseError>;
+    type SerializeTupleVariant = Impossible<Value, DatabaseError>;
+    type SerializeMap = Impossible<Value, DatabaseError>;
+    type SerializeStruct = Impossible<Value, DatabaseError>;
+    type SerializeStructVariant = Impossible<Value, DatabaseError>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(Value::Int(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        i64::try_from(v)
+            .map(Value::Int)
+            .map_err(|_| DatabaseError::TypeConversionError {
+                from: "u64".to_string(),
+                to: "Int",
+                value: v.to_string(),
+            })
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        Ok(Value::Float(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        let value = value.serialize(self)?;
+        if name != timestamp::MARKER {
+            return Ok(value);
+        }
+        match &value {
+            Value::String(s) => DateTime::parse_from_rfc3339(s)
+                .map(|t| Value::Timestamp(t.with_timezone(&Utc)))
+                .map_err(|e| ser::Error::custom(format!("invalid timestamp {:?}: {}", s, e))),
+            _ => Err(ser::Error::custom("a timestamp must be an RFC 3339 string")),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Value> {
+        Err(value_unsupported(name))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(value_unsupported("A sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(value_unsupported("A tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(value_unsupported(name))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(value_unsupported(name))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(value_unsupported("A map"))
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(value_unsupported(name))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(value_unsupported(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::{ColumnMetadata, DataType};
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Status {
+        Active,
+        Banned,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Audit {
+        #[serde(with = "timestamp")]
+        created: DateTime<Utc>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct User {
+        id: u32,
+        #[serde(rename = "user_name")]
+        name: String,
+        email: Option<String>,
+        status: Status,
+        #[serde(flatten)]
+        audit: Audit,
+    }
+
+    fn row(columns: &[&str], values: Vec<Value>) -> Row {
+        let columns = columns
+            .iter()
+            .enumerate()
+            .map(|(ordinal, name)| ColumnMetadata {
+                name: name.to_string(),
+                data_type: DataType::String,
+                nullable: true,
+                ordinal,
+            })
+            .collect();
+        Row::new(Arc::new(columns), values)
+    }
+
+    fn user() -> User {
+        User {
+            id: 7,
+            name: "alice".to_string(),
+            email: None,
+            status: Status::Banned,
+            audit: Audit {
+                created: Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_row_deserializes_by_column_name() {
+        let user_row = row(
+            &["status", "id", "user_name", "email", "created", "unused"],
+            vec![
+                Value::String("banned".to_string()),
+                Value::Int(7),
+                Value::String("alice".to_string()),
+                Value::Null,
+                Value::Timestamp(user().audit.created),
+                Value::Bytes(vec![1]),
+            ],
+        );
+        assert_eq!(from_row::<User>(&user_row).unwrap(), user());
+
+        let (id, name): (i64, String) = from_row(&row(
+            &["id", "name"],
+            vec![Value::Int(1), Value::String("bob".to_string())],
+        ))
+        .unwrap();
+        assert_eq!((id, name), (1, "bob".to_string()));
+
+        let map: BTreeMap<String, Option<i64>> =
+            from_row(&row(&["a", "b"], vec![Value::Int(1), Value::Null])).unwrap();
+        assert_eq!(map["a"], Some(1));
+        assert_eq!(map["b"], None);
+    }
+
+    #[test]
+    fn test_deserialize_errors_name_the_column() {
+        let row = row(
+            &["id", "user_name", "email", "status", "created"],
+            vec![
+                Value::Int(-1),
+                Value::String("alice".to_string()),
+                Value::Null,
+                Value::String("active".to_string()),
+                Value::Timestamp(Utc::now()),
+            ],
+        );
+        let error = from_row::<User>(&row).unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                DatabaseError::ColumnMappingError { column, to, .. }
+                    if column == "id" && to.contains("u32")
+            ),
+            "{:?}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_struct_serializes_to_params() {
+        let params = to_params(&user()).unwrap();
+        assert_eq!(
+            params,
+            vec![
+                ("id".to_string(), Value::Int(7)),
+                ("user_name".to_string(), Value::String("alice".to_string())),
+                ("email".to_string(), Value::Null),
+                ("status".to_string(), Value::String("banned".to_string())),
+                (
+                    "created".to_string(),
+                    Value::Timestamp(user().audit.created)
+                ),
+            ]
+        );
+
+        let (columns, values): (Vec<String>, Vec<Value>) = params.into_iter().unzip();
+        let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+        assert_eq!(from_row::<User>(&row(&columns, values)).unwrap(), user());
+
+        assert!(to_params(&(1, 2)).is_err());
+        assert!(to_params(&BTreeMap::from([("nested", vec![1])])).is_err());
+        assert!(to_params(&BTreeMap::from([("n", u64::MAX)])).is_err());
+    }
+}
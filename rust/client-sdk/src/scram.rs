@@ -0,0 +1,443 @@
+//! SCRAM-SHA-256 module for Q-Distributed-Database Client SDK
+//!
+//! This module implements the Salted Challenge Response Authentication
+//! Mechanism with SHA-256 (RFC 5802, RFC 7677). The client proves knowledge
+//! of the password without sending it, and the server proves it holds the
+//! stored verifier, so neither side can be impersonated by replaying the
+//! exchange.
+//!
+//! The exchange consists of four messages, without channel binding:
+//!
+//! ```text
+//! client-first  n,,n=<username>,r=<client nonce>
+//! server-first  r=<client nonce><server nonce>,s=<base64 salt>,i=<iterations>
+//! client-final  c=biws,r=<client nonce><server nonce>,p=<base64 client proof>
+//! server-final  v=<base64 server signature>
+//! ```
+//!
+//! Passwords are used as their UTF-8 bytes, without SASLprep normalization.
+
+use crate::error::DatabaseError;
+use crate::secret::SecretString;
+use crate::Result;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Name of the mechanism
+pub const MECHANISM: &str = "SCRAM-SHA-256";
+
+/// Smallest iteration count accepted from a server (RFC 7677)
+pub const MIN_ITERATIONS: u32 = 4096;
+
+/// Largest iteration count accepted from a server, so a hostile server cannot
+/// make the client spin
+pub const MAX_ITERATIONS: u32 = 1_000_000;
+
+/// Random bytes in each side's nonce
+const NONCE_BYTES: usize = 18;
+
+/// GS2 header of a client that does not support channel binding
+const GS2_HEADER: &str = "n,,";
+
+/// Base64 of `GS2_HEADER`, the channel binding attribute of the final message
+const CHANNEL_BINDING: &str = "biws";
+
+/// Client side of an exchange
+///
+/// # Example
+///
+/// ```ignore
+/// let client = ScramClient::new("alice", "secret")?;
+/// let server_first = send(client.client_first()).await?;
+/// let (client_final, verifier) = client.client_final(&server_first)?;
+/// let server_final = send(client_final).await?;
+/// verifier.verify(&server_final)?;
+/// ```
+pub struct ScramClient {
+    password: SecretString,
+    client_nonce: String,
+    client_first_bare: String,
+}
+
+impl ScramClient {
+    /// Starts an exchange with a random nonce
+    pub fn new(username: &str, password: &str) -> Result<Self> {
+        Ok(Self::with_nonce(username, password, nonce()?))
+    }
+
+    fn with_nonce(username: &str, password: &str, client_nonce: String) -> Self {
+        Self {
+            password: SecretString::new(password),
+            client_first_bare: format!("n={},r={}", escape(username), client_nonce),
+            client_nonce,
+        }
+    }
+
+    /// Returns the client-first message
+    pub fn client_first(&self) -> String {
+        format!("{}{}", GS2_HEADER, self.client_first_bare)
+    }
+
+    /// Answers the server-first message with the client-final message
+    ///
+    /// Returns the verifier for the server-final message. Fails if the server
+    /// nonce does not extend the client nonce or the iteration count is
+    /// outside `MIN_ITERATIONS..=MAX_ITERATIONS`.
+    pub fn client_final(self, server_first: &str) -> Result<(String, ScramVerifier)> {
+        let attributes = attributes(server_first)?;
+        if let Some(reason) = attribute(&attributes, 'e') {
+            return Err(failed(format!("server rejected the exchange: {}", reason)));
+        }
+
+        let nonce = required(&attributes, 'r')?;
+        if !nonce.starts_with(&self.client_nonce) || nonce.len() == self.client_nonce.len() {
+            return Err(failed("server nonce does not extend the client nonce"));
+        }
+        let salt = decode(required(&attributes, 's')?)?;
+        if salt.is_empty() {
+            return Err(failed("empty salt"));
+        }
+        let iterations: u32 = required(&attributes, 'i')?
+            .parse()
+            .map_err(|_| failed("invalid iteration count"))?;
+        if !(MIN_ITERATIONS..=MAX_ITERATIONS).contains(&iterations) {
+            return Err(failed(format!(
+                "iteration count {} outside {}..={}",
+                iterations, MIN_ITERATIONS, MAX_ITERATIONS
+            )));
+        }
+
+        let client_final_without_proof = format!("c={},r={}", CHANNEL_BINDING, nonce);
+        let auth_message = format!(
+            "{},{},{}",
+            self.client_first_bare, server_first, client_final_without_proof
+        );
+
+        let salted = salted_password(self.password.expose(), &salt, iterations);
+        let client_key = hmac(&salted, b"Client Key");
+        let credentials = ScramCredentials::from_salted(&salted, &salt, iterations);
+        let client_signature = hmac(&credentials.stored_key, auth_message.as_bytes());
+        let proof: Vec<u8> = client_key
+            .iter()
+            .zip(&client_signature)
+            .map(|(key, signature)| key ^ signature)
+            .collect();
+
+        let client_final = format!("{},p={}", client_final_without_proof, BASE64.encode(proof));
+        let verifier = ScramVerifier {
+            server_key: credentials.server_key,
+            auth_message,
+        };
+        Ok((client_final, verifier))
+    }
+}
+
+/// Checks the server-final message of an exchange
+pub struct ScramVerifier {
+    server_key: Vec<u8>,
+    auth_message: String,
+}
+
+impl ScramVerifier {
+    /// Verifies the server signature in the server-final message
+    pub fn verify(&self, server_final: &str) -> Result<()> {
+        let attributes = attributes(server_final)?;
+        if let Some(reason) = attribute(&attributes, 'e') {
+            return Err(failed(format!("server rejected the exchange: {}", reason)));
+        }
+
+        let signature = decode(required(&attributes, 'v')?)?;
+        let mut mac = mac(&self.server_key);
+        mac.update(self.auth_message.as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| failed("server signature mismatch"))
+    }
+}
+
+/// Verifier a server stores in place of the password
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScramCredentials {
+    /// Salt of the salted password
+    pub salt: Vec<u8>,
+    /// PBKDF2 iteration count
+    pub iterations: u32,
+    /// `H(HMAC(salted password, "Client Key"))`
+    pub stored_key: Vec<u8>,
+    /// `HMAC(salted password, "Server Key")`
+    pub server_key: Vec<u8>,
+}
+
+impl ScramCredentials {
+    /// Derives the verifier for a password
+    pub fn new(password: &str, salt: &[u8], iterations: u32) -> Self {
+        Self::from_salted(
+            &salted_password(password, salt, iterations),
+            salt,
+            iterations,
+        )
+    }
+
+    fn from_salted(salted: &[u8], salt: &[u8], iterations: u32) -> Self {
+        Self {
+            salt: salt.to_vec(),
+            iterations,
+            stored_key: Sha256::digest(hmac(salted, b"Client Key")).to_vec(),
+            server_key: hmac(salted, b"Server Key"),
+        }
+    }
+}
+
+/// Server side of an exchange
+pub struct ScramServer {
+    credentials: ScramCredentials,
+    client_first_bare: String,
+    server_first: String,
+    nonce: String,
+}
+
+impl ScramServer {
+    /// Returns the username of a client-first message, to look up its
+    /// credentials
+    pub fn username(client_first: &str) -> Result<String> {
+        let bare = client_first_bare(client_first)?;
+        unescape(required(&attributes(bare)?, 'n')?)
+    }
+
+    /// Starts an exchange from the client-first message
+    pub fn new(client_first: &str, credentials: ScramCredentials) -> Result<Self> {
+        let client_first_bare = client_first_bare(client_first)?.to_string();
+        let client_nonce = required(&attributes(&client_first_bare)?, 'r')?.to_string();
+        let nonce = format!("{}{}", client_nonce, self::nonce()?);
+        let server_first = format!(
+            "r={},s={},i={}",
+            nonce,
+            BASE64.encode(&credentials.salt),
+            credentials.iterations
+        );
+
+        Ok(Self {
+            credentials,
+            client_first_bare,
+            server_first,
+            nonce,
+        })
+    }
+
+    /// Returns the server-first message
+    pub fn server_first(&self) -> &str {
+        &self.server_first
+    }
+
+    /// Checks the client proof and returns the server-final message
+    pub fn finish(&self, client_final: &str) -> Result<String> {
+        let (without_proof, proof) = client_final
+            .rsplit_once(",p=")
+            .ok_or_else(|| failed("missing client proof"))?;
+        let attributes = attributes(without_proof)?;
+        if required(&attributes, 'c')? != CHANNEL_BINDING {
+            return Err(failed("unsupported channel binding"));
+        }
+        if required(&attributes, 'r')? != self.nonce {
+            return Err(failed("nonce mismatch"));
+        }
+
+        let auth_message = format!(
+            "{},{},{}",
+            self.client_first_bare, self.server_first, without_proof
+        );
+        let client_signature = hmac(&self.credentials.stored_key, auth_message.as_bytes());
+        let client_key: Vec<u8> = decode(proof)?
+            .iter()
+            .zip(&client_signature)
+            .map(|(proof, signature)| proof ^ signature)
+            .collect();
+        if !constant_time_eq(&Sha256::digest(&client_key), &self.credentials.stored_key) {
+            return Err(failed("invalid client proof"));
+        }
+
+        let server_signature = hmac(&self.credentials.server_key, auth_message.as_bytes());
+        Ok(format!("v={}", BASE64.encode(server_signature)))
+    }
+}
+
+/// Returns a random printable nonce
+fn nonce() -> Result<String> {
+    let mut bytes = [0u8; NONCE_BYTES];
+    getrandom::getrandom(&mut bytes).map_err(|e| failed(format!("no randomness: {}", e)))?;
+    Ok(BASE64.encode(bytes))
+}
+
+fn salted_password(password: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut salted = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut salted);
+    salted
+}
+
+fn mac(key: &[u8]) -> HmacSha256 {
+    HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = mac(key);
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn client_first_bare(client_first: &str) -> Result<&str> {
+    client_first
+        .strip_prefix(GS2_HEADER)
+        .ok_or_else(|| failed("unsupported GS2 header"))
+}
+
+/// Splits a message into its `key=value` attributes
+fn attributes(message: &str) -> Result<Vec<(char, &str)>> {
+    message
+        .split(',')
+        .map(|attribute| match attribute.split_once('=') {
+            Some((key, value)) if key.len() == 1 && key.as_bytes()[0].is_ascii_alphabetic() => {
+                Ok((char::from(key.as_bytes()[0]), value))
+            }
+            _ => Err(failed(format!("malformed attribute {:?}", attribute))),
+        })
+        .collect()
+}
+
+fn attribute<'a>(attributes: &[(char, &'a str)], key: char) -> Option<&'a str> {
+    attributes.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+fn required<'a>(attributes: &[(char, &'a str)], key: char) -> Result<&'a str> {
+    attribute(attributes, key).ok_or_else(|| failed(format!("missing attribute {}", key)))
+}
+
+fn decode(value: &str) -> Result<Vec<u8>> {
+    BASE64
+        .decode(value)
+        .map_err(|_| failed("invalid base64 value"))
+}
+
+/// Escapes a username as a SASL name
+fn escape(username: &str) -> String {
+    username.replace('=', "=3D").replace(',', "=2C")
+}
+
+fn unescape(name: &str) -> Result<String> {
+    let unescaped = name.replace("=2C", ",").replace("=3D", "=");
+    if name.replace("=2C", "").replace("=3D", "").contains('=') {
+        return Err(failed("invalid username escape"));
+    }
+    Ok(unescaped)
+}
+
+fn failed(reason: impl std::fmt::Display) -> DatabaseError {
+    DatabaseError::AuthenticationFailed {
+        reason: format!("{}: {}", MECHANISM, reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vector from RFC 7677, section 3
+    const CLIENT_FIRST: &str = "n,,n=user,r=rOprNGfwEbeRWgbNEkqO";
+    const SERVER_FIRST: &str =
+        "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+    const CLIENT_FINAL: &str = "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=";
+    const SERVER_FINAL: &str = "v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=";
+
+    fn rfc_client() -> ScramClient {
+        ScramClient::with_nonce("user", "pencil", "rOprNGfwEbeRWgbNEkqO".to_string())
+    }
+
+    #[test]
+    fn test_client_matches_rfc_vector() {
+        let client = rfc_client();
+        assert_eq!(client.client_first(), CLIENT_FIRST);
+
+        let (client_final, verifier) = client.client_final(SERVER_FIRST).unwrap();
+        assert_eq!(client_final, CLIENT_FINAL);
+        verifier.verify(SERVER_FINAL).unwrap();
+    }
+
+    #[test]
+    fn test_client_rejects_forged_server_signature() {
+        let (_, verifier) = rfc_client().client_final(SERVER_FIRST).unwrap();
+        let forged = format!("v={}", BASE64.encode([0u8; 32]));
+        assert!(matches!(
+            verifier.verify(&forged),
+            Err(DatabaseError::AuthenticationFailed { .. })
+        ));
+        assert!(verifier.verify("e=invalid-proof").is_err());
+    }
+
+    #[test]
+    fn test_client_rejects_weak_or_foreign_challenges() {
+        // Iteration count below the minimum
+        let weak = SERVER_FIRST.replace("i=4096", "i=1");
+        assert!(rfc_client().client_final(&weak).is_err());
+
+        // Nonce that does not extend the client nonce
+        let foreign = SERVER_FIRST.replace("rOprNGfwEbeRWgbNEkqO%", "someoneElsesNonce%");
+        assert!(rfc_client().client_final(&foreign).is_err());
+
+        assert!(rfc_client().client_final("garbage").is_err());
+    }
+
+    #[test]
+    fn test_server_round_trip() {
+        let credentials = ScramCredentials::new("secret", b"salt", MIN_ITERATIONS);
+        let client = ScramClient::new("al,ice=", "secret").unwrap();
+
+        let client_first = client.client_first();
+        assert_eq!(ScramServer::username(&client_first).unwrap(), "al,ice=");
+        let server = ScramServer::new(&client_first, credentials).unwrap();
+
+        let (client_final, verifier) = client.client_final(server.server_first()).unwrap();
+        assert!(!client_final.contains("secret"));
+        let server_final = server.finish(&client_final).unwrap();
+        verifier.verify(&server_final).unwrap();
+    }
+
+    #[test]
+    fn test_server_rejects_wrong_password() {
+        let credentials = ScramCredentials::new("secret", b"salt", MIN_ITERATIONS);
+        let client = ScramClient::new("alice", "wrong").unwrap();
+        let server = ScramServer::new(&client.client_first(), credentials).unwrap();
+
+        let (client_final, _) = client.client_final(server.server_first()).unwrap();
+        assert!(matches!(
+            server.finish(&client_final),
+            Err(DatabaseError::AuthenticationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_multibyte_attribute_keys_fail_authentication() {
+        let server_first = format!("€=x,{}", SERVER_FIRST);
+        assert!(matches!(
+            rfc_client().client_final(&server_first),
+            Err(DatabaseError::AuthenticationFailed { .. })
+        ));
+
+        let client_first = "n,,€=x,n=user,r=nonce";
+        assert!(matches!(
+            ScramServer::username(client_first),
+            Err(DatabaseError::AuthenticationFailed { .. })
+        ));
+        let credentials = ScramCredentials::new("secret", b"salt", MIN_ITERATIONS);
+        assert!(matches!(
+            ScramServer::new(client_first, credentials),
+            Err(DatabaseError::AuthenticationFailed { .. })
+        ));
+    }
+}
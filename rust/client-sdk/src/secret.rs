@@ -0,0 +1,205 @@
+//! Secret values for Q-Distributed-Database Client SDK
+//!
+//! Passwords, tokens and session keys are held in `SecretString` and
+//! `SecretBytes`. They print as `[REDACTED]` with `Debug` and `Display`, so
+//! they cannot end up in logs or panic messages, and their memory is zeroed
+//! when they are dropped. Reading the value takes an explicit `expose`.
+//!
+//! Both serialize exactly like the plain value, since requests must carry it
+//! over the wire.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Printed in place of a secret
+const REDACTED: &str = "[REDACTED]";
+
+/// Secret text, such as a password or an opaque token
+#[derive(Clone, Default)]
+pub struct SecretString(String);
+
+impl SecretString {
+    /// Wraps a secret
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// Returns the secret value
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Checks if the secret is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl PartialEq for SecretString {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(self.0.as_bytes(), other.0.as_bytes())
+    }
+}
+
+impl Eq for SecretString {}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+/// Secret bytes, such as a token signature or a session key
+#[derive(Clone, Default)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    /// Wraps a secret
+    pub fn new(value: impl Into<Vec<u8>>) -> Self {
+        Self(value.into())
+    }
+
+    /// Returns the secret value
+    pub fn expose(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the length of the secret in bytes
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Checks if the secret is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Serialize for SecretBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<u8>::deserialize(deserializer).map(Self)
+    }
+}
+
+/// Compares two secrets in time independent of where they differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let password = SecretString::new("hunter2");
+        assert_eq!(format!("{:?}", password), "[REDACTED]");
+        assert_eq!(password.to_string(), "[REDACTED]");
+        assert_eq!(password.expose(), "hunter2");
+
+        let key = SecretBytes::new(vec![1, 2, 3]);
+        assert_eq!(format!("{:?}", Some(key.clone())), "Some([REDACTED])");
+        assert_eq!(key.expose(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_secrets_serialize_like_plain_values() {
+        let password = SecretString::new("hunter2");
+        assert_eq!(
+            bincode::serialize(&password).unwrap(),
+            bincode::serialize("hunter2").unwrap()
+        );
+        let decoded: SecretString =
+            bincode::deserialize(&bincode::serialize(&password).unwrap()).unwrap();
+        assert_eq!(decoded, password);
+
+        let signature = SecretBytes::new(vec![0xaa; 4]);
+        assert_eq!(
+            bincode::serialize(&signature).unwrap(),
+            bincode::serialize(&vec![0xaau8; 4]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_secret_equality() {
+        assert_eq!(SecretBytes::new(vec![1, 2]), SecretBytes::new(vec![1, 2]));
+        assert_ne!(SecretBytes::new(vec![1, 2]), SecretBytes::new(vec![1, 3]));
+        assert_ne!(SecretBytes::new(vec![1]), SecretBytes::new(vec![1, 0]));
+        assert_ne!(SecretString::from("a"), SecretString::from("b"));
+    }
+}
@@ -0,0 +1,756 @@
+//! Fault-injecting transport layer
+//!
+//! `FaultInjector` wraps each connection's transport and applies the faults
+//! configured for its node to whole frames in both directions: frames can be
+//! dropped, swapped with the following frame, cut short, or have a bit of
+//! their trailing checksum flipped, and connections can be reset. Writes can
+//! be delayed and reads stalled. Every connection draws from its own random
+//! generator seeded from the injector seed, the node and the number of
+//! connections opened to that node before it, so a test that opens
+//! connections in the same order sees the same faults on every run.
+//!
+//! Fault settings are read for every frame, so changing them with
+//! `set_node` takes effect on connections that are already open.
+
+use crate::transport::{Transport, TransportLayer};
+use crate::types::NodeId;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::Sleep;
+
+/// Length of the frame length prefix
+const LENGTH_PREFIX: usize = 4;
+
+/// Length of the checksum that ends an uncompressed frame
+const CHECKSUM_LEN: usize = 4;
+
+/// Directions of a connection that faults are applied to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FaultDirection {
+    /// Requests and responses
+    #[default]
+    Both,
+    /// Frames written by the client
+    Outgoing,
+    /// Frames read by the client
+    Incoming,
+}
+
+impl FaultDirection {
+    fn includes(self, direction: FaultDirection) -> bool {
+        self == FaultDirection::Both || self == direction
+    }
+}
+
+/// Faults applied to the connections of one node
+///
+/// Rates are probabilities between 0.0 and 1.0. Frame faults are drawn per
+/// frame and direction; stalls are drawn per read from the underlying
+/// transport.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaultConfig {
+    /// Delay before each flushed write
+    pub latency_ms: u64,
+    /// Probability that a frame is silently discarded
+    pub drop_rate: f64,
+    /// Probability that a frame is held back and delivered after the next one
+    pub reorder_rate: f64,
+    /// Probability that only part of a frame is delivered before a reset
+    pub truncate_rate: f64,
+    /// Probability that a bit of the frame's trailing checksum is flipped
+    pub corrupt_rate: f64,
+    /// Probability that the connection is reset instead of passing the frame
+    pub reset_rate: f64,
+    /// Probability that a read is stalled for `stall_ms`
+    pub stall_rate: f64,
+    /// Duration of a stalled read
+    pub stall_ms: u64,
+    /// Directions the faults apply to; latency counts as outgoing and
+    /// stalls as incoming
+    pub direction: FaultDirection,
+}
+
+impl FaultConfig {
+    /// Creates a configuration that injects no faults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the delay before each flushed write
+    pub fn with_latency(mut self, latency_ms: u64) -> Self {
+        self.latency_ms = latency_ms;
+        self
+    }
+
+    /// Sets the probability of dropping a frame
+    pub fn with_drop_rate(mut self, rate: f64) -> Self {
+        self.drop_rate = rate;
+        self
+    }
+
+    /// Sets the probability of delivering a frame after the next one
+    pub fn with_reorder_rate(mut self, rate: f64) -> Self {
+        self.reorder_rate = rate;
+        self
+    }
+
+    /// Sets the probability of truncating a frame and resetting
+    pub fn with_truncate_rate(mut self, rate: f64) -> Self {
+        self.truncate_rate = rate;
+        self
+    }
+
+    /// Sets the probability of corrupting a frame
+    ///
+    /// Corruption flips a bit of the frame's last four bytes, which hold the
+    /// message checksum of uncompressed frames without extensions, so the
+    /// receiver fails with `ChecksumMismatch`.
+    pub fn with_corrupt_rate(mut self, rate: f64) -> Self {
+        self.corrupt_rate = rate;
+        self
+    }
+
+    /// Sets the probability of resetting the connection
+    pub fn with_reset_rate(mut self, rate: f64) -> Self {
+        self.reset_rate = rate;
+        self
+    }
+
+    /// Sets the probability and duration of stalled reads
+    pub fn with_stall(mut self, rate: f64, stall_ms: u64) -> Self {
+        self.stall_rate = rate;
+        self.stall_ms = stall_ms;
+        self
+    }
+
+    /// Restricts the faults to one direction
+    pub fn with_direction(mut self, direction: FaultDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+}
+
+/// Number of faults injected so far, across all connections
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultCounts {
+    pub dropped: u64,
+    pub reordered: u64,
+    pub truncated: u64,
+    pub corrupted: u64,
+    pub resets: u64,
+    pub stalls: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    dropped: AtomicU64,
+    reordered: AtomicU64,
+    truncated: AtomicU64,
+    corrupted: AtomicU64,
+    resets: AtomicU64,
+    stalls: AtomicU64,
+}
+
+impl Counters {
+    fn bump(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Settings {
+    nodes: HashMap<NodeId, FaultConfig>,
+    default: Option<FaultConfig>,
+    connections: HashMap<NodeId, u64>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    settings: Mutex<Settings>,
+    counters: Counters,
+}
+
+impl Shared {
+    fn config(&self, node_id: NodeId, direction: FaultDirection) -> Option<FaultConfig> {
+        let settings = self.settings.lock().unwrap();
+        settings
+            .nodes
+            .get(&node_id)
+            .or(settings.default.as_ref())
+            .filter(|config| config.direction.includes(direction))
+            .cloned()
+    }
+}
+
+/// Transport layer injecting network faults, configured per node
+///
+/// ```no_run
+/// use q_distributed_db_client::testing::{FaultConfig, FaultInjector, MockServer};
+/// use q_distributed_db_client::Client;
+/// use std::sync::Arc;
+///
+/// # async fn example() -> q_distributed_db_client::Result<()> {
+/// let server = MockServer::start().await?;
+/// let faults = FaultInjector::new(42);
+/// let client = Client::connect(
+///     server.config().with_transport_layer(Arc::new(faults.clone())),
+/// )
+/// .await?;
+///
+/// // Drop a quarter of the frames to and from node 1 from now on
+/// faults.set_node(1, Some(FaultConfig::new().with_drop_rate(0.25)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    seed: u64,
+    shared: Arc<Shared>,
+}
+
+impl FaultInjector {
+    /// Creates an injector with no faults configured
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            shared: Arc::new(Shared::default()),
+        }
+    }
+
+    /// Sets the faults applied to connections to `node_id`
+    pub fn with_node(self, node_id: NodeId, config: FaultConfig) -> Self {
+        self.set_node(node_id, Some(config));
+        self
+    }
+
+    /// Sets the faults applied to nodes without their own configuration
+    pub fn with_default(self, config: FaultConfig) -> Self {
+        self.set_default(Some(config));
+        self
+    }
+
+    /// Replaces or removes the faults applied to connections to `node_id`
+    pub fn set_node(&self, node_id: NodeId, config: Option<FaultConfig>) {
+        let mut settings = self.shared.settings.lock().unwrap();
+        match config {
+            Some(config) => settings.nodes.insert(node_id, config),
+            None => settings.nodes.remove(&node_id),
+        };
+    }
+
+    /// Replaces or removes the faults applied to nodes without their own
+    /// configuration
+    pub fn set_default(&self, config: Option<FaultConfig>) {
+        self.shared.settings.lock().unwrap().default = config;
+    }
+
+    /// Returns the number of faults injected so far
+    pub fn counts(&self) -> FaultCounts {
+        let counters = &self.shared.counters;
+        FaultCounts {
+            dropped: counters.dropped.load(Ordering::Relaxed),
+            reordered: counters.reordered.load(Ordering::Relaxed),
+            truncated: counters.truncated.load(Ordering::Relaxed),
+            corrupted: counters.corrupted.load(Ordering::Relaxed),
+            resets: counters.resets.load(Ordering::Relaxed),
+            stalls: counters.stalls.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl TransportLayer for FaultInjector {
+    fn wrap(&self, node_id: NodeId, transport: Box<dyn Transport>) -> Box<dyn Transport> {
+        let index = {
+            let mut settings = self.shared.settings.lock().unwrap();
+            let count = settings.connections.entry(node_id).or_insert(0);
+            *count += 1;
+            *count - 1
+        };
+        let seed = self.seed ^ (node_id << 32) ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+
+        Box::new(FaultTransport {
+            inner: transport,
+            node_id,
+            shared: self.shared.clone(),
+            rng: Rng::new(seed),
+            outgoing: FrameShaper::default(),
+            pending_out: Vec::new(),
+            written: 0,
+            write_delay: None,
+            reset_after_flush: false,
+            incoming: FrameShaper::default(),
+            pending_in: Vec::new(),
+            delivered: 0,
+            read_delay: None,
+            stall_drawn: false,
+            reset_after_read: false,
+            broken: false,
+        })
+    }
+}
+
+/// Deterministic SplitMix64 generator
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns true with the given probability
+    fn chance(&mut self, rate: f64) -> bool {
+        rate > 0.0 && ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < rate
+    }
+
+    /// Returns a value in `0..bound`
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// Result of passing bytes through a `FrameShaper`
+#[derive(Debug, PartialEq)]
+enum Shaped {
+    /// Bytes to deliver
+    Bytes(Vec<u8>),
+    /// Bytes to deliver before resetting the connection
+    Truncated(Vec<u8>),
+    /// The connection is reset without delivering anything more
+    Reset,
+}
+
+/// Splits one direction of a byte stream into frames and applies faults
+#[derive(Debug, Default)]
+struct FrameShaper {
+    buffer: Vec<u8>,
+    held: Option<Vec<u8>>,
+}
+
+impl FrameShaper {
+    fn next_frame(&mut self) -> Option<Vec<u8>> {
+        let prefix = self.buffer.get(..LENGTH_PREFIX)?;
+        let len = u32::from_be_bytes(prefix.try_into().unwrap()) as usize;
+        if self.buffer.len() < LENGTH_PREFIX + len {
+            return None;
+        }
+        Some(self.buffer.drain(..LENGTH_PREFIX + len).collect())
+    }
+
+    fn shape(
+        &mut self,
+        data: &[u8],
+        config: Option<&FaultConfig>,
+        rng: &mut Rng,
+        counters: &Counters,
+    ) -> Shaped {
+        self.buffer.extend_from_slice(data);
+
+        let mut out = Vec::new();
+        while let Some(mut frame) = self.next_frame() {
+            if let Some(config) = config {
+                if rng.chance(config.reset_rate) {
+                    Counters::bump(&counters.resets);
+                    return Shaped::Reset;
+                }
+                if rng.chance(config.drop_rate) {
+                    Counters::bump(&counters.dropped);
+                    continue;
+                }
+                if rng.chance(config.truncate_rate) {
+                    Counters::bump(&counters.truncated);
+                    let cut = LENGTH_PREFIX + rng.below(frame.len() - LENGTH_PREFIX);
+                    out.extend_from_slice(&frame[..cut]);
+                    return Shaped::Truncated(out);
+                }
+                if frame.len() >= LENGTH_PREFIX + CHECKSUM_LEN && rng.chance(config.corrupt_rate) {
+                    Counters::bump(&counters.corrupted);
+                    let index = frame.len() - 1 - rng.below(CHECKSUM_LEN);
+                    frame[index] ^= 1 << rng.below(8);
+                }
+                if self.held.is_none() && rng.chance(config.reorder_rate) {
+                    Counters::bump(&counters.reordered);
+                    self.held = Some(frame);
+                    continue;
+                }
+            }
+
+            out.extend_from_slice(&frame);
+            if let Some(held) = self.held.take() {
+                out.extend_from_slice(&held);
+            }
+        }
+        Shaped::Bytes(out)
+    }
+}
+
+/// Transport applying a node's faults to an inner transport
+struct FaultTransport {
+    inner: Box<dyn Transport>,
+    node_id: NodeId,
+    shared: Arc<Shared>,
+    rng: Rng,
+    outgoing: FrameShaper,
+    /// Shaped bytes not yet written to the inner transport
+    pending_out: Vec<u8>,
+    written: usize,
+    write_delay: Option<Pin<Box<Sleep>>>,
+    reset_after_flush: bool,
+    incoming: FrameShaper,
+    /// Shaped bytes not yet returned to the reader
+    pending_in: Vec<u8>,
+    delivered: usize,
+    read_delay: Option<Pin<Box<Sleep>>>,
+    stall_drawn: bool,
+    reset_after_read: bool,
+    broken: bool,
+}
+
+fn reset_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::ConnectionReset,
+        "connection reset by fault injector",
+    )
+}
+
+impl AsyncWrite for FaultTransport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.broken {
+            return Poll::Ready(Err(reset_error()));
+        }
+
+        let config = this.shared.config(this.node_id, FaultDirection::Outgoing);
+        match this
+            .outgoing
+            .shape(buf, config.as_ref(), &mut this.rng, &this.shared.counters)
+        {
+            Shaped::Bytes(bytes) => this.pending_out.extend_from_slice(&bytes),
+            Shaped::Truncated(bytes) => {
+                this.pending_out.extend_from_slice(&bytes);
+                this.reset_after_flush = true;
+            }
+            Shaped::Reset => {
+                this.broken = true;
+                return Poll::Ready(Err(reset_error()));
+            }
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.broken {
+            return Poll::Ready(Err(reset_error()));
+        }
+
+        if this.written < this.pending_out.len() {
+            if this.write_delay.is_none() {
+                let latency_ms = this
+                    .shared
+                    .config(this.node_id, FaultDirection::Outgoing)
+                    .map_or(0, |config| config.latency_ms);
+                if latency_ms > 0 {
+                    this.write_delay = Some(Box::pin(tokio::time::sleep(Duration::from_millis(
+                        latency_ms,
+                    ))));
+                }
+            }
+            if let Some(delay) = &mut this.write_delay {
+                if delay.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+            }
+
+            while this.written < this.pending_out.len() {
+                match Pin::new(&mut this.inner).poll_write(cx, &this.pending_out[this.written..]) {
+                    Poll::Ready(Ok(0)) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                    Poll::Ready(Ok(n)) => this.written += n,
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+            this.pending_out.clear();
+            this.written = 0;
+            this.write_delay = None;
+        }
+
+        if this.reset_after_flush {
+            this.broken = true;
+            // The peer sees the partial frame followed by a closed stream
+            let _ = Pin::new(&mut this.inner).poll_shutdown(cx);
+            return Poll::Ready(Err(reset_error()));
+        }
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.as_mut().poll_flush(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut self.get_mut().inner).poll_shutdown(cx),
+            other => other,
+        }
+    }
+}
+
+impl AsyncRead for FaultTransport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.delivered < this.pending_in.len() {
+                let available = &this.pending_in[this.delivered..];
+                let n = available.len().min(buf.remaining());
+                buf.put_slice(&available[..n]);
+                this.delivered += n;
+                if this.delivered == this.pending_in.len() {
+                    this.pending_in.clear();
+                    this.delivered = 0;
+                }
+                return Poll::Ready(Ok(()));
+            }
+            if this.reset_after_read {
+                this.broken = true;
+            }
+            if this.broken {
+                return Poll::Ready(Err(reset_error()));
+            }
+
+            let config = this.shared.config(this.node_id, FaultDirection::Incoming);
+            if !this.stall_drawn {
+                this.stall_drawn = true;
+                if let Some(config) = &config {
+                    if config.stall_ms > 0 && this.rng.chance(config.stall_rate) {
+                        Counters::bump(&this.shared.counters.stalls);
+                        this.read_delay = Some(Box::pin(tokio::time::sleep(
+                            Duration::from_millis(config.stall_ms),
+                        )));
+                    }
+                }
+            }
+            if let Some(delay) = &mut this.read_delay {
+                if delay.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                this.read_delay = None;
+            }
+
+            let mut chunk = [0u8; 8192];
+            let mut chunk_buf = ReadBuf::new(&mut chunk);
+            match Pin::new(&mut this.inner).poll_read(cx, &mut chunk_buf) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+            if chunk_buf.filled().is_empty() {
+                // End of stream; an incomplete frame is never delivered
+                return Poll::Ready(Ok(()));
+            }
+            this.stall_drawn = false;
+
+            match this.incoming.shape(
+                chunk_buf.filled(),
+                config.as_ref(),
+                &mut this.rng,
+                &this.shared.counters,
+            ) {
+                Shaped::Bytes(bytes) => this.pending_in = bytes,
+                Shaped::Truncated(bytes) => {
+                    this.pending_in = bytes;
+                    this.reset_after_read = true;
+                }
+                Shaped::Reset => this.broken = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::Connection;
+    use crate::error::DatabaseError;
+    use crate::protocol::{Message, MessageType};
+    use crate::testing::MockServer;
+
+    fn frame(body: &[u8]) -> Vec<u8> {
+        let mut frame = (body.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(body);
+        frame
+    }
+
+    fn shape_all(config: &FaultConfig, seed: u64, frames: &[Vec<u8>]) -> Vec<Shaped> {
+        let mut shaper = FrameShaper::default();
+        let mut rng = Rng::new(seed);
+        let counters = Counters::default();
+        frames
+            .iter()
+            .map(|f| shaper.shape(f, Some(config), &mut rng, &counters))
+            .collect()
+    }
+
+    async fn connect(server: &MockServer, faults: &FaultInjector) -> Connection {
+        let config = server
+            .config()
+            .with_transport_layer(Arc::new(faults.clone()));
+        Connection::connect_with_config(&server.address(), 1, &config)
+            .await
+            .unwrap()
+    }
+
+    async fn ping(connection: &mut Connection, timeout_ms: u64) -> crate::Result<Message> {
+        connection
+            .send_request(MessageType::Ping, Vec::new(), timeout_ms)
+            .await
+    }
+
+    #[test]
+    fn test_shaper_reassembles_split_frames() {
+        let mut shaper = FrameShaper::default();
+        let mut rng = Rng::new(1);
+        let counters = Counters::default();
+        let bytes = [frame(b"first"), frame(b"second")].concat();
+
+        assert_eq!(
+            shaper.shape(&bytes[..7], None, &mut rng, &counters),
+            Shaped::Bytes(vec![])
+        );
+        assert_eq!(
+            shaper.shape(&bytes[7..], None, &mut rng, &counters),
+            Shaped::Bytes(bytes.clone())
+        );
+    }
+
+    #[test]
+    fn test_shaper_reorders_with_next_frame() {
+        let config = FaultConfig::new().with_reorder_rate(1.0);
+        let shaped = shape_all(&config, 7, &[frame(b"a"), frame(b"b")]);
+        assert_eq!(shaped[0], Shaped::Bytes(vec![]));
+        assert_eq!(
+            shaped[1],
+            Shaped::Bytes([frame(b"b"), frame(b"a")].concat())
+        );
+    }
+
+    #[test]
+    fn test_shaper_truncates_within_frame() {
+        let config = FaultConfig::new().with_truncate_rate(1.0);
+        let original = frame(b"some frame body");
+        match &shape_all(&config, 3, std::slice::from_ref(&original))[0] {
+            Shaped::Truncated(bytes) => {
+                assert!(bytes.len() >= LENGTH_PREFIX && bytes.len() < original.len());
+                assert_eq!(bytes[..], original[..bytes.len()]);
+            }
+            other => panic!("expected truncation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_faults() {
+        let config = FaultConfig::new()
+            .with_drop_rate(0.3)
+            .with_corrupt_rate(0.3)
+            .with_reorder_rate(0.3);
+        let frames: Vec<_> = (0..50u8).map(|i| frame(&[i; 12])).collect();
+
+        assert_eq!(
+            shape_all(&config, 11, &frames),
+            shape_all(&config, 11, &frames)
+        );
+        assert_ne!(
+            shape_all(&config, 11, &frames),
+            shape_all(&config, 12, &frames)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_response_fails_checksum() {
+        let server = MockServer::start().await.unwrap();
+        let faults = FaultInjector::new(5);
+        let mut connection = connect(&server, &faults).await;
+
+        faults.set_node(
+            1,
+            Some(
+                FaultConfig::new()
+                    .with_corrupt_rate(1.0)
+                    .with_direction(FaultDirection::Incoming),
+            ),
+        );
+        let error = ping(&mut connection, 1000).await.unwrap_err();
+        assert!(matches!(error, DatabaseError::ChecksumMismatch { .. }));
+        assert!(faults.counts().corrupted >= 1);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_frames_time_out() {
+        let server = MockServer::start().await.unwrap();
+        let faults = FaultInjector::new(5);
+        let mut connection = connect(&server, &faults).await;
+
+        faults.set_node(1, Some(FaultConfig::new().with_drop_rate(1.0)));
+        let error = ping(&mut connection, 100).await.unwrap_err();
+        assert!(matches!(error, DatabaseError::TimeoutError { .. }));
+
+        // Healing the network restores the existing connection
+        faults.set_node(1, None);
+        let response = ping(&mut connection, 1000).await.unwrap();
+        assert_eq!(response.message_type, MessageType::Pong);
+    }
+
+    #[tokio::test]
+    async fn test_reset_fails_with_network_error() {
+        let server = MockServer::start().await.unwrap();
+        let faults = FaultInjector::new(5);
+        let mut connection = connect(&server, &faults).await;
+
+        faults.set_node(1, Some(FaultConfig::new().with_reset_rate(1.0)));
+        let error = ping(&mut connection, 1000).await.unwrap_err();
+        assert!(matches!(error, DatabaseError::NetworkError { .. }));
+        assert!(error.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_stalled_read_times_out() {
+        let server = MockServer::start().await.unwrap();
+        let faults = FaultInjector::new(5);
+        let mut connection = connect(&server, &faults).await;
+
+        faults.set_node(1, Some(FaultConfig::new().with_stall(1.0, 500)));
+        let error = ping(&mut connection, 50).await.unwrap_err();
+        assert!(matches!(error, DatabaseError::TimeoutError { .. }));
+        assert_eq!(faults.counts().stalls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_latency_delays_requests() {
+        let server = MockServer::start().await.unwrap();
+        let faults = FaultInjector::new(5).with_node(1, FaultConfig::new().with_latency(30));
+        let mut connection = connect(&server, &faults).await;
+
+        let started = std::time::Instant::now();
+        ping(&mut connection, 1000).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(30));
+    }
+}
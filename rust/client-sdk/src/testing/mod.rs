@@ -0,0 +1,14 @@
+//! Testing support for applications built on the SDK
+//!
+//! Enabled with the `testing` feature. `MockServer` runs an in-process server
+//! that speaks the client wire protocol, so data-access code can be tested in
+//! CI without a cluster, and `FaultInjector` wraps connection transports to
+//! inject network faults for resilience tests.
+
+mod fault;
+mod server;
+mod store;
+
+pub use fault::{FaultConfig, FaultCounts, FaultDirection, FaultInjector};
+pub use server::MockServer;
+pub use store::{Outcome, Table, TableStore};
@@ -0,0 +1,1098 @@
+//! Mock database server speaking the client wire protocol
+
+use super::store::{Outcome, TableStore};
+use crate::auth::{AuthRequest, AuthResponse, AuthToken};
+use crate::error::{sqlstate, DatabaseError, ServerError};
+use crate::integrity::FrameAuthenticator;
+use crate::protocol::{
+    BatchOperation, Envelope, Message, MessageCodec, MessageType, Request, Response, WireFormat,
+};
+use crate::scram::{ScramCredentials, ScramServer, MIN_ITERATIONS};
+use crate::secret::SecretString;
+use crate::token::{TokenClaims, TokenSigner, TokenVerifier, KEY_LENGTH};
+use crate::transaction::{TransactionRequest, TransactionResponse};
+use crate::types::{
+    ConnectionConfig, Feature, FeatureNegotiation, IntegrityConfig, Role, StatementId, StreamId,
+    TransactionId, UserId, Value,
+};
+use crate::Result;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::{JoinHandle, JoinSet};
+
+/// Ed25519 secret key the server signs tokens with
+const SIGNING_KEY: [u8; KEY_LENGTH] = [0x5a; KEY_LENGTH];
+
+/// Matches requests an expectation applies to
+type Matcher = Box<dyn Fn(&Request) -> bool + Send>;
+
+/// A scripted response
+struct Expectation {
+    matcher: Matcher,
+    response: Response,
+}
+
+/// A user accepted by the mock server
+struct MockUser {
+    user_id: UserId,
+    password: String,
+    /// Verifier checked by SCRAM-SHA-256 logins, derived on first use
+    scram: Option<ScramCredentials>,
+    roles: Vec<Role>,
+}
+
+/// A token issued by the mock server
+struct Session {
+    username: String,
+    token: AuthToken,
+    /// Lifetime granted at login, reused on refresh
+    ttl_secs: u64,
+}
+
+/// A SCRAM-SHA-256 login waiting for the client-final message
+struct PendingScram {
+    server: ScramServer,
+    username: String,
+    user_id: UserId,
+    roles: Vec<Role>,
+    ttl_secs: u64,
+}
+
+/// State of a single connection to a mock server
+#[derive(Default)]
+struct ConnectionState {
+    /// SCRAM-SHA-256 login in progress
+    scram: Option<PendingScram>,
+    /// Client nonce followed by the server nonce, once negotiated
+    nonce: Vec<u8>,
+    /// Verifies and signs frames once a session is bound
+    authenticator: Option<FrameAuthenticator>,
+}
+
+/// State shared by all connections to a mock server
+struct MockState {
+    /// Features advertised during negotiation
+    features: Vec<Feature>,
+    /// Whether feature negotiation is answered, as servers predating it do not
+    answer_negotiation: bool,
+    /// Scripted responses, checked in registration order
+    expectations: Vec<Expectation>,
+    /// Committed tables
+    store: TableStore,
+    /// Working copies of the tables for open transactions
+    transactions: HashMap<TransactionId, TableStore>,
+    /// Rows not yet fetched from open result streams
+    streams: HashMap<StreamId, VecDeque<Vec<Value>>>,
+    /// SQL of prepared statements
+    statements: HashMap<StatementId, String>,
+    /// Next stream or statement id
+    next_id: u64,
+    /// Users by name
+    users: HashMap<String, MockUser>,
+    /// Tokens that have been issued and not revoked, by signature
+    sessions: HashMap<Vec<u8>, Session>,
+    /// Number of tokens issued, used as the unique token id
+    tokens_issued: u64,
+    /// Signs issued tokens
+    signer: TokenSigner,
+    /// Every message received, in order
+    received: Vec<Message>,
+}
+
+/// In-process database server for testing code that uses the SDK
+///
+/// The server listens on a local port and speaks the real `MessageCodec`
+/// protocol, including feature negotiation, versioned framing and
+/// fragmentation. Data requests are answered from scripted expectations when
+/// one matches, and otherwise run against an in-memory `TableStore`.
+/// Transactions work on a copy of the tables that replaces them on commit.
+/// Logins are checked against the server's users, initially only the `mock`
+/// user with password `mock` and the `Admin` role, with SCRAM-SHA-256 or a
+/// plaintext password, and data requests carrying an unknown, expired or
+/// revoked token are rejected. Admin requests must carry a token whose user
+/// has the permission the request needs. Once a client binds a session to a
+/// connection, every frame on it must carry a valid authentication tag.
+/// Every received message is recorded for later assertions.
+///
+/// The server shuts down when dropped.
+///
+/// # Example
+///
+/// ```ignore
+/// let server = MockServer::start().await?;
+/// server.store(|store| {
+///     store.execute("CREATE TABLE users (id INT, name TEXT)", &[])
+/// })?;
+///
+/// let client = Client::connect(server.config()).await?;
+/// client.data().execute("INSERT INTO users VALUES (1, 'alice')").await?;
+/// assert_eq!(server.requests().len(), 1);
+/// ```
+pub struct MockServer {
+    /// Address the server listens on
+    address: SocketAddr,
+    /// Shared state
+    state: Arc<Mutex<MockState>>,
+    /// Accept loop, which owns the connection tasks
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Name of the user accepted by default
+    pub const USERNAME: &'static str = "mock";
+
+    /// Password of the user accepted by default
+    pub const PASSWORD: &'static str = "mock";
+
+    /// Issuer of the tokens signed by the server
+    pub const TOKEN_ISSUER: &'static str = "q-distributed-db-mock";
+
+    /// Features advertised by default
+    pub const DEFAULT_FEATURES: [Feature; 9] = [
+        Feature::Compression,
+        Feature::Heartbeat,
+        Feature::Streaming,
+        Feature::ZstdCompression,
+        Feature::SnappyCompression,
+        Feature::VersionedFraming,
+        Feature::FragmentedFrames,
+        Feature::ScramSha256,
+        Feature::MessageAuthentication,
+    ];
+
+    /// Starts a server on a free local port
+    pub async fn start() -> Result<Self> {
+        Self::start_with_features(Self::DEFAULT_FEATURES.to_vec()).await
+    }
+
+    /// Starts a server advertising the given features
+    pub async fn start_with_features(features: Vec<Feature>) -> Result<Self> {
+        let listener =
+            TcpListener::bind("127.0.0.1:0")
+                .await
+                .map_err(|e| DatabaseError::NetworkError {
+                    details: format!("Failed to bind mock server: {}", e),
+                })?;
+        let address = listener
+            .local_addr()
+            .map_err(|e| DatabaseError::NetworkError {
+                details: format!("Failed to read mock server address: {}", e),
+            })?;
+
+        let state = Arc::new(Mutex::new(MockState {
+            features,
+            answer_negotiation: true,
+            expectations: Vec::new(),
+            store: TableStore::new(),
+            transactions: HashMap::new(),
+            streams: HashMap::new(),
+            statements: HashMap::new(),
+            next_id: 1,
+            users: HashMap::new(),
+            sessions: HashMap::new(),
+            tokens_issued: 0,
+            signer: TokenSigner::new(&SIGNING_KEY, Self::TOKEN_ISSUER),
+            received: Vec::new(),
+        }));
+        lock(&state).add_user(Self::USERNAME, Self::PASSWORD, vec![Role::Admin]);
+
+        let task = tokio::spawn(accept(listener, Arc::clone(&state)));
+
+        Ok(Self {
+            address,
+            state,
+            task,
+        })
+    }
+
+    /// Stops answering feature negotiation, like a server predating it
+    ///
+    /// Clients then fall back to legacy framing once negotiation times out.
+    pub fn ignore_feature_negotiation(&self) {
+        self.lock().answer_negotiation = false;
+    }
+
+    /// Returns the `host:port` address of the server
+    pub fn address(&self) -> String {
+        self.address.to_string()
+    }
+
+    /// Returns a client configuration pointing at the server
+    pub fn config(&self) -> ConnectionConfig {
+        ConnectionConfig::new(vec![self.address()]).with_credentials(Self::USERNAME, Self::PASSWORD)
+    }
+
+    /// Adds a user that can log in, replacing any user with the same name, and
+    /// returns its id
+    pub fn add_user(&self, username: &str, password: &str, roles: Vec<Role>) -> UserId {
+        self.lock().add_user(username, password, roles)
+    }
+
+    /// Returns a verifier for the tokens issued by the server
+    pub fn token_verifier(&self) -> TokenVerifier {
+        self.lock().signer.verifier()
+    }
+
+    /// Returns the tokens that have been issued and not yet revoked
+    pub fn active_tokens(&self) -> Vec<AuthToken> {
+        self.lock()
+            .sessions
+            .values()
+            .map(|session| session.token.clone())
+            .collect()
+    }
+
+    /// Revokes every issued token, as an administrator or a server restart
+    /// would
+    ///
+    /// Data requests carrying a revoked token are rejected.
+    pub fn revoke_tokens(&self) {
+        self.lock().sessions.clear();
+    }
+
+    /// Forgets every prepared statement, as a server under memory pressure or
+    /// a restart would
+    ///
+    /// Requests naming a forgotten statement fail with
+    /// `sqlstate::INVALID_SQL_STATEMENT_NAME`.
+    pub fn forget_statements(&self) {
+        self.lock().statements.clear();
+    }
+
+    /// Answers data requests whose SQL equals `sql` with `response`
+    ///
+    /// Whitespace differences are ignored.
+    pub fn on_sql(&self, sql: &str, response: Response) {
+        let expected = normalize(sql);
+        self.on_request(
+            move |request| request.sql().is_some_and(|sql| normalize(sql) == expected),
+            response,
+        );
+    }
+
+    /// Answers data requests accepted by `matcher` with `response`
+    ///
+    /// Expectations are checked in registration order and take precedence
+    /// over the table store. They are not consumed.
+    pub fn on_request(
+        &self,
+        matcher: impl Fn(&Request) -> bool + Send + 'static,
+        response: Response,
+    ) {
+        self.lock().expectations.push(Expectation {
+            matcher: Box::new(matcher),
+            response,
+        });
+    }
+
+    /// Gives access to the committed tables, e.g. to seed or inspect them
+    pub fn store<T>(&self, f: impl FnOnce(&mut TableStore) -> T) -> T {
+        f(&mut self.lock().store)
+    }
+
+    /// Returns every message received so far
+    pub fn received(&self) -> Vec<Message> {
+        self.lock().received.clone()
+    }
+
+    /// Returns the data requests received so far
+    pub fn requests(&self) -> Vec<Request> {
+        self.decoded(MessageType::Data, |payload| {
+            Request::from_payload(payload).ok()
+        })
+    }
+
+    /// Returns the transaction requests received so far
+    pub fn transaction_requests(&self) -> Vec<TransactionRequest> {
+        self.decoded(MessageType::Transaction, |payload| {
+            bincode::deserialize(payload).ok()
+        })
+    }
+
+    /// Returns the authentication requests received so far
+    pub fn auth_requests(&self) -> Vec<AuthRequest> {
+        self.decoded(MessageType::Auth, |payload| {
+            AuthRequest::from_payload(payload).ok()
+        })
+    }
+
+    /// Forgets the messages received so far
+    pub fn clear_received(&self) {
+        self.lock().received.clear();
+    }
+
+    fn decoded<T>(&self, message_type: MessageType, decode: impl Fn(&[u8]) -> Option<T>) -> Vec<T> {
+        self.lock()
+            .received
+            .iter()
+            .filter(|m| m.message_type == message_type)
+            .filter_map(|m| decode(&m.payload))
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        lock(&self.state)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn lock(state: &Mutex<MockState>) -> MutexGuard<'_, MockState> {
+    // A panicking test must not hide the state from other assertions
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn password_failed(username: &str) -> ServerError {
+    ServerError::new(
+        sqlstate::INVALID_PASSWORD,
+        format!("password authentication failed for user \"{}\"", username),
+    )
+}
+
+fn normalize(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Accepts connections until the server is dropped
+async fn accept(listener: TcpListener, state: Arc<Mutex<MockState>>) {
+    let mut connections = JoinSet::new();
+    while let Ok((socket, _)) = listener.accept().await {
+        connections.spawn(serve(socket, Arc::clone(&state)));
+    }
+}
+
+/// Answers messages on one connection until it is closed
+async fn serve(mut socket: TcpStream, state: Arc<Mutex<MockState>>) {
+    let _ = socket.set_nodelay(true);
+    let mut codec = MessageCodec::new();
+    let mut connection = ConnectionState::default();
+
+    while let Ok(envelope) = codec.read_envelope(&mut socket).await {
+        let message = &envelope.message;
+        let (reply, negotiated) = {
+            let mut state = lock(&state);
+            state.received.push(message.clone());
+            match state.verify(&envelope, &mut connection) {
+                Ok(()) => state.handle(message, &mut connection),
+                Err(error) => (Some(error_reply(message, &error)), None),
+            }
+        };
+
+        if let Some(reply) = reply {
+            let written = match &connection.authenticator {
+                Some(authenticator) => match authenticator.sign(reply) {
+                    Ok(envelope) => codec.write_envelope(&mut socket, &envelope).await,
+                    Err(e) => Err(e),
+                },
+                None => codec.write_message(&mut socket, &reply).await,
+            };
+            if written.is_err() {
+                return;
+            }
+        }
+
+        // Switch formats only after the negotiation reply went out in the old one
+        if let Some(features) = negotiated {
+            if features.contains(&Feature::VersionedFraming) {
+                codec.set_wire_format(WireFormat::Versioned);
+            }
+            codec.set_fragmentation_enabled(features.contains(&Feature::FragmentedFrames));
+        }
+    }
+}
+
+/// Builds an error frame answering `message`
+fn error_reply(message: &Message, error: &ServerError) -> Message {
+    Message::new(
+        message.recipient,
+        message.sender,
+        message.sequence_number,
+        chrono::Utc::now().timestamp_millis(),
+        MessageType::Error,
+        bincode::serialize(error).unwrap_or_default(),
+    )
+}
+
+impl MockState {
+    /// Checks the authentication tag of a frame
+    ///
+    /// A session bind is verified with keys of the session it names, which
+    /// then replace the connection's keys. Other frames are verified once a
+    /// session is bound, and must not carry a tag before.
+    fn verify(
+        &self,
+        envelope: &Envelope,
+        connection: &mut ConnectionState,
+    ) -> std::result::Result<(), ServerError> {
+        let rejected = |e: DatabaseError| {
+            ServerError::new(sqlstate::INVALID_AUTHORIZATION_SPECIFICATION, e.to_string())
+        };
+
+        let message = &envelope.message;
+        let bind = match message.message_type {
+            MessageType::Auth => match AuthRequest::from_payload(&message.payload) {
+                Ok(AuthRequest::Bind { signature }) => Some(signature),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some(signature) = bind {
+            let session_key = self
+                .sessions
+                .get(signature.expose())
+                .filter(|session| !session.token.is_expired())
+                .and_then(|session| session.token.session_key.clone())
+                .ok_or_else(invalid_token)?;
+            let mut authenticator = FrameAuthenticator::server(
+                session_key.expose(),
+                &connection.nonce,
+                &IntegrityConfig::new(),
+            );
+            authenticator.verify(envelope).map_err(rejected)?;
+            connection.authenticator = Some(authenticator);
+            return Ok(());
+        }
+
+        match &mut connection.authenticator {
+            Some(authenticator) => authenticator.verify(envelope).map_err(rejected),
+            None if envelope
+                .extension(crate::integrity::MAC_EXTENSION)
+                .is_some() =>
+            {
+                Err(rejected(DatabaseError::MessageAuthenticationFailed {
+                    reason: "no session bound to the connection".to_string(),
+                }))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Builds the reply to a message, and the negotiated features if the
+    /// message was a feature negotiation
+    fn handle(
+        &mut self,
+        message: &Message,
+        connection: &mut ConnectionState,
+    ) -> (Option<Message>, Option<Vec<Feature>>) {
+        let reply = |message_type, payload| {
+            Some(Message::new(
+                message.recipient,
+                message.sender,
+                message.sequence_number,
+                chrono::Utc::now().timestamp_millis(),
+                message_type,
+                payload,
+            ))
+        };
+
+        match message.message_type {
+            MessageType::FeatureNegotiation if !self.answer_negotiation => (None, None),
+            MessageType::FeatureNegotiation => {
+                let requested = match FeatureNegotiation::from_payload(&message.payload) {
+                    Ok(requested) => requested,
+                    Err(e) => return (reply(MessageType::Error, error_payload(e)), None),
+                };
+                let negotiated = requested
+                    .supported_features
+                    .into_iter()
+                    .filter(|f| self.features.contains(f))
+                    .collect();
+                let nonce = match crate::integrity::connection_nonce() {
+                    Ok(nonce) => nonce,
+                    Err(e) => return (reply(MessageType::Error, error_payload(e)), None),
+                };
+                connection.nonce = [requested.nonce, nonce.clone()].concat();
+                let payload = FeatureNegotiation {
+                    supported_features: self.features.clone(),
+                    nonce,
+                }
+                .to_payload()
+                .unwrap_or_default();
+                (
+                    reply(MessageType::FeatureNegotiation, payload),
+                    Some(negotiated),
+                )
+            }
+            MessageType::Ping => (reply(MessageType::Pong, Vec::new()), None),
+            MessageType::Data => {
+                let response = match Request::from_payload(&message.payload) {
+                    Ok(request) => self.handle_request(&request),
+                    Err(e) => Response::ServerError(ServerError::unclassified(e.to_string())),
+                };
+                (
+                    reply(MessageType::Data, response.to_payload().unwrap_or_default()),
+                    None,
+                )
+            }
+            MessageType::Transaction => {
+                let response = match bincode::deserialize(&message.payload) {
+                    Ok(request) => self.handle_transaction(request),
+                    Err(e) => TransactionResponse::Error {
+                        message: e.to_string(),
+                    },
+                };
+                (
+                    reply(
+                        MessageType::Transaction,
+                        bincode::serialize(&response).unwrap_or_default(),
+                    ),
+                    None,
+                )
+            }
+            MessageType::Auth => {
+                let reply = match AuthRequest::from_payload(&message.payload)
+                    .map_err(|e| ServerError::unclassified(e.to_string()))
+                    .and_then(|request| self.handle_auth(request, connection))
+                {
+                    Ok(response) => {
+                        reply(MessageType::Auth, response.to_payload().unwrap_or_default())
+                    }
+                    Err(error) => reply(
+                        MessageType::Error,
+                        bincode::serialize(&error).unwrap_or_default(),
+                    ),
+                };
+                (reply, None)
+            }
+            _ => (None, None),
+        }
+    }
+
+    fn add_user(&mut self, username: &str, password: &str, roles: Vec<Role>) -> UserId {
+        let user_id = self.users.len() as UserId + 1;
+        self.users.insert(
+            username.to_string(),
+            MockUser {
+                user_id,
+                password: password.to_string(),
+                scram: None,
+                roles,
+            },
+        );
+        user_id
+    }
+
+    fn handle_auth(
+        &mut self,
+        request: AuthRequest,
+        connection: &mut ConnectionState,
+    ) -> std::result::Result<AuthResponse, ServerError> {
+        match request {
+            AuthRequest::Login {
+                username,
+                password,
+                token_ttl_secs,
+                ..
+            } => {
+                let user = self
+                    .users
+                    .get(&username)
+                    .filter(|user| {
+                        password.as_ref().map(SecretString::expose) == Some(&user.password)
+                    })
+                    .ok_or_else(|| password_failed(&username))?;
+                let (user_id, roles) = (user.user_id, user.roles.clone());
+                Ok(AuthResponse::Authenticated(self.issue(
+                    &username,
+                    user_id,
+                    roles,
+                    token_ttl_secs,
+                )?))
+            }
+            AuthRequest::Refresh { signature } => {
+                let session = self.revoke(signature.expose())?;
+                Ok(AuthResponse::Authenticated(self.issue(
+                    &session.username,
+                    session.token.user_id,
+                    session.token.roles,
+                    session.ttl_secs,
+                )?))
+            }
+            AuthRequest::Logout { signature } => {
+                self.revoke(signature.expose())?;
+                Ok(AuthResponse::LoggedOut)
+            }
+            // The frame was verified with the session's keys in `verify`
+            AuthRequest::Bind { .. } => Ok(AuthResponse::Bound),
+            AuthRequest::ScramClientFirst {
+                message,
+                token_ttl_secs,
+            } => {
+                let username = ScramServer::username(&message)
+                    .map_err(|e| ServerError::unclassified(e.to_string()))?;
+                let user = self
+                    .users
+                    .get_mut(&username)
+                    .ok_or_else(|| password_failed(&username))?;
+                let credentials = user.scram.get_or_insert_with(|| {
+                    let salt = Sha256::digest(username.as_bytes());
+                    ScramCredentials::new(&user.password, &salt[..16], MIN_ITERATIONS)
+                });
+                let server = ScramServer::new(&message, credentials.clone())
+                    .map_err(|e| ServerError::unclassified(e.to_string()))?;
+                let response = AuthResponse::ScramServerFirst {
+                    message: server.server_first().to_string(),
+                };
+                connection.scram = Some(PendingScram {
+                    server,
+                    username,
+                    user_id: user.user_id,
+                    roles: user.roles.clone(),
+                    ttl_secs: token_ttl_secs,
+                });
+                Ok(response)
+            }
+            AuthRequest::ScramClientFinal { message } => {
+                let pending = connection.scram.take().ok_or_else(|| {
+                    ServerError::new(
+                        sqlstate::INVALID_AUTHORIZATION_SPECIFICATION,
+                        "no SCRAM-SHA-256 exchange in progress",
+                    )
+                })?;
+                let server_final = pending
+                    .server
+                    .finish(&message)
+                    .map_err(|e| ServerError::new(sqlstate::INVALID_PASSWORD, e.to_string()))?;
+                Ok(AuthResponse::ScramServerFinal {
+                    message: server_final,
+                    token: self.issue(
+                        &pending.username,
+                        pending.user_id,
+                        pending.roles,
+                        pending.ttl_secs,
+                    )?,
+                })
+            }
+        }
+    }
+
+    /// Issues a signed token with a unique token id
+    fn issue(
+        &mut self,
+        username: &str,
+        user_id: UserId,
+        roles: Vec<Role>,
+        ttl_secs: u64,
+    ) -> std::result::Result<AuthToken, ServerError> {
+        self.tokens_issued += 1;
+        let issued_at = Utc::now();
+        let expiration = issued_at + chrono::Duration::seconds(ttl_secs as i64);
+        let claims = TokenClaims {
+            issuer: self.signer.issuer().to_string(),
+            username: username.to_string(),
+            user_id,
+            permissions: Role::union(&roles),
+            roles: roles.clone(),
+            audience: None,
+            issued_at: issued_at.timestamp(),
+            expires_at: expiration.timestamp(),
+            token_id: Some(self.tokens_issued.to_string()),
+        };
+        let signature = self
+            .signer
+            .sign(&claims)
+            .map_err(|e| ServerError::unclassified(e.to_string()))?
+            .into_bytes();
+        let session_key = Sha256::digest([b"session".as_slice(), &signature].concat()).to_vec();
+
+        let token = AuthToken::new(user_id, roles, expiration, signature.clone())
+            .with_session_key(session_key);
+        self.sessions.insert(
+            signature,
+            Session {
+                username: username.to_string(),
+                token: token.clone(),
+                ttl_secs,
+            },
+        );
+        Ok(token)
+    }
+
+    /// Removes an unexpired token
+    fn revoke(&mut self, signature: &[u8]) -> std::result::Result<Session, ServerError> {
+        self.sessions
+            .remove(signature)
+            .filter(|session| !session.token.is_expired())
+            .ok_or_else(invalid_token)
+    }
+
+    fn handle_request(&mut self, request: &Request) -> Response {
+        // Data requests without a token are accepted, for tests that build
+        // them by hand; admin requests always run as the token's user
+        let session = match request.auth_token() {
+            Some(signature) => match self.sessions.get(signature) {
+                Some(session) if !session.token.is_expired() => Some(session),
+                _ => return Response::ServerError(invalid_token()),
+            },
+            None => None,
+        };
+        if let Some(admin) = request.admin_request() {
+            let Some(session) = session else {
+                return Response::ServerError(ServerError::new(
+                    sqlstate::INVALID_AUTHORIZATION_SPECIFICATION,
+                    "admin request without a token",
+                ));
+            };
+            let required = admin.required_permission();
+            if !session.token.permissions().contains(&required) {
+                return Response::ServerError(
+                    ServerError::new(
+                        sqlstate::INSUFFICIENT_PRIVILEGE,
+                        format!("permission denied for user \"{}\"", session.username),
+                    )
+                    .with_detail("permission", format!("{:?}", required)),
+                );
+            }
+        }
+
+        if let Some(expectation) = self.expectations.iter().find(|e| (e.matcher)(request)) {
+            return expectation.response.clone();
+        }
+
+        self.run(request).unwrap_or_else(Response::ServerError)
+    }
+
+    fn run(&mut self, request: &Request) -> std::result::Result<Response, ServerError> {
+        match request {
+            Request::Admin(request) | Request::AuthenticatedAdmin { request, .. } => {
+                Err(ServerError::unclassified(format!(
+                    "No expectation for admin request {:?}",
+                    request
+                )))
+            }
+            Request::Execute {
+                sql,
+                params,
+                prepared_statement_id,
+                transaction_id,
+                ..
+            } => {
+                let sql = self.statement_sql(sql, *prepared_statement_id)?;
+                match self.tables(*transaction_id)?.execute(&sql, params)? {
+                    Outcome::Executed(result) => Ok(Response::Executed {
+                        rows_affected: result.rows_affected,
+                        last_insert_id: result.last_insert_id,
+                    }),
+                    Outcome::Rows { rows, .. } => Ok(Response::Executed {
+                        rows_affected: rows.len() as u64,
+                        last_insert_id: None,
+                    }),
+                }
+            }
+            Request::Query {
+                sql,
+                params,
+                prepared_statement_id,
+                transaction_id,
+                streaming,
+                ..
+            } => {
+                let sql = self.statement_sql(sql, *prepared_statement_id)?;
+                let (columns, rows) = match self.tables(*transaction_id)?.execute(&sql, params)? {
+                    Outcome::Rows { columns, rows } => (columns, rows),
+                    Outcome::Executed(_) => (Vec::new(), Vec::new()),
+                };
+
+                if *streaming {
+                    let stream_id = self.next_id();
+                    self.streams.insert(stream_id, rows.into());
+                    Ok(Response::StreamOpened { stream_id, columns })
+                } else {
+                    Ok(Response::Rows { columns, rows })
+                }
+            }
+            Request::Prepare { sql, .. } => {
+                let statement_id = self.next_id();
+                let param_count = sql.matches('?').count();
+                self.statements.insert(statement_id, sql.clone());
+                Ok(Response::Prepared {
+                    statement_id,
+                    param_count,
+                })
+            }
+            Request::Batch { operations, .. } => {
+                // Run against a copy so a failing operation leaves no changes
+                let mut store = self.store.clone();
+                let results = operations
+                    .iter()
+                    .map(|operation| {
+                        let BatchOperation::Execute { sql, params } = operation;
+                        match store.execute(sql, params)? {
+                            Outcome::Executed(result) => Ok(result),
+                            Outcome::Rows { rows, .. } => Ok(crate::data_client::ExecuteResult {
+                                rows_affected: rows.len() as u64,
+                                last_insert_id: None,
+                            }),
+                        }
+                    })
+                    .collect::<std::result::Result<Vec<_>, ServerError>>()?;
+                self.store = store;
+                Ok(Response::BatchExecuted(results))
+            }
+            Request::Fetch {
+                stream_id,
+                max_rows,
+            } => {
+                let stream = self
+                    .streams
+                    .get_mut(stream_id)
+                    .ok_or_else(|| unknown("stream", *stream_id))?;
+                let count = (*max_rows as usize).min(stream.len());
+                let rows: Vec<_> = stream.drain(..count).collect();
+                let done = stream.is_empty();
+                if done {
+                    self.streams.remove(stream_id);
+                }
+                Ok(Response::StreamRows { rows, done })
+            }
+            Request::Cancel { stream_id } => {
+                self.streams.remove(stream_id);
+                Ok(Response::Cancelled)
+            }
+            Request::Close { statement_id } => {
+                self.statements.remove(statement_id);
+                Ok(Response::Closed)
+            }
+        }
+    }
+
+    fn handle_transaction(&mut self, request: TransactionRequest) -> TransactionResponse {
+        match request {
+            TransactionRequest::Begin { transaction_id, .. } => {
+                self.transactions.insert(transaction_id, self.store.clone());
+                TransactionResponse::BeginSuccess
+            }
+            TransactionRequest::Commit { transaction_id } => {
+                match self.transactions.remove(&transaction_id) {
+                    Some(store) => {
+                        self.store = store;
+                        TransactionResponse::CommitSuccess
+                    }
+                    None => TransactionResponse::Error {
+                        message: format!("Unknown transaction {}", transaction_id),
+                    },
+                }
+            }
+            TransactionRequest::Rollback { transaction_id } => {
+                self.transactions.remove(&transaction_id);
+                TransactionResponse::RollbackSuccess
+            }
+        }
+    }
+
+    /// Returns the tables a request runs against
+    fn tables(
+        &mut self,
+        transaction_id: Option<TransactionId>,
+    ) -> std::result::Result<&mut TableStore, ServerError> {
+        match transaction_id {
+            Some(id) => self
+                .transactions
+                .get_mut(&id)
+                .ok_or_else(|| unknown("transaction", id)),
+            None => Ok(&mut self.store),
+        }
+    }
+
+    /// Returns the SQL to run, resolving prepared statements
+    fn statement_sql(
+        &self,
+        sql: &str,
+        statement_id: Option<StatementId>,
+    ) -> std::result::Result<String, ServerError> {
+        match statement_id {
+            Some(id) => self.statements.get(&id).cloned().ok_or_else(|| {
+                ServerError::new(
+                    sqlstate::INVALID_SQL_STATEMENT_NAME,
+                    format!("Unknown prepared statement {}", id),
+                )
+            }),
+            None => Ok(sql.to_string()),
+        }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+}
+
+fn invalid_token() -> ServerError {
+    ServerError::new(
+        sqlstate::INVALID_AUTHORIZATION_SPECIFICATION,
+        "invalid or expired token",
+    )
+}
+
+fn unknown(kind: &str, id: u64) -> ServerError {
+    ServerError::unclassified(format!("Unknown {} {}", kind, id))
+}
+
+fn error_payload(error: impl std::fmt::Display) -> Vec<u8> {
+    bincode::serialize(&ServerError::unclassified(error.to_string())).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::Connection;
+    use crate::result::DataType;
+
+    async fn connect(server: &MockServer) -> Connection {
+        Connection::connect_with_config(&server.address(), 1, &server.config())
+            .await
+            .unwrap()
+    }
+
+    fn query(sql: &str, streaming: bool) -> Request {
+        Request::Query {
+            sql: sql.to_string(),
+            params: vec![],
+            prepared_statement_id: None,
+            transaction_id: None,
+            auth_token: None,
+            streaming,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_negotiates_versioned_framing() {
+        let server = MockServer::start().await.unwrap();
+        let connection = connect(&server).await;
+        assert!(connection.has_feature(&Feature::VersionedFraming));
+        assert_eq!(
+            server.received()[0].message_type,
+            MessageType::FeatureNegotiation
+        );
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_legacy_framing_without_negotiation() {
+        let server = MockServer::start().await.unwrap();
+        server.ignore_feature_negotiation();
+        let config = server.config().with_timeout(200);
+
+        let mut connection = Connection::connect_with_config(&server.address(), 1, &config)
+            .await
+            .unwrap();
+        assert!(connection.negotiated_features().is_empty());
+        assert!(connection.is_legacy());
+
+        // The reconnected socket does not negotiate again
+        let negotiations = server
+            .received()
+            .iter()
+            .filter(|m| m.message_type == MessageType::FeatureNegotiation)
+            .count();
+        assert_eq!(negotiations, 1);
+
+        let pong = connection
+            .send_request(MessageType::Ping, Vec::new(), 1000)
+            .await
+            .unwrap();
+        assert_eq!(pong.message_type, MessageType::Pong);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_response() {
+        let server = MockServer::start().await.unwrap();
+        server.on_sql(
+            "SELECT  1",
+            Response::Executed {
+                rows_affected: 7,
+                last_insert_id: None,
+            },
+        );
+
+        let mut connection = connect(&server).await;
+        let response = connection
+            .send_data_request(&query("SELECT 1", false), 1000)
+            .await
+            .unwrap();
+        assert!(matches!(
+            response,
+            Response::Executed {
+                rows_affected: 7,
+                ..
+            }
+        ));
+        assert_eq!(server.requests()[0].sql(), Some("SELECT 1"));
+    }
+
+    #[tokio::test]
+    async fn test_streams_rows_from_store() {
+        let server = MockServer::start().await.unwrap();
+        server.store(|store| {
+            store.create_table("numbers", &[("n", DataType::Int)]);
+            for n in 0..5 {
+                store.insert("numbers", vec![Value::Int(n)]).unwrap();
+            }
+        });
+
+        let mut connection = connect(&server).await;
+        let Response::StreamOpened { stream_id, .. } = connection
+            .send_data_request(&query("SELECT * FROM numbers", true), 1000)
+            .await
+            .unwrap()
+        else {
+            panic!("expected stream");
+        };
+
+        let fetch = Request::Fetch {
+            stream_id,
+            max_rows: 3,
+        };
+        let first = connection.send_data_request(&fetch, 1000).await.unwrap();
+        assert!(matches!(first, Response::StreamRows { ref rows, done: false } if rows.len() == 3));
+        let second = connection.send_data_request(&fetch, 1000).await.unwrap();
+        assert!(matches!(second, Response::StreamRows { ref rows, done: true } if rows.len() == 2));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_admin_requests_without_a_valid_token() {
+        let server = MockServer::start().await.unwrap();
+        server.on_request(
+            |request| request.admin_request().is_some(),
+            Response::Admin(crate::protocol::AdminResponse::UserList(vec![])),
+        );
+        let mut connection = connect(&server).await;
+
+        for auth_token in [
+            None,
+            Some(crate::secret::SecretBytes::new(b"forged".to_vec())),
+        ] {
+            let request = Request::AuthenticatedAdmin {
+                request: crate::protocol::AdminRequest::ListUsers,
+                auth_token,
+            };
+            let error = connection
+                .send_data_request(&request, 1000)
+                .await
+                .unwrap_err();
+            assert!(error.is_auth_error(), "{:?}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_errors_map_to_typed_errors() {
+        let server = MockServer::start().await.unwrap();
+        let mut connection = connect(&server).await;
+        let result = connection
+            .send_data_request(&query("SELECT * FROM missing", false), 1000)
+            .await;
+        assert!(matches!(
+            result,
+            Err(DatabaseError::TableNotFound { ref table_name }) if table_name == "missing"
+        ));
+    }
+}
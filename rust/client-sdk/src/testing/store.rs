@@ -0,0 +1,959 @@
+//! In-memory table store backing the mock server
+//!
+//! Understands the small SQL subset produced by `QueryBuilder` and typical
+//! data-access code:
+//!
+//! - `CREATE TABLE [IF NOT EXISTS] t (col TYPE [constraints], ...)`
+//! - `DROP TABLE [IF EXISTS] t`
+//! - `INSERT INTO t [(cols)] VALUES (...), (...)`
+//! - `SELECT * | cols FROM t [WHERE ...] [ORDER BY col [ASC|DESC], ...] [LIMIT n] [OFFSET n]`
+//! - `UPDATE t SET col = value, ... [WHERE ...]`
+//! - `DELETE FROM t [WHERE ...]`
+//!
+//! Conditions combine `col op value`, row-value `(a, b) op (x, y)` and
+//! `col IS [NOT] NULL` predicates with `AND`, `OR` and parentheses. Values
+//! are `?` placeholders or literals. Failures are reported as `ServerError`s
+//! with the SQLSTATE a real server would use.
+
+use crate::data_client::ExecuteResult;
+use crate::error::{sqlstate, ServerError};
+use crate::result::{ColumnMetadata, DataType};
+use crate::types::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// A table held by the store
+#[derive(Debug, Clone)]
+pub struct Table {
+    /// Column definitions
+    pub columns: Vec<ColumnMetadata>,
+    /// Rows, with values in column order
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Result of running a statement against the store
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// A statement that modifies data or schema
+    Executed(ExecuteResult),
+    /// A query result
+    Rows {
+        /// Result columns
+        columns: Vec<ColumnMetadata>,
+        /// Result rows
+        rows: Vec<Vec<Value>>,
+    },
+}
+
+/// A set of named in-memory tables
+#[derive(Debug, Clone, Default)]
+pub struct TableStore {
+    tables: BTreeMap<String, Table>,
+}
+
+impl TableStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a table, replacing any existing table with the same name
+    pub fn create_table(&mut self, name: &str, columns: &[(&str, DataType)]) {
+        let columns = columns
+            .iter()
+            .enumerate()
+            .map(|(ordinal, (name, data_type))| ColumnMetadata {
+                name: name.to_string(),
+                data_type: *data_type,
+                nullable: true,
+                ordinal,
+            })
+            .collect();
+
+        self.tables.insert(
+            name.to_lowercase(),
+            Table {
+                columns,
+                rows: Vec::new(),
+            },
+        );
+    }
+
+    /// Appends a row to a table
+    pub fn insert(&mut self, table: &str, row: Vec<Value>) -> Result<(), ServerError> {
+        let table = self.table_mut(table)?;
+        if row.len() != table.columns.len() {
+            return Err(ServerError::new(
+                sqlstate::SYNTAX_ERROR,
+                format!("Expected {} values, got {}", table.columns.len(), row.len()),
+            ));
+        }
+        table.rows.push(row);
+        Ok(())
+    }
+
+    /// Returns a table by name
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.get(&name.to_lowercase())
+    }
+
+    /// Runs a statement with the given parameters
+    pub fn execute(&mut self, sql: &str, params: &[Value]) -> Result<Outcome, ServerError> {
+        let mut parser = Parser::new(sql, params)?;
+        let outcome = match parser.keyword()?.as_str() {
+            "CREATE" => self.create(&mut parser),
+            "DROP" => self.drop(&mut parser),
+            "INSERT" => self.insert_into(&mut parser),
+            "SELECT" => self.select(&mut parser),
+            "UPDATE" => self.update(&mut parser),
+            "DELETE" => self.delete(&mut parser),
+            other => Err(parser.error(format!("Unsupported statement {}", other))),
+        }?;
+        parser.finish()?;
+        Ok(outcome)
+    }
+
+    fn table_mut(&mut self, name: &str) -> Result<&mut Table, ServerError> {
+        self.tables
+            .get_mut(&name.to_lowercase())
+            .ok_or_else(|| undefined_table(name))
+    }
+
+    fn create(&mut self, parser: &mut Parser) -> Result<Outcome, ServerError> {
+        parser.expect_keyword("TABLE")?;
+        let if_not_exists = parser.accept_keyword("IF");
+        if if_not_exists {
+            parser.expect_keyword("NOT")?;
+            parser.expect_keyword("EXISTS")?;
+        }
+        let name = parser.identifier()?;
+
+        parser.expect_symbol("(")?;
+        let mut columns = Vec::new();
+        loop {
+            let column = parser.identifier()?;
+            let type_name = parser.keyword()?;
+            let data_type = data_type(&type_name)
+                .ok_or_else(|| parser.error(format!("Unknown type {}", type_name)))?;
+
+            // Skip type arguments and column constraints
+            let mut nullable = true;
+            let mut depth = 0;
+            loop {
+                match parser.peek() {
+                    Some(Token::Symbol(",")) | Some(Token::Symbol(")")) if depth == 0 => break,
+                    Some(Token::Symbol("(")) => depth += 1,
+                    Some(Token::Symbol(")")) => depth -= 1,
+                    Some(Token::Word(word))
+                        if word.eq_ignore_ascii_case("NOT")
+                            || word.eq_ignore_ascii_case("PRIMARY") =>
+                    {
+                        nullable = false
+                    }
+                    None => return Err(parser.error("Unterminated column list")),
+                    _ => {}
+                }
+                parser.advance();
+            }
+
+            columns.push(ColumnMetadata {
+                name: column,
+                data_type,
+                nullable,
+                ordinal: columns.len(),
+            });
+            if !parser.accept_symbol(",") {
+                break;
+            }
+        }
+        parser.expect_symbol(")")?;
+
+        let key = name.to_lowercase();
+        if self.tables.contains_key(&key) {
+            if if_not_exists {
+                return Ok(executed(0));
+            }
+            return Err(ServerError::new(
+                sqlstate::SYNTAX_ERROR,
+                format!("Table {} already exists", name),
+            )
+            .with_detail("table", name));
+        }
+
+        self.tables.insert(
+            key,
+            Table {
+                columns,
+                rows: Vec::new(),
+            },
+        );
+        Ok(executed(0))
+    }
+
+    fn drop(&mut self, parser: &mut Parser) -> Result<Outcome, ServerError> {
+        parser.expect_keyword("TABLE")?;
+        let if_exists = parser.accept_keyword("IF");
+        if if_exists {
+            parser.expect_keyword("EXISTS")?;
+        }
+        let name = parser.identifier()?;
+
+        if self.tables.remove(&name.to_lowercase()).is_none() && !if_exists {
+            return Err(undefined_table(&name));
+        }
+        Ok(executed(0))
+    }
+
+    fn insert_into(&mut self, parser: &mut Parser) -> Result<Outcome, ServerError> {
+        parser.expect_keyword("INTO")?;
+        let name = parser.identifier()?;
+        let table = self.table_mut(&name)?;
+
+        let positions = if parser.accept_symbol("(") {
+            let names = parser.identifier_list()?;
+            parser.expect_symbol(")")?;
+            names
+                .iter()
+                .map(|column| column_index(table, column))
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            (0..table.columns.len()).collect()
+        };
+
+        parser.expect_keyword("VALUES")?;
+        let mut rows = Vec::new();
+        loop {
+            parser.expect_symbol("(")?;
+            let mut row = vec![Value::Null; table.columns.len()];
+            for (i, position) in positions.iter().enumerate() {
+                if i > 0 {
+                    parser.expect_symbol(",")?;
+                }
+                row[*position] = parser.value()?;
+            }
+            parser.expect_symbol(")")?;
+            rows.push(row);
+
+            if !parser.accept_symbol(",") {
+                break;
+            }
+        }
+
+        let count = rows.len() as u64;
+        table.rows.extend(rows);
+        Ok(executed(count))
+    }
+
+    fn select(&mut self, parser: &mut Parser) -> Result<Outcome, ServerError> {
+        let names = if parser.accept_symbol("*") {
+            None
+        } else {
+            Some(parser.identifier_list()?)
+        };
+        parser.expect_keyword("FROM")?;
+        let table = self.table_mut(&parser.identifier()?)?;
+
+        let projection = match names {
+            None => (0..table.columns.len()).collect(),
+            Some(names) => names
+                .iter()
+                .map(|column| column_index(table, column))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
+        let condition = parser.where_clause(table)?;
+        let mut rows: Vec<&Vec<Value>> = table
+            .rows
+            .iter()
+            .filter(|row| condition.as_ref().is_none_or(|c| c.matches(row)))
+            .collect();
+
+        if parser.accept_keyword("ORDER") {
+            parser.expect_keyword("BY")?;
+            let mut keys = Vec::new();
+            loop {
+                let column = column_index(table, &parser.identifier()?)?;
+                let descending = if parser.accept_keyword("DESC") {
+                    true
+                } else {
+                    parser.accept_keyword("ASC");
+                    false
+                };
+                keys.push((column, descending));
+                if !parser.accept_symbol(",") {
+                    break;
+                }
+            }
+
+            rows.sort_by(|a, b| {
+                keys.iter()
+                    .map(|&(column, descending)| {
+                        let ordering = sort_order(&a[column], &b[column]);
+                        if descending {
+                            ordering.reverse()
+                        } else {
+                            ordering
+                        }
+                    })
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+
+        let limit = if parser.accept_keyword("LIMIT") {
+            parser.count()?
+        } else {
+            usize::MAX
+        };
+        let offset = if parser.accept_keyword("OFFSET") {
+            parser.count()?
+        } else {
+            0
+        };
+
+        let columns = projection
+            .iter()
+            .enumerate()
+            .map(|(ordinal, &i)| ColumnMetadata {
+                ordinal,
+                ..table.columns[i].clone()
+            })
+            .collect();
+        let rows = rows
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|row| projection.iter().map(|&i| row[i].clone()).collect())
+            .collect();
+
+        Ok(Outcome::Rows { columns, rows })
+    }
+
+    fn update(&mut self, parser: &mut Parser) -> Result<Outcome, ServerError> {
+        let table = self.table_mut(&parser.identifier()?)?;
+        parser.expect_keyword("SET")?;
+
+        let mut assignments = Vec::new();
+        loop {
+            let column = column_index(table, &parser.identifier()?)?;
+            parser.expect_symbol("=")?;
+            assignments.push((column, parser.value()?));
+            if !parser.accept_symbol(",") {
+                break;
+            }
+        }
+
+        let condition = parser.where_clause(table)?;
+        let mut count = 0;
+        for row in &mut table.rows {
+            if condition.as_ref().is_none_or(|c| c.matches(row)) {
+                for (column, value) in &assignments {
+                    row[*column] = value.clone();
+                }
+                count += 1;
+            }
+        }
+        Ok(executed(count))
+    }
+
+    fn delete(&mut self, parser: &mut Parser) -> Result<Outcome, ServerError> {
+        parser.expect_keyword("FROM")?;
+        let table = self.table_mut(&parser.identifier()?)?;
+        let condition = parser.where_clause(table)?;
+
+        let before = table.rows.len();
+        table
+            .rows
+            .retain(|row| !condition.as_ref().is_none_or(|c| c.matches(row)));
+        Ok(executed((before - table.rows.len()) as u64))
+    }
+}
+
+fn executed(rows_affected: u64) -> Outcome {
+    Outcome::Executed(ExecuteResult {
+        rows_affected,
+        last_insert_id: None,
+    })
+}
+
+fn undefined_table(name: &str) -> ServerError {
+    ServerError::new(
+        sqlstate::UNDEFINED_TABLE,
+        format!("Table {} does not exist", name),
+    )
+    .with_detail("table", name)
+}
+
+fn column_index(table: &Table, name: &str) -> Result<usize, ServerError> {
+    table
+        .columns
+        .iter()
+        .position(|c| c.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            ServerError::new(
+                sqlstate::UNDEFINED_COLUMN,
+                format!("Column {} does not exist", name),
+            )
+            .with_detail("column", name)
+        })
+}
+
+fn data_type(name: &str) -> Option<DataType> {
+    Some(match name {
+        "INT" | "INTEGER" | "BIGINT" | "SMALLINT" => DataType::Int,
+        "FLOAT" | "DOUBLE" | "REAL" | "DECIMAL" | "NUMERIC" => DataType::Float,
+        "TEXT" | "VARCHAR" | "CHAR" | "STRING" => DataType::String,
+        "BOOL" | "BOOLEAN" => DataType::Bool,
+        "BYTES" | "BLOB" | "BYTEA" => DataType::Bytes,
+        "TIMESTAMP" | "DATETIME" => DataType::Timestamp,
+        _ => return None,
+    })
+}
+
+/// Compares two values, returning `None` if either is null or they are not
+/// comparable
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            a.as_float()?.partial_cmp(&b.as_float()?)
+        }
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Bytes(a), Value::Bytes(b)) => Some(a.cmp(b)),
+        (Value::Timestamp(a), Value::Timestamp(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// Orders values for `ORDER BY`, with nulls first
+fn sort_order(a: &Value, b: &Value) -> Ordering {
+    match (a.is_null(), b.is_null()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => compare(a, b).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Operand of a predicate
+#[derive(Debug)]
+enum Operand {
+    Column(usize),
+    Value(Value),
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, row: &'a [Value]) -> &'a Value {
+        match self {
+            Operand::Column(i) => &row[*i],
+            Operand::Value(value) => value,
+        }
+    }
+}
+
+/// Parsed `WHERE` condition
+#[derive(Debug)]
+enum Condition {
+    Compare(Operand, &'static str, Operand),
+    /// Row-value comparison such as `(a, b) > (?, ?)`, compared lexicographically
+    CompareRows(Vec<Operand>, &'static str, Vec<Operand>),
+    IsNull(Operand, bool),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    fn matches(&self, row: &[Value]) -> bool {
+        match self {
+            Condition::Compare(left, op, right) => compare(left.resolve(row), right.resolve(row))
+                .is_some_and(|ordering| holds(op, ordering)),
+            Condition::CompareRows(left, op, right) => {
+                let mut ordering = Some(std::cmp::Ordering::Equal);
+                for (left, right) in left.iter().zip(right) {
+                    ordering = compare(left.resolve(row), right.resolve(row));
+                    if ordering != Some(std::cmp::Ordering::Equal) {
+                        break;
+                    }
+                }
+                ordering.is_some_and(|ordering| holds(op, ordering))
+            }
+            Condition::IsNull(operand, negated) => operand.resolve(row).is_null() != *negated,
+            Condition::And(a, b) => a.matches(row) && b.matches(row),
+            Condition::Or(a, b) => a.matches(row) || b.matches(row),
+        }
+    }
+}
+
+/// Applies a comparison operator to an ordering
+fn holds(op: &str, ordering: std::cmp::Ordering) -> bool {
+    match op {
+        "=" => ordering.is_eq(),
+        "!=" | "<>" => ordering.is_ne(),
+        "<" => ordering.is_lt(),
+        "<=" => ordering.is_le(),
+        ">" => ordering.is_gt(),
+        _ => ordering.is_ge(),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    Word(String),
+    Number(String),
+    Str(String),
+    Param,
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 12] = [
+    "<=", ">=", "!=", "<>", "(", ")", ",", "*", "=", "<", ">", ";",
+];
+
+/// Recursive-descent parser over a tokenized statement
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    params: &'a [Value],
+    next_param: usize,
+    sql_len: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(sql: &str, params: &'a [Value]) -> Result<Self, ServerError> {
+        let mut tokens = Vec::new();
+        let chars: Vec<(usize, char)> = sql.char_indices().collect();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let (position, c) = chars[i];
+            if c.is_whitespace() {
+                i += 1;
+            } else if c.is_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') {
+                    i += 1;
+                }
+                let word = chars[start..i].iter().map(|(_, c)| c).collect();
+                tokens.push((Token::Word(word), position));
+            } else if c.is_ascii_digit()
+                || (c == '-' && chars.get(i + 1).is_some_and(|(_, c)| c.is_ascii_digit()))
+            {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].1.is_ascii_digit() || chars[i].1 == '.') {
+                    i += 1;
+                }
+                let number = chars[start..i].iter().map(|(_, c)| c).collect();
+                tokens.push((Token::Number(number), position));
+            } else if c == '\'' {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        Some((_, '\'')) if chars.get(i + 1).is_some_and(|(_, c)| *c == '\'') => {
+                            text.push('\'');
+                            i += 2;
+                        }
+                        Some((_, '\'')) => {
+                            i += 1;
+                            break;
+                        }
+                        Some((_, c)) => {
+                            text.push(*c);
+                            i += 1;
+                        }
+                        None => {
+                            return Err(ServerError::new(
+                                sqlstate::SYNTAX_ERROR,
+                                "Unterminated string literal",
+                            )
+                            .with_position(position))
+                        }
+                    }
+                }
+                tokens.push((Token::Str(text), position));
+            } else if c == '?' {
+                tokens.push((Token::Param, position));
+                i += 1;
+            } else {
+                let rest = &sql[position..];
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|s| rest.starts_with(*s))
+                    .ok_or_else(|| {
+                        ServerError::new(
+                            sqlstate::SYNTAX_ERROR,
+                            format!("Unexpected character '{}'", c),
+                        )
+                        .with_position(position)
+                    })?;
+                tokens.push((Token::Symbol(symbol), position));
+                i += symbol.len();
+            }
+        }
+
+        Ok(Self {
+            tokens,
+            index: 0,
+            params,
+            next_param: 0,
+            sql_len: sql.len(),
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(token, _)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn error(&self, message: impl Into<String>) -> ServerError {
+        let position = self
+            .tokens
+            .get(self.index)
+            .map_or(self.sql_len, |(_, position)| *position);
+        ServerError::new(sqlstate::SYNTAX_ERROR, message).with_position(position)
+    }
+
+    fn keyword(&mut self) -> Result<String, ServerError> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                let word = word.to_uppercase();
+                self.index += 1;
+                Ok(word)
+            }
+            _ => Err(self.error("Expected keyword")),
+        }
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        let matched =
+            matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword));
+        if matched {
+            self.index += 1;
+        }
+        matched
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ServerError> {
+        if self.accept_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected {}", keyword)))
+        }
+    }
+
+    fn accept_symbol(&mut self, symbol: &str) -> bool {
+        let matched = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if matched {
+            self.index += 1;
+        }
+        matched
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), ServerError> {
+        if self.accept_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected '{}'", symbol)))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, ServerError> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                let word = word.clone();
+                self.index += 1;
+                Ok(word)
+            }
+            _ => Err(self.error("Expected identifier")),
+        }
+    }
+
+    fn identifier_list(&mut self) -> Result<Vec<String>, ServerError> {
+        let mut names = vec![self.identifier()?];
+        while self.accept_symbol(",") {
+            names.push(self.identifier()?);
+        }
+        Ok(names)
+    }
+
+    /// Parses a placeholder or literal
+    fn value(&mut self) -> Result<Value, ServerError> {
+        let value = match self.peek() {
+            Some(Token::Param) => {
+                let value = self.params.get(self.next_param).cloned().ok_or_else(|| {
+                    self.error(format!(
+                        "Missing value for parameter {}",
+                        self.next_param + 1
+                    ))
+                })?;
+                self.next_param += 1;
+                value
+            }
+            Some(Token::Number(number)) => {
+                let parsed = if number.contains('.') {
+                    number.parse().map(Value::Float).ok()
+                } else {
+                    number.parse().map(Value::Int).ok()
+                };
+                parsed.ok_or_else(|| self.error(format!("Invalid number {}", number)))?
+            }
+            Some(Token::Str(text)) => Value::String(text.clone()),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("NULL") => Value::Null,
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("TRUE") => Value::Bool(true),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("FALSE") => Value::Bool(false),
+            _ => return Err(self.error("Expected value")),
+        };
+        self.index += 1;
+        Ok(value)
+    }
+
+    fn count(&mut self) -> Result<usize, ServerError> {
+        match self.value()? {
+            Value::Int(n) if n >= 0 => Ok(n as usize),
+            _ => Err(self.error("Expected a non-negative integer")),
+        }
+    }
+
+    fn operand(&mut self, table: &Table) -> Result<Operand, ServerError> {
+        match self.peek() {
+            Some(Token::Word(word))
+                if !["NULL", "TRUE", "FALSE"]
+                    .iter()
+                    .any(|k| word.eq_ignore_ascii_case(k)) =>
+            {
+                let column = column_index(table, &word.clone())?;
+                self.index += 1;
+                Ok(Operand::Column(column))
+            }
+            _ => self.value().map(Operand::Value),
+        }
+    }
+
+    fn where_clause(&mut self, table: &Table) -> Result<Option<Condition>, ServerError> {
+        if self.accept_keyword("WHERE") {
+            self.or_condition(table).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn or_condition(&mut self, table: &Table) -> Result<Condition, ServerError> {
+        let mut condition = self.and_condition(table)?;
+        while self.accept_keyword("OR") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and_condition(table)?));
+        }
+        Ok(condition)
+    }
+
+    fn and_condition(&mut self, table: &Table) -> Result<Condition, ServerError> {
+        let mut condition = self.predicate(table)?;
+        while self.accept_keyword("AND") {
+            condition = Condition::And(Box::new(condition), Box::new(self.predicate(table)?));
+        }
+        Ok(condition)
+    }
+
+    fn predicate(&mut self, table: &Table) -> Result<Condition, ServerError> {
+        if self.accept_symbol("(") {
+            let (index, next_param) = (self.index, self.next_param);
+            if let Ok(condition) = self.row_comparison(table) {
+                return Ok(condition);
+            }
+            (self.index, self.next_param) = (index, next_param);
+            let condition = self.or_condition(table)?;
+            self.expect_symbol(")")?;
+            return Ok(condition);
+        }
+
+        let left = self.operand(table)?;
+        if self.accept_keyword("IS") {
+            let negated = self.accept_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Condition::IsNull(left, negated));
+        }
+
+        let op = self.comparison_operator()?;
+        Ok(Condition::Compare(left, op, self.operand(table)?))
+    }
+
+    /// Parses the rest of `(a, b) op (x, y)` after the opening parenthesis
+    fn row_comparison(&mut self, table: &Table) -> Result<Condition, ServerError> {
+        let left = self.operand_list(table)?;
+        if left.len() < 2 {
+            return Err(self.error("Expected a row value"));
+        }
+        let op = self.comparison_operator()?;
+        self.expect_symbol("(")?;
+        let right = self.operand_list(table)?;
+        if right.len() != left.len() {
+            return Err(self.error("Row values differ in length"));
+        }
+        Ok(Condition::CompareRows(left, op, right))
+    }
+
+    /// Parses comma-separated operands up to and including `)`
+    fn operand_list(&mut self, table: &Table) -> Result<Vec<Operand>, ServerError> {
+        let mut operands = vec![self.operand(table)?];
+        while self.accept_symbol(",") {
+            operands.push(self.operand(table)?);
+        }
+        self.expect_symbol(")")?;
+        Ok(operands)
+    }
+
+    fn comparison_operator(&mut self) -> Result<&'static str, ServerError> {
+        let op = match self.peek() {
+            Some(Token::Symbol(op)) if ["=", "!=", "<>", "<", "<=", ">", ">="].contains(op) => *op,
+            _ => return Err(self.error("Expected comparison operator")),
+        };
+        self.index += 1;
+        Ok(op)
+    }
+
+    /// Checks that the whole statement was consumed
+    fn finish(&mut self) -> Result<(), ServerError> {
+        self.accept_symbol(";");
+        if self.index < self.tokens.len() {
+            return Err(self.error("Unexpected trailing input"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with_users() -> TableStore {
+        let mut store = TableStore::new();
+        store
+            .execute(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name VARCHAR(64) NOT NULL, age INT)",
+                &[],
+            )
+            .unwrap();
+        store
+            .execute(
+                "INSERT INTO users (id, name, age) VALUES (1, 'alice', 30), (2, 'bob', 25), (3, 'carol', NULL)",
+                &[],
+            )
+            .unwrap();
+        store
+    }
+
+    fn rows(outcome: Outcome) -> Vec<Vec<Value>> {
+        match outcome {
+            Outcome::Rows { rows, .. } => rows,
+            other => panic!("expected rows, got {:?}", other),
+        }
+    }
+
+    fn affected(outcome: Outcome) -> u64 {
+        match outcome {
+            Outcome::Executed(result) => result.rows_affected,
+            other => panic!("expected execute result, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_create_table_columns() {
+        let store = store_with_users();
+        let table = store.table("USERS").unwrap();
+        let columns: Vec<_> = table
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type, c.nullable))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("id", DataType::Int, false),
+                ("name", DataType::String, false),
+                ("age", DataType::Int, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_with_params_order_and_limit() {
+        let mut store = store_with_users();
+        let result = store
+            .execute(
+                "SELECT name FROM users WHERE age >= ? OR age IS NULL ORDER BY name DESC LIMIT 2",
+                &[Value::Int(26)],
+            )
+            .unwrap();
+        assert_eq!(
+            rows(result),
+            [
+                vec![Value::String("carol".to_string())],
+                vec![Value::String("alice".to_string())],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_row_value_comparison() {
+        let mut store = store_with_users();
+        let result = store
+            .execute(
+                "SELECT id FROM users WHERE (name = 'bob' OR (age, id) > (?, ?)) ORDER BY id",
+                &[Value::Int(25), Value::Int(2)],
+            )
+            .unwrap();
+        assert_eq!(rows(result), [vec![Value::Int(1)], vec![Value::Int(2)]]);
+    }
+
+    #[test]
+    fn test_update_and_delete() {
+        let mut store = store_with_users();
+        let updated = store
+            .execute(
+                "UPDATE users SET age = ? WHERE name = ?",
+                &[Value::Int(31), Value::String("alice".to_string())],
+            )
+            .unwrap();
+        assert_eq!(affected(updated), 1);
+
+        let deleted = store
+            .execute("DELETE FROM users WHERE age < 31", &[])
+            .unwrap();
+        assert_eq!(affected(deleted), 1);
+
+        let remaining = store.execute("SELECT id, age FROM users", &[]).unwrap();
+        assert_eq!(
+            rows(remaining),
+            [
+                vec![Value::Int(1), Value::Int(31)],
+                vec![Value::Int(3), Value::Null],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors_use_sqlstate() {
+        let mut store = store_with_users();
+
+        let error = store.execute("SELECT * FROM missing", &[]).unwrap_err();
+        assert_eq!(error.sqlstate, sqlstate::UNDEFINED_TABLE);
+        assert_eq!(error.detail("table"), Some("missing"));
+
+        let error = store.execute("SELECT email FROM users", &[]).unwrap_err();
+        assert_eq!(error.sqlstate, sqlstate::UNDEFINED_COLUMN);
+
+        let error = store.execute("SELEC * FROM users", &[]).unwrap_err();
+        assert_eq!(error.sqlstate, sqlstate::SYNTAX_ERROR);
+
+        let error = store
+            .execute("SELECT * FROM users WHERE id = ?", &[])
+            .unwrap_err();
+        assert_eq!(error.position, Some(31));
+    }
+}
@@ -0,0 +1,411 @@
+//! Signed token module for Q-Distributed-Database Client SDK
+//!
+//! Servers issue tokens as JSON Web Tokens (RFC 7519) in compact form,
+//! signed with Ed25519 (`"alg": "EdDSA"`, RFC 8037). The compact token is
+//! carried in `AuthToken::signature`. A client configured with the server's
+//! public key verifies every token it receives and exposes its claims, and
+//! services such as gateways can verify forwarded tokens with `TokenVerifier`
+//! alone.
+//!
+//! Only `EdDSA` is accepted, so a token cannot downgrade verification by
+//! naming another algorithm in its header.
+
+use crate::auth::AuthToken;
+use crate::error::DatabaseError;
+use crate::types::{Permission, Role, UserId};
+use crate::Result;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL;
+use base64::Engine;
+use chrono::{DateTime, TimeZone, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Signature algorithm named in the token header
+pub const ALGORITHM: &str = "EdDSA";
+
+/// Length in bytes of an Ed25519 public or secret key
+pub const KEY_LENGTH: usize = 32;
+
+/// Token header
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    alg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    typ: Option<String>,
+}
+
+/// Claims of a signed token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenClaims {
+    /// Issuer of the token
+    #[serde(rename = "iss")]
+    pub issuer: String,
+    /// Name of the authenticated user
+    #[serde(rename = "sub")]
+    pub username: String,
+    /// Identifier of the authenticated user
+    #[serde(rename = "uid")]
+    pub user_id: UserId,
+    /// Roles of the user
+    #[serde(default)]
+    pub roles: Vec<Role>,
+    /// Permissions of the user
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
+    /// Intended audience of the token
+    #[serde(rename = "aud", default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
+    /// Issuance time in seconds since the Unix epoch
+    #[serde(rename = "iat")]
+    pub issued_at: i64,
+    /// Expiration time in seconds since the Unix epoch
+    #[serde(rename = "exp")]
+    pub expires_at: i64,
+    /// Unique identifier of the token
+    #[serde(rename = "jti", default, skip_serializing_if = "Option::is_none")]
+    pub token_id: Option<String>,
+}
+
+impl TokenClaims {
+    /// Returns the expiration time
+    pub fn expiration(&self) -> DateTime<Utc> {
+        Utc.timestamp_opt(self.expires_at, 0)
+            .single()
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+/// Verifies signed tokens against a server's public key
+///
+/// Tokens are rejected if their signature does not match, if they have
+/// expired or are not yet valid (allowing `leeway` seconds of clock skew, 30
+/// by default), or if the issuer or audience differ from the configured ones.
+///
+/// # Example
+///
+/// ```ignore
+/// let verifier = TokenVerifier::new(&public_key)?.with_issuer("qdb-cluster");
+/// let claims = verifier.verify(forwarded_token)?;
+/// println!("{} acts with roles {:?}", claims.username, claims.roles);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TokenVerifier {
+    key: VerifyingKey,
+    issuer: Option<String>,
+    audience: Option<String>,
+    leeway_secs: i64,
+}
+
+impl TokenVerifier {
+    /// Creates a verifier for a raw 32-byte Ed25519 public key
+    pub fn new(public_key: &[u8]) -> Result<Self> {
+        let bytes: &[u8; KEY_LENGTH] =
+            public_key
+                .try_into()
+                .map_err(|_| DatabaseError::InvalidToken {
+                    reason: format!(
+                        "public key must be {} bytes, got {}",
+                        KEY_LENGTH,
+                        public_key.len()
+                    ),
+                })?;
+        let key = VerifyingKey::from_bytes(bytes).map_err(|e| DatabaseError::InvalidToken {
+            reason: format!("invalid public key: {}", e),
+        })?;
+
+        Ok(Self {
+            key,
+            issuer: None,
+            audience: None,
+            leeway_secs: 30,
+        })
+    }
+
+    /// Requires tokens to be issued by the given issuer
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Requires tokens to be intended for the given audience
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Sets the accepted clock skew in seconds
+    pub fn with_leeway(mut self, leeway_secs: u64) -> Self {
+        self.leeway_secs = i64::try_from(leeway_secs).unwrap_or(i64::MAX);
+        self
+    }
+
+    /// Verifies a token in compact form and returns its claims
+    pub fn verify(&self, token: &str) -> Result<TokenClaims> {
+        let (signing_input, signature) = token
+            .rsplit_once('.')
+            .ok_or_else(|| invalid("not a compact JWT"))?;
+        let (header, claims) = signing_input
+            .split_once('.')
+            .filter(|(_, claims)| !claims.contains('.'))
+            .ok_or_else(|| invalid("not a compact JWT"))?;
+
+        let header: Header = decode_json(header)?;
+        if header.alg != ALGORITHM {
+            return Err(invalid(format!("unsupported algorithm {:?}", header.alg)));
+        }
+
+        let signature = BASE64URL
+            .decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or_else(|| invalid("malformed signature"))?;
+        self.key
+            .verify_strict(signing_input.as_bytes(), &signature)
+            .map_err(|_| invalid("signature mismatch"))?;
+
+        let claims: TokenClaims = decode_json(claims)?;
+        self.check(&claims)?;
+        Ok(claims)
+    }
+
+    /// Verifies an issued token and checks that it matches its claims
+    pub fn verify_auth_token(&self, token: &AuthToken) -> Result<TokenClaims> {
+        let compact = token
+            .signed_token()
+            .ok_or_else(|| invalid("token is not a signed JWT"))?;
+        let claims = self.verify(compact)?;
+
+        if claims.user_id != token.user_id
+            || claims.roles != token.roles
+            || claims.expires_at != token.expiration.timestamp()
+        {
+            return Err(invalid("claims do not match the issued token"));
+        }
+        Ok(claims)
+    }
+
+    fn check(&self, claims: &TokenClaims) -> Result<()> {
+        let now = Utc::now().timestamp();
+        if claims.expires_at.saturating_add(self.leeway_secs) < now {
+            return Err(invalid(format!("expired at {}", claims.expiration())));
+        }
+        if claims.issued_at.saturating_sub(self.leeway_secs) > now {
+            return Err(invalid("issued in the future"));
+        }
+        if let Some(issuer) = &self.issuer {
+            if &claims.issuer != issuer {
+                return Err(invalid(format!("unexpected issuer {:?}", claims.issuer)));
+            }
+        }
+        if let Some(audience) = &self.audience {
+            if claims.audience.as_ref() != Some(audience) {
+                return Err(invalid(format!(
+                    "unexpected audience {:?}",
+                    claims.audience
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Issues signed tokens, as a server does
+pub struct TokenSigner {
+    key: SigningKey,
+    issuer: String,
+}
+
+impl TokenSigner {
+    /// Creates a signer from a 32-byte Ed25519 secret key
+    pub fn new(secret_key: &[u8; KEY_LENGTH], issuer: impl Into<String>) -> Self {
+        Self {
+            key: SigningKey::from_bytes(secret_key),
+            issuer: issuer.into(),
+        }
+    }
+
+    /// Returns the issuer written into tokens
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Returns the public key that verifies the tokens
+    pub fn public_key(&self) -> [u8; KEY_LENGTH] {
+        self.key.verifying_key().to_bytes()
+    }
+
+    /// Returns a verifier for the tokens, requiring this signer's issuer
+    pub fn verifier(&self) -> TokenVerifier {
+        TokenVerifier {
+            key: self.key.verifying_key(),
+            issuer: Some(self.issuer.clone()),
+            audience: None,
+            leeway_secs: 30,
+        }
+    }
+
+    /// Signs claims into a token in compact form
+    pub fn sign(&self, claims: &TokenClaims) -> Result<String> {
+        let header = Header {
+            alg: ALGORITHM.to_string(),
+            typ: Some("JWT".to_string()),
+        };
+        let signing_input = format!("{}.{}", encode_json(&header)?, encode_json(claims)?);
+        let signature = self.key.sign(signing_input.as_bytes());
+        Ok(format!(
+            "{}.{}",
+            signing_input,
+            BASE64URL.encode(signature.to_bytes())
+        ))
+    }
+}
+
+impl fmt::Debug for TokenSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenSigner")
+            .field("issuer", &self.issuer)
+            .finish_non_exhaustive()
+    }
+}
+
+fn encode_json<T: Serialize>(value: &T) -> Result<String> {
+    let json = serde_json::to_vec(value).map_err(|e| DatabaseError::SerializationError {
+        message: format!("Failed to serialize token: {}", e),
+    })?;
+    Ok(BASE64URL.encode(json))
+}
+
+fn decode_json<T: for<'de> Deserialize<'de>>(part: &str) -> Result<T> {
+    let json = BASE64URL
+        .decode(part)
+        .map_err(|_| invalid("malformed base64url"))?;
+    serde_json::from_slice(&json).map_err(|e| invalid(format!("malformed JSON: {}", e)))
+}
+
+fn invalid(reason: impl Into<String>) -> DatabaseError {
+    DatabaseError::InvalidToken {
+        reason: reason.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer() -> TokenSigner {
+        TokenSigner::new(&[7; KEY_LENGTH], "qdb-test")
+    }
+
+    fn claims(expires_in: i64) -> TokenClaims {
+        let now = Utc::now().timestamp();
+        TokenClaims {
+            issuer: "qdb-test".to_string(),
+            username: "alice".to_string(),
+            user_id: 42,
+            roles: vec![Role::User],
+            permissions: vec![Permission::Read, Permission::Write],
+            audience: None,
+            issued_at: now,
+            expires_at: now + expires_in,
+            token_id: Some("1".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify_round_trip() {
+        let signer = signer();
+        let token = signer.sign(&claims(3600)).unwrap();
+        assert_eq!(token.split('.').count(), 3);
+
+        let verifier = TokenVerifier::new(&signer.public_key())
+            .unwrap()
+            .with_issuer("qdb-test");
+        assert_eq!(verifier.verify(&token).unwrap(), claims_of(&token));
+    }
+
+    fn claims_of(token: &str) -> TokenClaims {
+        decode_json(token.split('.').nth(1).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_rejects_tampered_tokens() {
+        let signer = signer();
+        let verifier = signer.verifier();
+        let token = signer.sign(&claims(3600)).unwrap();
+
+        // Escalated roles with the original signature
+        let mut forged = claims_of(&token);
+        forged.roles = vec![Role::Admin];
+        let parts: Vec<&str> = token.split('.').collect();
+        let tampered = format!(
+            "{}.{}.{}",
+            parts[0],
+            encode_json(&forged).unwrap(),
+            parts[2]
+        );
+        assert!(matches!(
+            verifier.verify(&tampered),
+            Err(DatabaseError::InvalidToken { .. })
+        ));
+
+        // Signed by another key
+        let other = TokenSigner::new(&[8; KEY_LENGTH], "qdb-test");
+        assert!(verifier
+            .verify(&other.sign(&claims(3600)).unwrap())
+            .is_err());
+
+        // Downgraded algorithm
+        let none_header = BASE64URL.encode(br#"{"alg":"none"}"#);
+        let downgraded = format!("{}.{}.", none_header, parts[1]);
+        assert!(verifier.verify(&downgraded).is_err());
+
+        assert!(verifier.verify("not-a-token").is_err());
+    }
+
+    #[test]
+    fn test_rejects_expired_and_mis_issued_tokens() {
+        let signer = signer();
+        let verifier = signer.verifier().with_leeway(0);
+
+        let expired = signer.sign(&claims(-60)).unwrap();
+        assert!(verifier.verify(&expired).is_err());
+        let lenient = signer.verifier().with_leeway(u64::MAX);
+        assert!(lenient.verify(&expired).is_ok());
+        assert!(lenient.verify(&signer.sign(&claims(3600)).unwrap()).is_ok());
+
+        let foreign = TokenSigner::new(&[7; KEY_LENGTH], "someone-else");
+        let mut mis_issued = claims(3600);
+        mis_issued.issuer = foreign.issuer().to_string();
+        assert!(verifier
+            .verify(&foreign.sign(&mis_issued).unwrap())
+            .is_err());
+
+        let audience = signer.verifier().with_audience("gateway");
+        assert!(audience
+            .verify(&signer.sign(&claims(3600)).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_auth_token_checks_claims_match() {
+        let signer = signer();
+        let claims = claims(3600);
+        let compact = signer.sign(&claims).unwrap();
+        let token = AuthToken::new(
+            claims.user_id,
+            claims.roles.clone(),
+            claims.expiration(),
+            compact.clone().into_bytes(),
+        );
+        assert_eq!(signer.verifier().verify_auth_token(&token).unwrap(), claims);
+
+        let mismatched = AuthToken::new(
+            claims.user_id,
+            vec![Role::Admin],
+            claims.expiration(),
+            compact.into_bytes(),
+        );
+        assert!(signer.verifier().verify_auth_token(&mismatched).is_err());
+    }
+}
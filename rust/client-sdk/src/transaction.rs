@@ -6,8 +6,8 @@
 use crate::auth::AuthToken;
 use crate::connection::PooledConnection;
 use crate::error::DatabaseError;
-use crate::protocol::MessageType;
-use crate::result::{ColumnMetadata, QueryResult};
+use crate::protocol::{MessageType, Request, Response};
+use crate::result::QueryResult;
 use crate::types::{TransactionId, Value};
 use crate::Result;
 use serde::{Deserialize, Serialize};
@@ -56,38 +56,13 @@ pub enum TransactionResponse {
     Error { message: String },
 }
 
-/// Execute request with optional transaction context
-#[derive(Debug, Serialize, Deserialize)]
-struct ExecuteRequest {
-    sql: String,
-    params: Vec<Value>,
-    transaction_id: Option<TransactionId>,
-    auth_token: Option<Vec<u8>>,
-}
-
-/// Execute response
-#[derive(Debug, Serialize, Deserialize)]
-struct ExecuteResponse {
-    rows_affected: u64,
-    last_insert_id: Option<i64>,
-    error: Option<String>,
-}
-
-/// Query request with optional transaction context
-#[derive(Debug, Serialize, Deserialize)]
-struct QueryRequest {
-    sql: String,
-    params: Vec<Value>,
-    transaction_id: Option<TransactionId>,
-    auth_token: Option<Vec<u8>>,
-}
-
-/// Query response
-#[derive(Debug, Serialize, Deserialize)]
-struct QueryResponse {
-    columns: Vec<ColumnMetadata>,
-    rows: Vec<Vec<Value>>,
-    error: Option<String>,
+impl TransactionResponse {
+    /// Deserializes a transaction response from a message payload
+    pub fn from_payload(payload: &[u8]) -> Result<Self> {
+        bincode::deserialize(payload).map_err(|e| DatabaseError::SerializationError {
+            message: format!("Failed to deserialize transaction response: {}", e),
+        })
+    }
 }
 
 /// Transaction context for executing operations atomically
@@ -104,6 +79,11 @@ struct QueryResponse {
 /// txn.execute("INSERT INTO users (name) VALUES (?)", &[Value::from("Bob")]).await?;
 /// txn.commit().await?;
 /// ```
+///
+/// Operations use the token the transaction began with and are not replayed
+/// if the server rejects it, since the transaction's server-side state may be
+/// gone. The auth error is returned and the transaction should be retried
+/// from the start.
 pub struct Transaction {
     /// Dedicated connection for this transaction
     connection: PooledConnection,
@@ -160,58 +140,24 @@ impl Transaction {
         self.check_active()?;
 
         // Build execute request with transaction ID
-        let request = ExecuteRequest {
+        let request = Request::Execute {
             sql: sql.to_string(),
             params: params.to_vec(),
+            prepared_statement_id: None,
             transaction_id: Some(self.transaction_id),
             auth_token: Some(self.auth_token.signature.clone()),
         };
 
-        // Serialize request
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize execute request: {}", e),
-            })?;
-
-        // Send request and receive response
-        let response = match self
-            .connection
-            .connection_mut()
-            .send_request(MessageType::Data, payload, 5000)
-            .await
-        {
-            Ok(resp) => resp,
-            Err(e) => {
-                // Automatic rollback on error
-                eprintln!("Transaction operation failed, attempting automatic rollback");
-                let _ = self.rollback_internal().await;
-                return Err(e);
-            }
-        };
-
-        // Parse response
-        let execute_response: ExecuteResponse =
-            bincode::deserialize(&response.payload).map_err(|e| {
-                DatabaseError::SerializationError {
-                    message: format!("Failed to deserialize execute response: {}", e),
-                }
-            })?;
-
-        // Check for errors
-        if let Some(error) = execute_response.error {
-            // Automatic rollback on error
-            eprintln!("Transaction operation returned error, attempting automatic rollback");
-            let _ = self.rollback_internal().await;
-            return Err(DatabaseError::InternalError {
-                component: "Transaction".to_string(),
-                details: error,
-            });
+        match self.send_operation(&request).await? {
+            Response::Executed {
+                rows_affected,
+                last_insert_id,
+            } => Ok(ExecuteResult {
+                rows_affected,
+                last_insert_id,
+            }),
+            other => Err(other.unexpected("Transaction")),
         }
-
-        Ok(ExecuteResult {
-            rows_affected: execute_response.rows_affected,
-            last_insert_id: execute_response.last_insert_id,
-        })
     }
 
     /// Executes a query without parameters within the transaction
@@ -224,58 +170,37 @@ impl Transaction {
         self.check_active()?;
 
         // Build query request with transaction ID
-        let request = QueryRequest {
+        let request = Request::Query {
             sql: sql.to_string(),
             params: params.to_vec(),
+            prepared_statement_id: None,
             transaction_id: Some(self.transaction_id),
             auth_token: Some(self.auth_token.signature.clone()),
+            streaming: false,
         };
 
-        // Serialize request
-        let payload =
-            bincode::serialize(&request).map_err(|e| DatabaseError::SerializationError {
-                message: format!("Failed to serialize query request: {}", e),
-            })?;
+        match self.send_operation(&request).await? {
+            Response::Rows { columns, rows } => Ok(QueryResult::from_raw(columns, rows)),
+            other => Err(other.unexpected("Transaction")),
+        }
+    }
 
-        // Send request and receive response
-        let response = match self
+    /// Sends an operation within the transaction, rolling back on failure
+    async fn send_operation(&mut self, request: &Request) -> Result<Response> {
+        match self
             .connection
             .connection_mut()
-            .send_request(MessageType::Data, payload, 5000)
+            .send_data_request(request, 5000)
             .await
         {
-            Ok(resp) => resp,
+            Ok(response) => Ok(response),
             Err(e) => {
                 // Automatic rollback on error
                 eprintln!("Transaction operation failed, attempting automatic rollback");
                 let _ = self.rollback_internal().await;
-                return Err(e);
+                Err(e)
             }
-        };
-
-        // Parse response
-        let query_response: QueryResponse =
-            bincode::deserialize(&response.payload).map_err(|e| {
-                DatabaseError::SerializationError {
-                    message: format!("Failed to deserialize query response: {}", e),
-                }
-            })?;
-
-        // Check for errors
-        if let Some(error) = query_response.error {
-            // Automatic rollback on error
-            eprintln!("Transaction operation returned error, attempting automatic rollback");
-            let _ = self.rollback_internal().await;
-            return Err(DatabaseError::InternalError {
-                component: "Transaction".to_string(),
-                details: error,
-            });
         }
-
-        Ok(QueryResult::from_raw(
-            query_response.columns,
-            query_response.rows,
-        ))
     }
 
     /// Commits the transaction, persisting all changes
@@ -298,15 +223,13 @@ impl Transaction {
             .connection
             .connection_mut()
             .send_request(MessageType::Transaction, payload, 5000)
-            .await;
+            .await
+            .and_then(|resp| resp.check_server_error(None, Some(self.transaction_id)));
 
         match response {
             Ok(resp) => {
                 // Parse response
-                let txn_response: TransactionResponse = bincode::deserialize(&resp.payload)
-                    .map_err(|e| DatabaseError::SerializationError {
-                        message: format!("Failed to deserialize transaction response: {}", e),
-                    })?;
+                let txn_response = TransactionResponse::from_payload(&resp.payload)?;
 
                 match txn_response {
                     TransactionResponse::CommitSuccess => {
@@ -364,15 +287,11 @@ impl Transaction {
             .connection
             .connection_mut()
             .send_request(MessageType::Transaction, payload, 5000)
-            .await?;
+            .await?
+            .check_server_error(None, Some(self.transaction_id))?;
 
         // Parse response
-        let txn_response: TransactionResponse =
-            bincode::deserialize(&response.payload).map_err(|e| {
-                DatabaseError::SerializationError {
-                    message: format!("Failed to deserialize transaction response: {}", e),
-                }
-            })?;
+        let txn_response = TransactionResponse::from_payload(&response.payload)?;
 
         match txn_response {
             TransactionResponse::RollbackSuccess => {
@@ -0,0 +1,27 @@
+//! Transport abstraction for Q-Distributed-Database Client SDK
+//!
+//! A `Connection` reads and writes frames through a boxed `Transport` rather
+//! than a concrete socket. The TCP stream opened by the connection can be
+//! wrapped by a `TransportLayer` configured on `ConnectionConfig`, which is how
+//! the `testing` feature injects network faults underneath the protocol.
+
+use crate::types::NodeId;
+use std::fmt;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// A byte stream that frames are read from and written to
+///
+/// Implemented for every `AsyncRead + AsyncWrite` type, including
+/// `tokio::net::TcpStream` and `tokio::io::DuplexStream`.
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Transport for T {}
+
+/// Wraps the transport of each new connection
+///
+/// Layers are shared by all connections of a `ConnectionConfig` and are
+/// called once per connection, before feature negotiation.
+pub trait TransportLayer: fmt::Debug + Send + Sync {
+    /// Returns the transport used for a new connection to the given node
+    fn wrap(&self, node_id: NodeId, transport: Box<dyn Transport>) -> Box<dyn Transport>;
+}
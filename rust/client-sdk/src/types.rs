@@ -3,8 +3,15 @@
 //! This module defines the fundamental data types used throughout the SDK,
 //! including node identifiers, values, timestamps, and configuration types.
 
+use crate::capture::CaptureSink;
+use crate::credentials::CredentialProvider;
+use crate::secret::SecretString;
+use crate::token::TokenVerifier;
+use crate::transport::TransportLayer;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Unique identifier for a database node
 ///
@@ -17,6 +24,9 @@ pub type TransactionId = u64;
 /// Unique identifier for a prepared statement
 pub type StatementId = u64;
 
+/// Unique identifier for a server-side result stream
+pub type StreamId = u64;
+
 /// Unique identifier for a user
 pub type UserId = u64;
 
@@ -246,9 +256,12 @@ pub struct ConnectionConfig {
     /// Username for authentication
     pub username: String,
     /// Password for authentication (optional if using certificate auth)
-    pub password: Option<String>,
+    pub password: Option<SecretString>,
     /// Client certificate for TLS authentication
     pub certificate: Option<Vec<u8>>,
+    /// Source of credentials fetched at every login, used instead of
+    /// `username`, `password` and `certificate` when set
+    pub credential_provider: Option<Arc<dyn CredentialProvider>>,
     /// Enable TLS encryption
     pub enable_tls: bool,
     /// Connection timeout in milliseconds
@@ -261,6 +274,27 @@ pub struct ConnectionConfig {
     pub compression_enabled: bool,
     /// Compression threshold in bytes
     pub compression_threshold: usize,
+    /// Compression algorithm selection and tuning
+    pub compression: CompressionConfig,
+    /// Maximum size in bytes of a message reassembled from fragment frames
+    pub max_reassembled_size: usize,
+    /// Prepared statements kept per connection before the least recently used
+    /// is closed, 0 to run prepared statements as plain SQL
+    pub statement_cache_capacity: usize,
+    /// Per-message authentication and replay protection
+    pub integrity: IntegrityConfig,
+    /// How the password may be sent when authenticating
+    pub password_policy: PasswordPolicy,
+    /// Background renewal of the authentication token
+    pub token_refresh: TokenRefreshConfig,
+    /// Verifies the signed tokens issued by the server (unchecked when `None`)
+    pub token_verifier: Option<TokenVerifier>,
+    /// Check the token's permissions before sending requests that need them
+    pub permission_checks: bool,
+    /// Wire-traffic capture shared by all connections (disabled when `None`)
+    pub capture: Option<CaptureSink>,
+    /// Wraps the transport of every new connection (plain TCP when `None`)
+    pub transport_layer: Option<Arc<dyn TransportLayer>>,
     /// Logging configuration
     pub log_config: Option<LogConfig>,
     /// Distributed tracing configuration
@@ -274,12 +308,23 @@ impl Default for ConnectionConfig {
             username: String::new(),
             password: None,
             certificate: None,
+            credential_provider: None,
             enable_tls: false,
             timeout_ms: 5000,
             pool_config: PoolConfig::default(),
             retry_config: RetryConfig::default(),
             compression_enabled: false,
             compression_threshold: 1024,
+            compression: CompressionConfig::default(),
+            max_reassembled_size: 64 * 1024 * 1024,
+            statement_cache_capacity: 256,
+            integrity: IntegrityConfig::default(),
+            password_policy: PasswordPolicy::default(),
+            token_refresh: TokenRefreshConfig::default(),
+            token_verifier: None,
+            permission_checks: false,
+            capture: None,
+            transport_layer: None,
             log_config: None,
             tracing_config: None,
         }
@@ -306,18 +351,30 @@ impl ConnectionConfig {
             });
         }
 
-        if self.username.is_empty() {
+        if self.credential_provider.is_none() && self.username.is_empty() {
             return Err(DatabaseError::AuthenticationFailed {
                 reason: "Username is required".to_string(),
             });
         }
 
-        if self.password.is_none() && self.certificate.is_none() {
+        if self.credential_provider.is_none()
+            && self.password.is_none()
+            && self.certificate.is_none()
+        {
             return Err(DatabaseError::AuthenticationFailed {
                 reason: "Either password or certificate must be provided".to_string(),
             });
         }
 
+        if !(self.token_refresh.refresh_fraction > 0.0
+            && self.token_refresh.refresh_fraction <= 1.0)
+        {
+            return Err(DatabaseError::InternalError {
+                component: "ConnectionConfig".to_string(),
+                details: "Token refresh fraction must be in (0, 1]".to_string(),
+            });
+        }
+
         Ok(())
     }
 
@@ -331,13 +388,20 @@ impl ConnectionConfig {
     pub fn with_credentials(
         mut self,
         username: impl Into<String>,
-        password: impl Into<String>,
+        password: impl Into<SecretString>,
     ) -> Self {
         self.username = username.into();
         self.password = Some(password.into());
         self
     }
 
+    /// Fetches credentials from the provider at every login instead of using
+    /// fixed credentials
+    pub fn with_credential_provider(mut self, provider: Arc<dyn CredentialProvider>) -> Self {
+        self.credential_provider = Some(provider);
+        self
+    }
+
     /// Enables TLS
     pub fn with_tls(mut self, enabled: bool) -> Self {
         self.enable_tls = enabled;
@@ -369,6 +433,73 @@ impl ConnectionConfig {
         self
     }
 
+    /// Sets the compression algorithm configuration
+    pub fn with_compression_config(mut self, compression: CompressionConfig) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the maximum size of a message reassembled from fragment frames
+    pub fn with_max_reassembled_size(mut self, max_reassembled_size: usize) -> Self {
+        self.max_reassembled_size = max_reassembled_size;
+        self
+    }
+
+    /// Sets the per-message authentication configuration
+    pub fn with_integrity(mut self, integrity: IntegrityConfig) -> Self {
+        self.integrity = integrity;
+        self
+    }
+
+    /// Sets how the password may be sent when authenticating
+    pub fn with_password_policy(mut self, password_policy: PasswordPolicy) -> Self {
+        self.password_policy = password_policy;
+        self
+    }
+
+    /// Sets the background token renewal configuration
+    pub fn with_token_refresh(mut self, token_refresh: TokenRefreshConfig) -> Self {
+        self.token_refresh = token_refresh;
+        self
+    }
+
+    /// Verifies every token issued by the server with the given verifier
+    pub fn with_token_verifier(mut self, token_verifier: TokenVerifier) -> Self {
+        self.token_verifier = Some(token_verifier);
+        self
+    }
+
+    /// Sets how many prepared statements each connection keeps
+    ///
+    /// Preparing one more statement closes the least recently used one on
+    /// that connection. A capacity of 0 disables the cache, and prepared
+    /// statements are then sent as plain SQL.
+    pub fn with_statement_cache_capacity(mut self, capacity: usize) -> Self {
+        self.statement_cache_capacity = capacity;
+        self
+    }
+
+    /// Enables or disables client-side permission checks
+    ///
+    /// With checks enabled, requests the token's permissions do not allow
+    /// fail with `InsufficientPermissions` without reaching the server.
+    pub fn with_permission_checks(mut self, enabled: bool) -> Self {
+        self.permission_checks = enabled;
+        self
+    }
+
+    /// Records every message sent and received to the given capture
+    pub fn with_capture(mut self, capture: CaptureSink) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Sets the layer wrapping the transport of every new connection
+    pub fn with_transport_layer(mut self, layer: Arc<dyn TransportLayer>) -> Self {
+        self.transport_layer = Some(layer);
+        self
+    }
+
     /// Sets the logging configuration
     pub fn with_logging(mut self, log_config: LogConfig) -> Self {
         self.log_config = Some(log_config);
@@ -499,6 +630,43 @@ pub enum Role {
     ReadOnly,
 }
 
+impl Role {
+    /// Returns the permissions the role grants
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            Role::Admin => &[
+                Permission::Read,
+                Permission::Write,
+                Permission::Delete,
+                Permission::CreateTable,
+                Permission::DropTable,
+                Permission::ManageUsers,
+                Permission::ManageCluster,
+            ],
+            Role::User => &[
+                Permission::Read,
+                Permission::Write,
+                Permission::Delete,
+                Permission::CreateTable,
+                Permission::DropTable,
+            ],
+            Role::ReadOnly => &[Permission::Read],
+        }
+    }
+
+    /// Returns the permissions granted by any of the roles, without
+    /// duplicates
+    pub fn union(roles: &[Role]) -> Vec<Permission> {
+        let mut permissions = Vec::new();
+        for permission in roles.iter().flat_map(Role::permissions) {
+            if !permissions.contains(permission) {
+                permissions.push(permission.clone());
+            }
+        }
+        permissions
+    }
+}
+
 /// User permission
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Permission {
@@ -636,7 +804,7 @@ pub struct UserInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserUpdate {
     /// New password (optional)
-    pub password: Option<String>,
+    pub password: Option<SecretString>,
     /// New roles (optional)
     pub roles: Option<Vec<Role>>,
 }
@@ -644,19 +812,412 @@ pub struct UserUpdate {
 /// Feature enumeration for protocol feature negotiation
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Feature {
-    /// Message compression support
+    /// Message compression support (LZ4)
     Compression,
     /// Heartbeat support
     Heartbeat,
     /// Streaming support
     Streaming,
+    /// Zstandard message compression support
+    ZstdCompression,
+    /// Snappy message compression support
+    SnappyCompression,
+    /// Versioned frame header and message envelope
+    VersionedFraming,
+    /// Splitting large messages into fragment frames
+    FragmentedFrames,
+    /// Per-message authentication tags with replay protection
+    MessageAuthentication,
+    /// SCRAM-SHA-256 challenge-response password authentication
+    ScramSha256,
+}
+
+impl Feature {
+    /// Every feature, in id order
+    const ALL: [Feature; 9] = [
+        Feature::Compression,
+        Feature::Heartbeat,
+        Feature::Streaming,
+        Feature::ZstdCompression,
+        Feature::SnappyCompression,
+        Feature::VersionedFraming,
+        Feature::FragmentedFrames,
+        Feature::MessageAuthentication,
+        Feature::ScramSha256,
+    ];
+
+    /// Returns the feature id used during negotiation
+    ///
+    /// Ids equal the bincode variant index the features were first sent as,
+    /// so replies from servers predating ids still decode. New features must
+    /// take new ids.
+    pub fn id(&self) -> u32 {
+        match self {
+            Feature::Compression => 0,
+            Feature::Heartbeat => 1,
+            Feature::Streaming => 2,
+            Feature::ZstdCompression => 3,
+            Feature::SnappyCompression => 4,
+            Feature::VersionedFraming => 5,
+            Feature::FragmentedFrames => 6,
+            Feature::MessageAuthentication => 7,
+            Feature::ScramSha256 => 8,
+        }
+    }
+
+    /// Looks up a feature by its negotiation id
+    pub fn from_id(id: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|feature| feature.id() == id)
+    }
+}
+
+/// Compression algorithm applied to an encoded frame
+///
+/// The discriminant is the algorithm id written into every frame header, so
+/// existing ids must never be renumbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CompressionAlgorithm {
+    /// Uncompressed frame
+    None = 0,
+    /// LZ4 block compression (fast, moderate ratio)
+    Lz4 = 1,
+    /// Zstandard compression (slower, high ratio)
+    Zstd = 2,
+    /// Snappy raw compression (fast, moderate ratio)
+    Snappy = 3,
+}
+
+impl CompressionAlgorithm {
+    /// Returns the algorithm id used on the wire
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Looks up an algorithm by its wire id
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(CompressionAlgorithm::None),
+            1 => Some(CompressionAlgorithm::Lz4),
+            2 => Some(CompressionAlgorithm::Zstd),
+            3 => Some(CompressionAlgorithm::Snappy),
+            _ => None,
+        }
+    }
+
+    /// Returns the negotiation feature that advertises this algorithm
+    ///
+    /// Returns None for `CompressionAlgorithm::None`, which is always supported.
+    pub fn feature(&self) -> Option<Feature> {
+        match self {
+            CompressionAlgorithm::None => None,
+            CompressionAlgorithm::Lz4 => Some(Feature::Compression),
+            CompressionAlgorithm::Zstd => Some(Feature::ZstdCompression),
+            CompressionAlgorithm::Snappy => Some(Feature::SnappyCompression),
+        }
+    }
+
+    /// Returns true for algorithms tuned for speed rather than ratio
+    pub fn is_fast(&self) -> bool {
+        matches!(
+            self,
+            CompressionAlgorithm::Lz4 | CompressionAlgorithm::Snappy
+        )
+    }
+}
+
+/// Compression algorithm configuration
+///
+/// Controls which algorithms the client offers during feature negotiation and
+/// how an algorithm is chosen for each message. Messages at or below
+/// `ConnectionConfig::compression_threshold` are never compressed. Messages
+/// above `high_ratio_threshold` use zstd when it was negotiated; everything
+/// else uses the first fast algorithm (LZ4 or Snappy) in preference order.
+#[derive(Debug, Clone)]
+pub struct CompressionConfig {
+    /// Algorithms offered to the server, in order of preference
+    pub algorithms: Vec<CompressionAlgorithm>,
+    /// Zstandard compression level (1-22)
+    pub zstd_level: i32,
+    /// Size in bytes above which the high-ratio algorithm is preferred
+    pub high_ratio_threshold: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            algorithms: vec![CompressionAlgorithm::Lz4],
+            zstd_level: 3,
+            high_ratio_threshold: 64 * 1024,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// Creates a compression configuration offering the given algorithms
+    pub fn new(algorithms: Vec<CompressionAlgorithm>) -> Self {
+        Self {
+            algorithms,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the zstd compression level
+    pub fn with_zstd_level(mut self, level: i32) -> Self {
+        self.zstd_level = level;
+        self
+    }
+
+    /// Sets the size above which the high-ratio algorithm is preferred
+    pub fn with_high_ratio_threshold(mut self, threshold: usize) -> Self {
+        self.high_ratio_threshold = threshold;
+        self
+    }
+
+    /// Returns the negotiation features advertising the configured algorithms
+    pub fn features(&self) -> Vec<Feature> {
+        self.algorithms.iter().filter_map(|a| a.feature()).collect()
+    }
+}
+
+/// Per-message authentication configuration
+///
+/// When enabled, every frame carries an HMAC-SHA256 tag keyed from the session
+/// secret established at authentication. Received frames are rejected if the
+/// tag is missing or wrong, if their timestamp is more than `max_clock_skew_ms`
+/// away from the local clock, or if their sequence number was already seen or
+/// is older than the replay window.
+#[derive(Debug, Clone)]
+pub struct IntegrityConfig {
+    /// Whether frames are authenticated once a session key is available
+    pub enabled: bool,
+    /// Maximum accepted difference between a frame timestamp and the local clock
+    pub max_clock_skew_ms: u64,
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_clock_skew_ms: 30_000,
+        }
+    }
+}
+
+impl IntegrityConfig {
+    /// Creates an enabled integrity configuration
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the maximum accepted clock skew
+    pub fn with_max_clock_skew(mut self, max_clock_skew_ms: u64) -> Self {
+        self.max_clock_skew_ms = max_clock_skew_ms;
+        self
+    }
+}
+
+/// How a password may be sent when authenticating
+///
+/// Passwords are authenticated with SCRAM-SHA-256 whenever the server
+/// negotiates `Feature::ScramSha256`, so the password itself never leaves the
+/// client. The policy decides what happens with servers that do not support it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PasswordPolicy {
+    /// Fail authentication rather than send the password
+    #[default]
+    RequireScram,
+    /// Send the password in the login request if SCRAM-SHA-256 is unavailable
+    AllowPlaintext,
+}
+
+/// Background token renewal configuration
+///
+/// When enabled, the token is renewed once `refresh_fraction` of its lifetime
+/// has passed, so requests never wait for a login at expiry. Failed renewals
+/// are retried with jittered exponential backoff between
+/// `retry_initial_backoff_ms` and `retry_max_backoff_ms`.
+#[derive(Debug, Clone)]
+pub struct TokenRefreshConfig {
+    /// Whether the token is renewed in the background
+    pub enabled: bool,
+    /// Fraction of the token lifetime after which it is renewed
+    pub refresh_fraction: f64,
+    /// Delay before the first retry of a failed renewal
+    pub retry_initial_backoff_ms: u64,
+    /// Maximum delay between retries of a failed renewal
+    pub retry_max_backoff_ms: u64,
+}
+
+impl Default for TokenRefreshConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            refresh_fraction: 0.8,
+            retry_initial_backoff_ms: 1000,
+            retry_max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl TokenRefreshConfig {
+    /// Creates a configuration that only renews tokens once they have expired
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the fraction of the token lifetime after which it is renewed
+    pub fn with_refresh_fraction(mut self, refresh_fraction: f64) -> Self {
+        self.refresh_fraction = refresh_fraction;
+        self
+    }
+
+    /// Sets the backoff bounds for retrying failed renewals
+    pub fn with_retry_backoff(mut self, initial_ms: u64, max_ms: u64) -> Self {
+        self.retry_initial_backoff_ms = initial_ms;
+        self.retry_max_backoff_ms = max_ms;
+        self
+    }
+
+    /// Returns the delay before retry `attempt` (starting at 0) of a failed
+    /// renewal
+    ///
+    /// The delay doubles with each attempt up to the maximum, and a random
+    /// jitter of up to half the delay is subtracted so that clients whose
+    /// renewals failed together do not retry together.
+    pub fn retry_delay(&self, attempt: u32) -> std::time::Duration {
+        let backoff = self
+            .retry_initial_backoff_ms
+            .saturating_mul(1u64 << attempt.min(32))
+            .min(self.retry_max_backoff_ms);
+        let mut random = [0u8; 8];
+        let jitter = match getrandom::getrandom(&mut random) {
+            Ok(()) => u64::from_le_bytes(random) % (backoff / 2 + 1),
+            Err(_) => 0,
+        };
+        std::time::Duration::from_millis(backoff - jitter)
+    }
+}
+
+/// Wire-traffic capture configuration
+///
+/// Captured messages are appended to `path`. When the file would exceed
+/// `max_file_size` it is rotated to `path.1`, older files shift up by one,
+/// and at most `max_files` files are kept.
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    /// Path of the active capture file
+    pub path: PathBuf,
+    /// Size in bytes at which the capture file is rotated
+    pub max_file_size: u64,
+    /// Number of capture files kept, including the active one
+    pub max_files: usize,
+    /// Whether message payloads are dropped from the capture
+    pub redact_payloads: bool,
+}
+
+impl CaptureConfig {
+    /// Creates a capture configuration writing to the given path
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_file_size: 16 * 1024 * 1024,
+            max_files: 5,
+            redact_payloads: false,
+        }
+    }
+
+    /// Sets the size at which the capture file is rotated
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Sets the number of capture files kept
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Sets whether message payloads are dropped from the capture
+    pub fn with_redacted_payloads(mut self, redact: bool) -> Self {
+        self.redact_payloads = redact;
+        self
+    }
 }
 
 /// Feature negotiation request/response
+///
+/// Features are sent as their numeric ids; ids this client does not know
+/// are skipped when decoding, so either side may add features. The nonce
+/// keys message authentication to the connection; peers predating it omit
+/// it, which `from_payload` decodes as an empty nonce.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeatureNegotiation {
     /// List of supported features
+    #[serde(with = "feature_ids")]
     pub supported_features: Vec<Feature>,
+    /// Random bytes chosen by the sender for this connection
+    pub nonce: Vec<u8>,
+}
+
+/// Feature negotiation as sent by peers predating the nonce
+#[derive(Deserialize)]
+struct LegacyFeatureNegotiation {
+    #[serde(with = "feature_ids")]
+    supported_features: Vec<Feature>,
+}
+
+impl FeatureNegotiation {
+    /// Serializes the negotiation into a message payload
+    pub fn to_payload(&self) -> Result<Vec<u8>, crate::error::DatabaseError> {
+        bincode::serialize(self).map_err(|e| crate::error::DatabaseError::SerializationError {
+            message: format!("Failed to serialize feature negotiation: {}", e),
+        })
+    }
+
+    /// Deserializes a negotiation from a message payload
+    pub fn from_payload(payload: &[u8]) -> Result<Self, crate::error::DatabaseError> {
+        bincode::deserialize(payload)
+            .or_else(|_| {
+                bincode::deserialize::<LegacyFeatureNegotiation>(payload).map(|legacy| Self {
+                    supported_features: legacy.supported_features,
+                    nonce: Vec::new(),
+                })
+            })
+            .map_err(|e| crate::error::DatabaseError::SerializationError {
+                message: format!("Failed to deserialize feature negotiation: {}", e),
+            })
+    }
+}
+
+/// Serializes features as their negotiation ids
+mod feature_ids {
+    use super::Feature;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        features: &[Feature],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        features
+            .iter()
+            .map(Feature::id)
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Feature>, D::Error> {
+        let ids = Vec::<u32>::deserialize(deserializer)?;
+        Ok(ids.into_iter().filter_map(Feature::from_id).collect())
+    }
 }
 
 // ============================================================================
@@ -953,7 +1514,10 @@ mod tests {
         assert_eq!(config.hosts[0], "node1:7000");
         assert_eq!(config.hosts[1], "node2:7000");
         assert_eq!(config.username, "admin");
-        assert_eq!(config.password, Some("password".to_string()));
+        assert_eq!(
+            config.password.as_ref().map(SecretString::expose),
+            Some("password")
+        );
         assert_eq!(config.timeout_ms, 10000);
         assert!(config.enable_tls);
         assert!(config.compression_enabled);
@@ -1062,6 +1626,30 @@ mod tests {
         assert_eq!(config.backoff_multiplier, 3.0);
     }
 
+    #[test]
+    fn test_token_refresh_retry_delay_is_jittered_and_capped() {
+        let config = TokenRefreshConfig::default().with_retry_backoff(100, 1000);
+        for attempt in 0..40 {
+            let backoff = (100u64 << attempt.min(10)).min(1000);
+            let delay = config.retry_delay(attempt).as_millis() as u64;
+            assert!(delay <= backoff, "attempt {}: {}ms", attempt, delay);
+            assert!(delay >= backoff / 2, "attempt {}: {}ms", attempt, delay);
+        }
+    }
+
+    #[test]
+    fn test_connection_config_rejects_invalid_refresh_fraction() {
+        let config = ConnectionConfig::default().with_credentials("admin", "password");
+        assert!(config.validate().is_ok());
+
+        for fraction in [0.0, 1.5, f64::NAN] {
+            let config = config
+                .clone()
+                .with_token_refresh(TokenRefreshConfig::default().with_refresh_fraction(fraction));
+            assert!(config.validate().is_err());
+        }
+    }
+
     // Role and Permission Tests
     #[test]
     fn test_role_equality() {
@@ -1078,6 +1666,19 @@ mod tests {
         assert_ne!(Permission::Read, Permission::Write);
     }
 
+    #[test]
+    fn test_role_permissions() {
+        assert!(Role::Admin.permissions().contains(&Permission::ManageUsers));
+        assert!(!Role::User
+            .permissions()
+            .contains(&Permission::ManageCluster));
+        assert_eq!(Role::ReadOnly.permissions(), &[Permission::Read]);
+
+        let union = Role::union(&[Role::ReadOnly, Role::User]);
+        assert_eq!(union.len(), Role::User.permissions().len());
+        assert_eq!(union[0], Permission::Read);
+    }
+
     // NodeHealthStatus Tests
     #[test]
     fn test_node_health_status_equality() {
@@ -1118,6 +1719,7 @@ mod tests {
         let features = vec![Feature::Compression, Feature::Heartbeat];
         let negotiation = FeatureNegotiation {
             supported_features: features.clone(),
+            nonce: Vec::new(),
         };
         assert_eq!(negotiation.supported_features.len(), 2);
         assert!(negotiation
@@ -1126,6 +1728,68 @@ mod tests {
         assert!(negotiation.supported_features.contains(&Feature::Heartbeat));
     }
 
+    #[test]
+    fn test_feature_negotiation_skips_unknown_features() {
+        for feature in Feature::ALL {
+            assert_eq!(Feature::from_id(feature.id()), Some(feature));
+        }
+
+        // A newer peer's unknown feature is ignored
+        let payload = bincode::serialize(&(vec![Feature::Heartbeat.id(), 999], vec![7u8])).unwrap();
+        let negotiation = FeatureNegotiation::from_payload(&payload).unwrap();
+        assert_eq!(negotiation.supported_features, vec![Feature::Heartbeat]);
+        assert_eq!(negotiation.nonce, vec![7]);
+
+        // Replies encoded as the enum itself, before ids and nonces, decode the same
+        let legacy = bincode::serialize(&vec![Feature::Compression, Feature::Streaming]).unwrap();
+        let negotiation = FeatureNegotiation::from_payload(&legacy).unwrap();
+        assert_eq!(
+            negotiation.supported_features,
+            vec![Feature::Compression, Feature::Streaming]
+        );
+        assert!(negotiation.nonce.is_empty());
+    }
+
+    // Compression Tests
+    #[test]
+    fn test_compression_algorithm_ids() {
+        for algorithm in [
+            CompressionAlgorithm::None,
+            CompressionAlgorithm::Lz4,
+            CompressionAlgorithm::Zstd,
+            CompressionAlgorithm::Snappy,
+        ] {
+            assert_eq!(
+                CompressionAlgorithm::from_id(algorithm.id()),
+                Some(algorithm)
+            );
+        }
+
+        // LZ4 keeps the id of the legacy "compressed" flag
+        assert_eq!(CompressionAlgorithm::Lz4.id(), 1);
+        assert_eq!(CompressionAlgorithm::from_id(42), None);
+    }
+
+    #[test]
+    fn test_compression_config_features() {
+        let config = CompressionConfig::new(vec![
+            CompressionAlgorithm::None,
+            CompressionAlgorithm::Zstd,
+            CompressionAlgorithm::Lz4,
+        ]);
+        assert_eq!(
+            config.features(),
+            vec![Feature::ZstdCompression, Feature::Compression]
+        );
+    }
+
+    #[test]
+    fn test_compression_config_defaults() {
+        let config = CompressionConfig::default();
+        assert_eq!(config.algorithms, vec![CompressionAlgorithm::Lz4]);
+        assert_eq!(config.zstd_level, 3);
+    }
+
     #[test]
     fn test_feature_negotiation_intersection() {
         let client_features = vec![Feature::Compression, Feature::Heartbeat];
@@ -0,0 +1,1146 @@
+//! Client tests against the in-process mock server
+//!
+//! These run without a cluster using the `testing` feature.
+
+#![cfg(feature = "testing")]
+
+use futures::StreamExt;
+use q_distributed_db_client::testing::{FaultConfig, FaultDirection, FaultInjector, MockServer};
+use q_distributed_db_client::{
+    AdminResponse, AuthRequest, Client, Credentials, DatabaseError, FromRow, IntegrityConfig,
+    MessageType, OrderDirection, PageCursor, PasswordPolicy, Permission, PoolConfig, QueryBuilder,
+    Request, Response, Role, TokenRefreshConfig, Value,
+};
+use std::sync::Arc;
+
+async fn connect(server: &MockServer) -> Client {
+    Client::connect(server.config())
+        .await
+        .expect("Failed to connect to mock server")
+}
+
+#[tokio::test]
+async fn test_crud_against_table_store() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+
+    client
+        .data()
+        .execute("CREATE TABLE users (id INT, name TEXT)")
+        .await
+        .unwrap();
+    let inserted = client
+        .data()
+        .execute_with_params(
+            "INSERT INTO users VALUES (?, ?)",
+            &[Value::Int(1), Value::String("alice".to_string())],
+        )
+        .await
+        .unwrap();
+    assert_eq!(inserted.rows_affected, 1);
+
+    let result = client
+        .data()
+        .query_with_params("SELECT name FROM users WHERE id = ?", &[Value::Int(1)])
+        .await
+        .unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(
+        result.iter().next().unwrap().get_string(0).unwrap(),
+        "alice"
+    );
+
+    let sql: Vec<_> = server
+        .requests()
+        .iter()
+        .filter_map(|r| r.sql().map(str::to_string))
+        .collect();
+    assert_eq!(sql.len(), 3);
+    assert_eq!(sql[2], "SELECT name FROM users WHERE id = ?");
+}
+
+#[tokio::test]
+async fn test_transaction_commit_and_rollback() {
+    let server = MockServer::start().await.unwrap();
+    server
+        .store(|store| store.execute("CREATE TABLE items (id INT)", &[]))
+        .unwrap();
+    let client = connect(&server).await;
+
+    let mut txn = client.data().begin_transaction().await.unwrap();
+    txn.execute("INSERT INTO items VALUES (1)").await.unwrap();
+    txn.rollback().await.unwrap();
+    assert!(server.store(|store| store.table("items").unwrap().rows.is_empty()));
+
+    let mut txn = client.data().begin_transaction().await.unwrap();
+    txn.execute("INSERT INTO items VALUES (2)").await.unwrap();
+    txn.commit().await.unwrap();
+    assert_eq!(
+        server.store(|store| store.table("items").unwrap().rows.clone()),
+        [vec![Value::Int(2)]]
+    );
+    assert_eq!(server.transaction_requests().len(), 4);
+}
+
+#[tokio::test]
+async fn test_streaming_query() {
+    let server = MockServer::start().await.unwrap();
+    server
+        .store(|store| {
+            store.execute("CREATE TABLE numbers (n INT)", &[])?;
+            store.execute("INSERT INTO numbers VALUES (1), (2), (3)", &[])
+        })
+        .unwrap();
+    let client = connect(&server).await;
+
+    let mut stream = client
+        .data()
+        .query_stream("SELECT n FROM numbers ORDER BY n DESC")
+        .await
+        .unwrap();
+    stream.set_fetch_size(2);
+
+    let mut values = Vec::new();
+    while let Some(row) = stream.next().await {
+        values.push(row.unwrap().get_i64(0).unwrap());
+    }
+    assert_eq!(values, [3, 2, 1]);
+}
+
+#[tokio::test]
+async fn test_stream_read_ahead_is_bounded() {
+    let server = MockServer::start().await.unwrap();
+    server
+        .store(|store| {
+            store.execute("CREATE TABLE numbers (n INT)", &[])?;
+            store.execute(
+                "INSERT INTO numbers VALUES (0), (1), (2), (3), (4), (5), (6), (7), (8), (9)",
+                &[],
+            )
+        })
+        .unwrap();
+    let client = connect(&server).await;
+    let fetches = |server: &MockServer| {
+        server
+            .requests()
+            .iter()
+            .filter(|r| matches!(r, Request::Fetch { .. }))
+            .count()
+    };
+
+    let mut stream = client
+        .data()
+        .query_stream_with_params("SELECT n FROM numbers WHERE n = ?", &[Value::Int(4)])
+        .await
+        .unwrap();
+    assert_eq!(stream.columns()[0].name, "n");
+    let rows: Vec<_> = (&mut stream).collect().await;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].as_ref().unwrap().get_i64(0).unwrap(), 4);
+    server.clear_received();
+
+    let mut stream = client
+        .data()
+        .query_stream("SELECT n FROM numbers")
+        .await
+        .unwrap();
+    stream.set_fetch_size(2);
+    stream.set_read_ahead(1);
+    assert_eq!(fetches(&server), 0);
+
+    stream.next().await.unwrap().unwrap();
+    assert!(stream.buffered_rows() <= 3);
+
+    // Nothing more is fetched while the consumer is not reading
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    let fetched = fetches(&server);
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert_eq!(fetches(&server), fetched);
+    assert!(fetched <= 2);
+
+    let mut count = 1;
+    while let Some(row) = stream.next().await {
+        row.unwrap();
+        count += 1;
+        assert!(stream.buffered_rows() <= 4);
+    }
+    assert_eq!(count, 10);
+}
+
+#[tokio::test]
+async fn test_abandoned_streams_release_their_connection() {
+    let server = MockServer::start().await.unwrap();
+    server
+        .store(|store| {
+            store.execute("CREATE TABLE numbers (n INT)", &[])?;
+            store.execute(
+                "INSERT INTO numbers VALUES (0), (1), (2), (3), (4), (5), (6), (7), (8), (9)",
+                &[],
+            )
+        })
+        .unwrap();
+    let faults = FaultInjector::new(7);
+    let client = Client::connect(
+        server
+            .config()
+            .with_pool_config(PoolConfig {
+                max_connections: 1,
+                ..Default::default()
+            })
+            .with_transport_layer(Arc::new(faults.clone())),
+    )
+    .await
+    .unwrap();
+    let open = || async {
+        let mut stream = client
+            .data()
+            .query_stream("SELECT n FROM numbers")
+            .await
+            .unwrap();
+        stream.set_fetch_size(2);
+        stream
+    };
+
+    // Leaving the loop early drops the stream
+    let mut stream = open().await;
+    while let Some(row) = stream.next().await {
+        if row.unwrap().get_i64(0).unwrap() == 1 {
+            break;
+        }
+    }
+    drop(stream);
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(server
+        .requests()
+        .iter()
+        .any(|r| matches!(r, Request::Cancel { .. })));
+    client.data().query("SELECT n FROM numbers").await.unwrap();
+
+    let mut stream = open().await;
+    stream.next().await.unwrap().unwrap();
+    stream.cancel().await.unwrap();
+    client.data().query("SELECT n FROM numbers").await.unwrap();
+
+    // A cancel that is never answered discards the connection
+    let mut stream = open().await;
+    stream.set_drain_timeout_ms(100);
+    stream.next().await.unwrap().unwrap();
+    faults.set_default(Some(
+        FaultConfig::new()
+            .with_drop_rate(1.0)
+            .with_direction(FaultDirection::Incoming),
+    ));
+    assert!(stream.cancel().await.is_err());
+    faults.set_default(None);
+    client.data().query("SELECT n FROM numbers").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stream_fetches_use_the_configured_timeout() {
+    let server = MockServer::start().await.unwrap();
+    server
+        .store(|store| {
+            store.execute("CREATE TABLE numbers (n INT)", &[])?;
+            store.execute("INSERT INTO numbers VALUES (1), (2), (3)", &[])
+        })
+        .unwrap();
+    let faults = FaultInjector::new(3);
+    let client = Client::connect(
+        server
+            .config()
+            .with_timeout(200)
+            .with_transport_layer(Arc::new(faults.clone())),
+    )
+    .await
+    .unwrap();
+
+    let mut stream = client
+        .data()
+        .query_stream("SELECT n FROM numbers")
+        .await
+        .unwrap();
+    faults.set_default(Some(
+        FaultConfig::new()
+            .with_drop_rate(1.0)
+            .with_direction(FaultDirection::Incoming),
+    ));
+    let error = stream.next().await.unwrap().unwrap_err();
+    assert!(matches!(
+        error,
+        DatabaseError::TimeoutError {
+            timeout_ms: 200,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn test_stream_with_unexpected_response_discards_its_connection() {
+    let server = MockServer::start().await.unwrap();
+    server
+        .store(|store| {
+            store.execute("CREATE TABLE numbers (n INT)", &[])?;
+            store.execute("INSERT INTO numbers VALUES (1), (2), (3)", &[])
+        })
+        .unwrap();
+    server.on_request(
+        |request| matches!(request, Request::Fetch { .. }),
+        Response::Closed,
+    );
+    let client = Client::connect(server.config().with_pool_config(PoolConfig {
+        max_connections: 1,
+        ..Default::default()
+    }))
+    .await
+    .unwrap();
+
+    let mut stream = client
+        .data()
+        .query_stream("SELECT n FROM numbers")
+        .await
+        .unwrap();
+    assert!(stream.next().await.unwrap().is_err());
+    drop(stream);
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    server.clear_received();
+
+    // The next request opens a fresh connection rather than reusing one
+    // whose stream may still be open on the server
+    client.data().query("SELECT n FROM numbers").await.unwrap();
+    assert!(server
+        .received()
+        .iter()
+        .any(|m| m.message_type == MessageType::FeatureNegotiation));
+}
+
+#[tokio::test]
+async fn test_pool_remembers_nodes_without_feature_negotiation() {
+    let server = MockServer::start().await.unwrap();
+    server.ignore_feature_negotiation();
+    server
+        .store(|store| store.execute("CREATE TABLE numbers (n INT)", &[]))
+        .unwrap();
+    let config = server
+        .config()
+        .with_timeout(200)
+        .with_password_policy(PasswordPolicy::AllowPlaintext);
+    let client = Client::connect(config).await.unwrap();
+
+    // A stream holds one connection, so the query opens a second
+    let stream = client
+        .data()
+        .query_stream("SELECT n FROM numbers")
+        .await
+        .unwrap();
+    let started = std::time::Instant::now();
+    client.data().query("SELECT n FROM numbers").await.unwrap();
+    assert!(started.elapsed() < std::time::Duration::from_millis(200));
+    drop(stream);
+
+    let negotiations = server
+        .received()
+        .iter()
+        .filter(|m| m.message_type == MessageType::FeatureNegotiation)
+        .count();
+    assert_eq!(negotiations, 1);
+}
+
+#[tokio::test]
+async fn test_scripted_responses_and_errors() {
+    let server = MockServer::start().await.unwrap();
+    server.on_request(
+        |request| matches!(request, Request::Execute { sql, .. } if sql.starts_with("DELETE")),
+        Response::Executed {
+            rows_affected: 42,
+            last_insert_id: None,
+        },
+    );
+    let client = connect(&server).await;
+
+    let deleted = client.data().execute("DELETE FROM anything").await.unwrap();
+    assert_eq!(deleted.rows_affected, 42);
+
+    let error = client
+        .data()
+        .query("SELECT * FROM missing")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, DatabaseError::TableNotFound { .. }));
+}
+
+#[tokio::test]
+async fn test_authentication_against_server() {
+    let server = MockServer::start().await.unwrap();
+
+    let error = Client::connect(
+        server
+            .config()
+            .with_credentials(MockServer::USERNAME, "wrong"),
+    )
+    .await
+    .err()
+    .unwrap();
+    assert!(matches!(error, DatabaseError::InvalidCredentials));
+    assert!(server.active_tokens().is_empty());
+
+    let client = connect(&server).await;
+    assert_eq!(server.active_tokens().len(), 1);
+
+    client.disconnect().await.unwrap();
+    assert!(server.active_tokens().is_empty());
+}
+
+#[tokio::test]
+async fn test_verified_token_can_be_forwarded() {
+    let server = MockServer::start().await.unwrap();
+    let client = Client::connect(server.config().with_token_verifier(server.token_verifier()))
+        .await
+        .unwrap();
+
+    let token = client.auth_token().await.unwrap();
+    assert_eq!(
+        token.claims.as_ref().unwrap().username,
+        MockServer::USERNAME
+    );
+
+    // A gateway holding only the verifier accepts the forwarded token
+    let claims = server
+        .token_verifier()
+        .verify(token.signed_token().unwrap())
+        .unwrap();
+    assert_eq!(Some(&claims), token.claims.as_ref());
+}
+
+#[tokio::test]
+async fn test_revoked_token_is_replaced_and_request_replayed() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+    client
+        .data()
+        .execute("CREATE TABLE users (id INT, name TEXT)")
+        .await
+        .unwrap();
+    let mut txn = client.data().begin_transaction().await.unwrap();
+
+    server.revoke_tokens();
+    client
+        .data()
+        .execute("INSERT INTO users VALUES (1, 'alice')")
+        .await
+        .unwrap();
+    assert_eq!(server.active_tokens().len(), 1);
+
+    server.revoke_tokens();
+    let mut batch = client.data().batch().await.unwrap();
+    batch.add_execute("INSERT INTO users VALUES (2, 'bob')", &[]);
+    batch.execute().await.unwrap();
+
+    server.revoke_tokens();
+    let result = client.data().query("SELECT * FROM users").await.unwrap();
+    assert_eq!(result.len(), 2);
+
+    // The transaction's server-side state may be gone, so it is not replayed
+    let error = txn
+        .execute("INSERT INTO users VALUES (3, 'carol')")
+        .await
+        .unwrap_err();
+    assert!(error.is_auth_error(), "{:?}", error);
+}
+
+#[tokio::test]
+async fn test_rejected_token_is_replaced_with_a_single_connection_pool() {
+    let server = MockServer::start().await.unwrap();
+    let client = Client::connect(server.config().with_pool_config(PoolConfig {
+        max_connections: 1,
+        ..Default::default()
+    }))
+    .await
+    .unwrap();
+    client
+        .data()
+        .execute("CREATE TABLE users (id INT, name TEXT)")
+        .await
+        .unwrap();
+
+    // The login happens over the connection already held by the request
+    server.revoke_tokens();
+    client
+        .data()
+        .execute("INSERT INTO users VALUES (1, 'alice')")
+        .await
+        .unwrap();
+    assert_eq!(server.active_tokens().len(), 1);
+
+    server.revoke_tokens();
+    let result = client.data().query("SELECT * FROM users").await.unwrap();
+    assert_eq!(result.len(), 1);
+}
+
+#[tokio::test]
+async fn test_as_user_attaches_the_users_token() {
+    let server = MockServer::start().await.unwrap();
+    server.add_user("alice", "alice-password", vec![Role::ReadOnly]);
+    let client = connect(&server).await;
+    client
+        .data()
+        .execute("CREATE TABLE users (id INT, name TEXT)")
+        .await
+        .unwrap();
+
+    let alice = client
+        .as_user(Credentials::new("alice", "alice-password"))
+        .await
+        .unwrap();
+    server.clear_received();
+    alice.data().query("SELECT * FROM users").await.unwrap();
+
+    let token = alice.auth_token().await.unwrap();
+    let sent = server.requests();
+    assert_eq!(sent[0].auth_token(), Some(token.signature.expose()));
+    let claims = server
+        .token_verifier()
+        .verify(token.signed_token().unwrap())
+        .unwrap();
+    assert_eq!(claims.username, "alice");
+    assert_ne!(
+        token.signature,
+        client.auth_token().await.unwrap().signature
+    );
+
+    // A forwarded token is used as is and cannot be replaced once revoked
+    let forwarded = client.as_user(token.clone()).await.unwrap();
+    server.clear_received();
+    forwarded.data().query("SELECT * FROM users").await.unwrap();
+    assert_eq!(
+        server.requests()[0].auth_token(),
+        Some(token.signature.expose())
+    );
+
+    alice.logout().await.unwrap();
+    let error = forwarded
+        .data()
+        .query("SELECT * FROM users")
+        .await
+        .unwrap_err();
+    assert!(error.is_auth_error(), "{:?}", error);
+    client.data().query("SELECT * FROM users").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_user_views_refresh_their_token_before_it_expires() {
+    let server = MockServer::start().await.unwrap();
+    server.add_user("alice", "alice-password", vec![Role::ReadOnly]);
+    server
+        .store(|store| store.execute("CREATE TABLE numbers (n INT)", &[]))
+        .unwrap();
+    let client = Client::connect(
+        server
+            .config()
+            .with_token_refresh(TokenRefreshConfig::default().with_refresh_fraction(0.5)),
+    )
+    .await
+    .unwrap();
+
+    // A forwarded token close to expiry, with no credentials to log in again
+    let mut token = client
+        .as_user(Credentials::new("alice", "alice-password"))
+        .await
+        .unwrap()
+        .auth_token()
+        .await
+        .unwrap();
+    token.expiration = chrono::Utc::now() + chrono::Duration::milliseconds(1000);
+    let forwarded = client.as_user(token.clone()).await.unwrap();
+
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    forwarded
+        .data()
+        .query("SELECT n FROM numbers")
+        .await
+        .unwrap();
+    let refreshed = forwarded.auth_token().await.unwrap();
+    assert_ne!(refreshed.signature, token.signature);
+    assert!(server
+        .auth_requests()
+        .iter()
+        .any(|request| matches!(request, AuthRequest::Refresh { .. })));
+}
+
+#[tokio::test]
+async fn test_user_views_leave_the_pool_session_to_the_client() {
+    let server = MockServer::start().await.unwrap();
+    server.add_user("alice", "alice-password", vec![Role::ReadOnly]);
+    server
+        .store(|store| store.execute("CREATE TABLE numbers (n INT)", &[]))
+        .unwrap();
+    let client = Client::connect(
+        server
+            .config()
+            .with_integrity(IntegrityConfig::new())
+            .with_pool_config(PoolConfig {
+                max_connections: 1,
+                ..Default::default()
+            }),
+    )
+    .await
+    .unwrap();
+    let bound_to = |server: &MockServer| {
+        server
+            .auth_requests()
+            .into_iter()
+            .filter_map(|request| match request {
+                AuthRequest::Bind { signature } => Some(signature.expose().to_vec()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let alice = client
+        .as_user(Credentials::new("alice", "alice-password"))
+        .await
+        .unwrap();
+    alice.data().query("SELECT n FROM numbers").await.unwrap();
+    let alice_token = alice.auth_token().await.unwrap();
+    assert!(bound_to(&server).contains(&alice_token.signature.expose().to_vec()));
+
+    // The client's requests never run on alice's session
+    let token = client.auth_token().await.unwrap();
+    server.clear_received();
+    for _ in 0..2 {
+        client.data().query("SELECT n FROM numbers").await.unwrap();
+    }
+    assert_eq!(bound_to(&server), vec![token.signature.expose().to_vec()]);
+
+    // Nor does alice logging out unbind the client's connections
+    alice.logout().await.unwrap();
+    server.clear_received();
+    client.data().query("SELECT n FROM numbers").await.unwrap();
+    assert!(bound_to(&server).is_empty());
+}
+
+#[tokio::test]
+async fn test_admin_requests_run_as_the_impersonated_user() {
+    let server = MockServer::start().await.unwrap();
+    server.add_user("operator", "operator-password", vec![Role::Admin]);
+    server.add_user("alice", "alice-password", vec![Role::ReadOnly]);
+    server.on_request(
+        |request| request.admin_request().is_some(),
+        Response::Admin(AdminResponse::UserList(vec![])),
+    );
+    let client = connect(&server).await;
+
+    let operator = client
+        .as_user(Credentials::new("operator", "operator-password"))
+        .await
+        .unwrap();
+    server.clear_received();
+    operator.admin().list_users().await.unwrap();
+    let token = operator.auth_token().await.unwrap();
+    assert_eq!(
+        server.requests()[0].auth_token(),
+        Some(token.signature.expose())
+    );
+
+    // The server rejects the request as alice, not as the pool's user
+    let alice = client
+        .as_user(Credentials::new("alice", "alice-password"))
+        .await
+        .unwrap();
+    server.clear_received();
+    let error = alice.admin().list_users().await.unwrap_err();
+    assert!(
+        matches!(error, DatabaseError::InsufficientPermissions { ref required } if required == "ManageUsers"),
+        "{:?}",
+        error
+    );
+    let token = alice.auth_token().await.unwrap();
+    assert_eq!(
+        server.requests()[0].auth_token(),
+        Some(token.signature.expose())
+    );
+}
+
+#[tokio::test]
+async fn test_permission_checks_fail_fast() {
+    let server = MockServer::start().await.unwrap();
+    server.store(|store| {
+        store
+            .execute("CREATE TABLE users (id INT, name TEXT)", &[])
+            .unwrap()
+    });
+    server.add_user("reader", "reader-password", vec![Role::ReadOnly]);
+    let client = Client::connect(
+        server
+            .config()
+            .with_credentials("reader", "reader-password")
+            .with_permission_checks(true),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        client.effective_permissions().await.unwrap(),
+        vec![Permission::Read]
+    );
+    server.clear_received();
+
+    client.data().query("SELECT * FROM users").await.unwrap();
+    let error = client
+        .data()
+        .execute("INSERT INTO users VALUES (1, 'alice')")
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, DatabaseError::InsufficientPermissions { ref required } if required == "Write"),
+        "{:?}",
+        error
+    );
+    let error = client
+        .admin()
+        .create_user("bob", "bob-password", &[Role::User])
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        DatabaseError::InsufficientPermissions { .. }
+    ));
+
+    // Only the query reached the server
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn test_prepared_statements_are_cached_per_connection() {
+    let server = MockServer::start().await.unwrap();
+    server.store(|store| {
+        store
+            .execute("CREATE TABLE users (id INT, name TEXT)", &[])
+            .unwrap()
+    });
+    let client = Client::connect(server.config().with_statement_cache_capacity(1))
+        .await
+        .unwrap();
+    server.clear_received();
+    let prepares = |server: &MockServer| {
+        server
+            .requests()
+            .iter()
+            .filter(|r| matches!(r, Request::Prepare { .. }))
+            .count()
+    };
+    let closed = |server: &MockServer| {
+        server
+            .requests()
+            .iter()
+            .filter_map(|r| match r {
+                Request::Close { statement_id } => Some(*statement_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let prepared_id = |server: &MockServer, expected: &str| {
+        server
+            .requests()
+            .iter()
+            .find_map(|r| match r {
+                Request::Execute {
+                    sql,
+                    prepared_statement_id: Some(id),
+                    ..
+                }
+                | Request::Query {
+                    sql,
+                    prepared_statement_id: Some(id),
+                    ..
+                } if sql == expected => Some(*id),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    let insert_sql = "INSERT INTO users VALUES (?, ?)";
+    let insert = client.data().prepare(insert_sql).await.unwrap();
+    assert_eq!(insert.param_count, 2);
+    for id in 1..=2 {
+        client
+            .data()
+            .execute_prepared(&insert, &[Value::Int(id), Value::String("bob".into())])
+            .await
+            .unwrap();
+    }
+    assert_eq!(prepares(&server), 1);
+    let insert_id = prepared_id(&server, insert_sql);
+
+    // A second statement evicts the first from the single-entry cache
+    let select_sql = "SELECT name FROM users WHERE id = ?";
+    let select = client.data().prepare(select_sql).await.unwrap();
+    assert_eq!(closed(&server), vec![insert_id]);
+    let result = client
+        .data()
+        .query_prepared(&select, &[Value::Int(2)])
+        .await
+        .unwrap();
+    assert_eq!(result.iter().next().unwrap().get_string(0).unwrap(), "bob");
+    let select_id = prepared_id(&server, select_sql);
+
+    // The evicted statement is prepared again when next used
+    client
+        .data()
+        .execute_prepared(&insert, &[Value::Int(3), Value::String("carol".into())])
+        .await
+        .unwrap();
+    assert_eq!(prepares(&server), 3);
+    assert_eq!(closed(&server), vec![insert_id, select_id]);
+
+    server.clear_received();
+    client.data().close_prepared(&insert).await.unwrap();
+    assert_eq!(closed(&server).len(), 1);
+    client.data().close_prepared(&insert).await.unwrap();
+    assert_eq!(closed(&server).len(), 1);
+}
+
+#[tokio::test]
+async fn test_statement_forgotten_by_the_server_is_prepared_again() {
+    let server = MockServer::start().await.unwrap();
+    server.store(|store| store.execute("CREATE TABLE users (id INT)", &[]).unwrap());
+    let client = connect(&server).await;
+    let insert = client
+        .data()
+        .prepare("INSERT INTO users VALUES (?)")
+        .await
+        .unwrap();
+    server.forget_statements();
+    server.clear_received();
+
+    let result = client
+        .data()
+        .execute_prepared(&insert, &[Value::Int(1)])
+        .await
+        .unwrap();
+    assert_eq!(result.rows_affected, 1);
+
+    // The rejected request and its retry both name the statement and its SQL
+    let executes: Vec<_> = server
+        .requests()
+        .into_iter()
+        .filter_map(|r| match r {
+            Request::Execute {
+                sql,
+                prepared_statement_id,
+                ..
+            } => Some((sql, prepared_statement_id)),
+            Request::Prepare { .. } => Some((String::new(), None)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(executes.len(), 3);
+    assert_eq!(executes[1], (String::new(), None));
+    for (sql, id) in [&executes[0], &executes[2]] {
+        assert_eq!(sql, "INSERT INTO users VALUES (?)");
+        assert!(id.is_some());
+    }
+    assert_ne!(executes[0].1, executes[2].1);
+    assert_eq!(
+        server.store(|store| store.table("users").unwrap().rows.len()),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_zero_statement_cache_capacity_runs_prepared_statements_as_sql() {
+    let server = MockServer::start().await.unwrap();
+    server.store(|store| store.execute("CREATE TABLE users (id INT)", &[]).unwrap());
+    let client = Client::connect(server.config().with_statement_cache_capacity(0))
+        .await
+        .unwrap();
+    server.clear_received();
+
+    let insert = client
+        .data()
+        .prepare("INSERT INTO users VALUES (?)")
+        .await
+        .unwrap();
+    assert_eq!(insert.param_count, 1);
+    for id in 1..=2 {
+        client
+            .data()
+            .execute_prepared(&insert, &[Value::Int(id)])
+            .await
+            .unwrap();
+    }
+
+    // Preparing only validates the statement, which is closed again at once
+    let requests = server.requests();
+    assert!(matches!(requests[0], Request::Prepare { .. }));
+    assert!(matches!(requests[1], Request::Close { .. }));
+    assert_eq!(requests.len(), 4);
+    assert!(requests[2..].iter().all(|r| matches!(
+        r,
+        Request::Execute { sql, prepared_statement_id: None, .. }
+            if sql == "INSERT INTO users VALUES (?)"
+    )));
+}
+
+#[tokio::test]
+async fn test_keyset_pagination_resumes_from_cursor_tokens() {
+    let server = MockServer::start().await.unwrap();
+    server
+        .store(|store| {
+            store.execute("CREATE TABLE events (id INT, score INT)", &[])?;
+            let rows: Vec<_> = (1..=25).map(|id| format!("({}, {})", id, id % 4)).collect();
+            store.execute(
+                &format!("INSERT INTO events VALUES {}", rows.join(", ")),
+                &[],
+            )
+        })
+        .unwrap();
+    let client = connect(&server).await;
+    let keys = [("score", OrderDirection::Desc), ("id", OrderDirection::Asc)];
+    let query = || QueryBuilder::select(&["id", "score"]).from("events");
+    let ids = |result: &q_distributed_db_client::QueryResult| {
+        result
+            .iter()
+            .map(|row| row.get_i64(0).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let expected = ids(&client
+        .data()
+        .query("SELECT id, score FROM events ORDER BY score DESC, id ASC")
+        .await
+        .unwrap());
+
+    let mut pages = client.data().paginate(query(), &keys, 10).unwrap();
+    let first = pages.next_page().await.unwrap().unwrap();
+    assert_eq!(ids(&first.result), expected[..10]);
+    let token = first.cursor.unwrap().to_token();
+
+    // Rows inserted before the cursor do not shift later pages
+    client
+        .data()
+        .execute("INSERT INTO events VALUES (100, 3)")
+        .await
+        .unwrap();
+
+    let mut resumed = client
+        .data()
+        .paginate(query(), &keys, 10)
+        .unwrap()
+        .resume(PageCursor::from_token(&token).unwrap())
+        .unwrap();
+    let mut rest = Vec::new();
+    while let Some(page) = resumed.next_page().await.unwrap() {
+        assert_eq!(page.cursor.is_none(), page.result.len() < 10);
+        rest.extend(ids(&page.result));
+    }
+    assert_eq!(rest, expected[10..]);
+    assert!(resumed.next_page().await.unwrap().is_none());
+
+    let ascending = client
+        .data()
+        .paginate(query(), &[("id", OrderDirection::Asc)], 10)
+        .unwrap();
+    assert!(ascending
+        .resume(PageCursor::from_token(&token).unwrap())
+        .is_err());
+
+    assert!(client.data().paginate(query(), &keys, 0).is_err());
+    assert!(client.data().paginate(query(), &keys, u64::MAX).is_err());
+}
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Audit {
+    created: i64,
+}
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Account {
+    id: u32,
+    #[row(rename = "name")]
+    login: String,
+    email: Option<String>,
+    #[row(default)]
+    visits: i64,
+    #[row(flatten)]
+    audit: Audit,
+}
+
+#[derive(Debug, PartialEq, FromRow)]
+struct IdAndName(i64, String);
+
+#[tokio::test]
+async fn test_rows_map_to_typed_structs() {
+    let server = MockServer::start().await.unwrap();
+    server
+        .store(|store| {
+            store.execute(
+                "CREATE TABLE accounts (id INT, name TEXT, email TEXT, visits INT, created INT)",
+                &[],
+            )?;
+            store.execute(
+                "INSERT INTO accounts VALUES (1, 'alice', 'a@example.com', 3, 100), \
+                 (2, 'bob', NULL, NULL, 200)",
+                &[],
+            )
+        })
+        .unwrap();
+    let client = connect(&server).await;
+
+    let accounts: Vec<Account> = client
+        .data()
+        .query("SELECT * FROM accounts ORDER BY id")
+        .await
+        .unwrap()
+        .into_typed()
+        .unwrap();
+    assert_eq!(
+        accounts,
+        vec![
+            Account {
+                id: 1,
+                login: "alice".to_string(),
+                email: Some("a@example.com".to_string()),
+                visits: 3,
+                audit: Audit { created: 100 },
+            },
+            Account {
+                id: 2,
+                login: "bob".to_string(),
+                email: None,
+                visits: 0,
+                audit: Audit { created: 200 },
+            },
+        ]
+    );
+
+    // Defaulted columns may be left out of the query
+    let accounts: Vec<Account> = client
+        .data()
+        .query("SELECT id, name, email, created FROM accounts WHERE id = 1")
+        .await
+        .unwrap()
+        .into_typed()
+        .unwrap();
+    assert_eq!(accounts[0].visits, 0);
+
+    let pairs: Vec<IdAndName> = client
+        .data()
+        .query_stream("SELECT id, name FROM accounts ORDER BY id")
+        .await
+        .unwrap()
+        .into_typed()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(
+        pairs,
+        vec![
+            IdAndName(1, "alice".to_string()),
+            IdAndName(2, "bob".to_string())
+        ]
+    );
+
+    // Mapping errors name the column and the target type
+    let error = client
+        .data()
+        .query("SELECT name, id FROM accounts")
+        .await
+        .unwrap()
+        .into_typed::<IdAndName>()
+        .unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            DatabaseError::ColumnMappingError { column, to, .. }
+                if column == "name" && *to == "i64"
+        ),
+        "{:?}",
+        error
+    );
+    assert!(error.to_string().contains("name"));
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Event {
+    id: i64,
+    #[serde(rename = "kind")]
+    event_kind: String,
+    note: Option<String>,
+}
+
+#[tokio::test]
+async fn test_serde_structs_round_trip_through_rows() {
+    let server = MockServer::start().await.unwrap();
+    let client = connect(&server).await;
+    client
+        .data()
+        .execute("CREATE TABLE events (id INT, kind TEXT, note TEXT)")
+        .await
+        .unwrap();
+
+    let events = vec![
+        Event {
+            id: 1,
+            event_kind: "login".to_string(),
+            note: None,
+        },
+        Event {
+            id: 2,
+            event_kind: "logout".to_string(),
+            note: Some("idle".to_string()),
+        },
+    ];
+    let mut insert = QueryBuilder::insert_into("events");
+    for event in &events {
+        insert = insert.values_from(event).unwrap();
+    }
+    let (sql, params) = insert.build().unwrap();
+    assert_eq!(
+        sql,
+        "INSERT INTO events (id, kind, note) VALUES (?, ?, ?), (?, ?, ?)"
+    );
+    let inserted = client
+        .data()
+        .execute_with_params(&sql, &params)
+        .await
+        .unwrap();
+    assert_eq!(inserted.rows_affected, 2);
+
+    let read: Vec<Event> = client
+        .data()
+        .query("SELECT * FROM events ORDER BY id")
+        .await
+        .unwrap()
+        .into_deserialized()
+        .unwrap();
+    assert_eq!(read, events);
+
+    let streamed: Vec<Event> = client
+        .data()
+        .query_stream("SELECT * FROM events ORDER BY id")
+        .await
+        .unwrap()
+        .into_deserialized()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(streamed, events);
+
+    // Tuples read columns by position; errors name the column and type
+    let error = client
+        .data()
+        .query("SELECT kind, id FROM events")
+        .await
+        .unwrap()
+        .into_deserialized::<(i64, String)>()
+        .unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            DatabaseError::ColumnMappingError { column, to, .. }
+                if column == "kind" && *to == "i64"
+        ),
+        "{:?}",
+        error
+    );
+}